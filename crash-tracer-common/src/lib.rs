@@ -1,15 +1,45 @@
 #![no_std]
 
 pub const STACK_DUMP_SIZE: usize = 16384; // 16KB
+/// Stack bytes captured for the non-faulting threads of a crashing process.
+pub const THREAD_STACK_DUMP_SIZE: usize = 4096;
+
+// Indices into the eBPF `CONFIG` array, written by userspace before attaching.
+/// Non-zero: on a crash, also capture every other thread of the process.
+pub const CONFIG_ALL_THREADS: u32 = 0;
+pub const CONFIG_LEN: u32 = 1;
 
-// Let's consider these as crashes
 pub const SIGILL: i32 = 4;
+pub const SIGTRAP: i32 = 5;
 pub const SIGABRT: i32 = 6;
 pub const SIGBUS: i32 = 7;
 pub const SIGFPE: i32 = 8;
+pub const SIGKILL: i32 = 9;
 pub const SIGSEGV: i32 = 11;
+pub const SIGXCPU: i32 = 24;
+pub const SIGXFSZ: i32 = 25;
+pub const SIGSYS: i32 = 31;
+
+/// `/proc/<pid>/stat` gives the start time in clock ticks of `USER_HZ`, 100
+/// on every architecture; events carry it in nanoseconds.
+pub const NS_PER_TICK: u64 = 1_000_000_000 / 100;
+
+/// Highest signal number; the eBPF `CRASH_SIGNALS` array is indexed by signal.
+pub const MAX_SIGNAL: u32 = 64;
 
 pub const ARTIFACT_FILENAME_MAX: usize = 128;
+/// Artifacts tracked open at once per process.
+pub const MAX_OPEN_ARTIFACTS: usize = 4;
+/// Bytes of one stderr write carried per `CrashOutputEvent`.
+pub const CRASH_OUTPUT_CHUNK: usize = 256;
+/// Bytes of the path passed to exec carried per `SchedExecEvent`.
+pub const EXEC_FILENAME_MAX: usize = 256;
+/// Bytes of the argument vector carried per `SchedExecEvent`.
+pub const EXEC_ARGS_MAX: usize = 512;
+/// Bytes of working directory components carried per `SchedExecEvent`.
+pub const EXEC_CWD_MAX: usize = 256;
+/// Bytes of path components carried per `ModuleLoadEvent`.
+pub const MODULE_PATH_MAX: usize = 256;
 
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -23,6 +53,7 @@ pub enum RuntimeKindId {
     Python = 6,
 }
 
+/// An open file descriptor of a process (tgid).
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct FdTrackKey {
@@ -30,14 +61,31 @@ pub struct FdTrackKey {
     pub fd: u32,
 }
 
+/// A runtime artifact (hs_err log, diagnostic report, ...) opened for writing.
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct ArtifactInfo {
+    /// Path as passed to `openat`, possibly relative to the process' cwd.
     pub filename: [u8; ARTIFACT_FILENAME_MAX],
+    /// Length of `filename`, without the trailing NUL.
     pub filename_len: u32,
-    pub _pad: u32,
+    /// Bytes passed to `write` on the descriptor, saturating.
+    pub bytes_written: u32,
+    /// The kernel `struct file` opened, to tell when the descriptor number
+    /// has been reused for another file.
+    pub file: u64,
+}
+
+/// Descriptors of the artifacts a process (tgid) has open, so that userspace
+/// finds them in `TRACKED_FDS` at exit without scanning it.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ArtifactFds {
+    /// -1 for a free slot.
+    pub fds: [i32; MAX_OPEN_ARTIFACTS],
 }
 
+/// An artifact's descriptor was closed; the file is complete and can be read.
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct ArtifactReadyEvent {
@@ -45,6 +93,45 @@ pub struct ArtifactReadyEvent {
     pub filename_len: u32,
     pub boottime: u64,
     pub filename: [u8; ARTIFACT_FILENAME_MAX],
+    pub bytes_written: u32,
+    pub _pad: u32,
+}
+
+/// A file mapped executable by a running process, typically a library
+/// `dlopen` loaded. The fields follow a `/proc/<pid>/maps` line.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ModuleLoadEvent {
+    pub pid: u32,
+    /// `PROT_*` and `MAP_*` flags passed to `mmap`.
+    pub prot: u32,
+    pub boottime: u64,
+    pub start: u64,
+    pub end: u64,
+    /// Offset into the file.
+    pub offset: u64,
+    pub inode: u64,
+    /// Kernel `dev_t` of the file system: major in the top 12 bits, minor in
+    /// the low 20.
+    pub dev: u32,
+    pub flags: u32,
+    /// Components of the path, innermost first, each NUL-terminated.
+    pub path: [u8; MODULE_PATH_MAX],
+    pub path_len: u32,
+    /// Non-zero when `path` goes up to the process' root.
+    pub path_complete: u32,
+}
+
+/// Part of a write to stderr by a process that already took a crash signal,
+/// e.g. the traceback printed by a fatal signal handler. Larger writes are
+/// split across several events.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CrashOutputEvent {
+    pub pid: u32,
+    pub len: u32,
+    pub boottime: u64,
+    pub data: [u8; CRASH_OUTPUT_CHUNK],
 }
 
 // Event type discriminant
@@ -55,6 +142,9 @@ pub enum EventType {
     SignalDeliver = 1,
     SchedExit = 2,
     ArtifactReady = 3,
+    ThreadState = 4,
+    CrashOutput = 5,
+    ModuleLoad = 6,
 }
 
 // Unified event for the ring buffer
@@ -73,6 +163,9 @@ pub union EventPayload {
     pub exit: SchedExitEvent,
     pub signal: SignalDeliverEvent,
     pub artifact: ArtifactReadyEvent,
+    pub thread: ThreadStateEvent,
+    pub output: CrashOutputEvent,
+    pub module_load: ModuleLoadEvent,
 }
 
 impl CrashTracerEvent {
@@ -103,13 +196,56 @@ impl CrashTracerEvent {
             _ => None,
         }
     }
+
+    pub fn as_thread(&self) -> Option<&ThreadStateEvent> {
+        match self.tag {
+            EventType::ThreadState => Some(unsafe { &self.payload.thread }),
+            _ => None,
+        }
+    }
+
+    pub fn as_output(&self) -> Option<&CrashOutputEvent> {
+        match self.tag {
+            EventType::CrashOutput => Some(unsafe { &self.payload.output }),
+            _ => None,
+        }
+    }
+
+    pub fn as_module_load(&self) -> Option<&ModuleLoadEvent> {
+        match self.tag {
+            EventType::ModuleLoad => Some(unsafe { &self.payload.module_load }),
+            _ => None,
+        }
+    }
 }
 
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct SchedExecEvent {
     pub pid: u32,
+    /// `pid` as seen from the process' own pid namespace; equal to it on the
+    /// host.
+    pub ns_pid: u32,
     pub boottime: u64,
+    /// cgroup v2 id of the exec'ing task.
+    pub cgroup_id: u64,
+    /// Inodes of its pid and mount namespaces.
+    pub pid_ns: u32,
+    pub mnt_ns: u32,
+    /// Path passed to exec, possibly relative to the working directory.
+    pub filename: [u8; EXEC_FILENAME_MAX],
+    pub filename_len: u32,
+    /// `argv` as laid out in the new image, NUL-terminated strings,
+    /// truncated to [`EXEC_ARGS_MAX`].
+    pub args_len: u32,
+    pub args: [u8; EXEC_ARGS_MAX],
+    /// Components of the working directory, innermost first, each
+    /// NUL-terminated.
+    pub cwd: [u8; EXEC_CWD_MAX],
+    pub cwd_len: u32,
+    /// Non-zero when `cwd` goes up to the root; deep directories and long
+    /// names do not fit.
+    pub cwd_complete: u32,
 }
 
 #[repr(C)]
@@ -118,6 +254,9 @@ pub struct SchedExitEvent {
     pub pid: u32,
     pub exit_code: u32,
     pub boottime: u64,
+    pub timestamp_ns: u64,
+    /// `comm` of the thread group leader.
+    pub cmd: [u8; 16],
 }
 
 #[repr(C)]
@@ -156,9 +295,28 @@ pub struct SignalDeliverEvent {
     pub r15: u64,
     pub rflags: u64,
 
+    /// The syscall a seccomp filter rejected (`si_syscall`) for a SIGSYS it
+    /// raised, otherwise -1.
+    pub syscall_nr: i64,
+
     // Stack trace IDs (resolved in userspace)
     pub kernel_stack_id: i64,
     pub user_stack_id: i64,
+
+    /// cgroup v2 id and namespace inodes at the time of the crash; the
+    /// process may have moved since exec.
+    pub cgroup_id: u64,
+    pub pid_ns: u32,
+    pub mnt_ns: u32,
+    /// `pid` and `tid` as seen from the process' own pid namespace.
+    pub ns_pid: u32,
+    pub ns_tid: u32,
+
+    /// Non-zero when sent for a Go panic exit rather than a death by the
+    /// signal. The program may have recovered from the signal long before,
+    /// so userspace checks the panic names it.
+    pub go_panic: u32,
+    pub _pad: u32,
 }
 
 impl SignalDeliverEvent {
@@ -190,14 +348,51 @@ impl SignalDeliverEvent {
             r14: 0,
             r15: 0,
             rflags: 0,
+            syscall_nr: -1,
             kernel_stack_id: -1,
             user_stack_id: -1,
+            cgroup_id: 0,
+            pid_ns: 0,
+            mnt_ns: 0,
+            ns_pid: 0,
+            ns_tid: 0,
+            go_panic: 0,
+            _pad: 0,
         }
     }
-    #[inline]
-    pub const fn is_crash_signal(sig: i32) -> bool {
-        matches!(sig, SIGILL | SIGABRT | SIGBUS | SIGFPE | SIGSEGV)
-    }
+}
+
+/// State of a sibling thread of a crashing process, captured as the kernel
+/// kills it. Its stack dump, if any, is in the stack dump map under its tid.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ThreadStateEvent {
+    pub pid: u32,
+    pub tid: u32,
+    pub cmd: [u8; 16],
+    pub boottime: u64,
+
+    // Registers (x86_64)
+    pub rip: u64,
+    pub rsp: u64,
+    pub rbp: u64,
+    pub rax: u64,
+    pub rbx: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub r8: u64,
+    pub r9: u64,
+    pub r10: u64,
+    pub r11: u64,
+    pub r12: u64,
+    pub r13: u64,
+    pub r14: u64,
+    pub r15: u64,
+    pub rflags: u64,
+
+    pub user_stack_id: i64,
 }
 
 #[repr(C)]
@@ -232,6 +427,12 @@ unsafe impl aya::Pod for StackDump {}
 #[cfg(feature = "user")]
 unsafe impl aya::Pod for ArtifactReadyEvent {}
 #[cfg(feature = "user")]
+unsafe impl aya::Pod for ThreadStateEvent {}
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for CrashOutputEvent {}
+#[cfg(feature = "user")]
 unsafe impl aya::Pod for FdTrackKey {}
 #[cfg(feature = "user")]
 unsafe impl aya::Pod for ArtifactInfo {}
+#[cfg(feature = "user")]
+unsafe impl aya::Pod for ArtifactFds {}
@@ -7,14 +7,23 @@ mod programs;
 mod vmlinux;
 
 use crate::programs::{
+    artifact::{
+        try_handle_sys_enter_close, try_handle_sys_enter_openat, try_handle_sys_enter_write,
+        try_handle_sys_exit_openat,
+    },
+    module::{try_handle_sys_enter_mmap, try_handle_sys_exit_mmap},
     sched_process_exec::try_handle_sched_process_exec,
-    sched_process_exit::try_handle_sched_process_exit, signal_deliver::try_handle_signal_deliver,
+    sched_process_exit::try_handle_sched_process_exit,
+    signal_deliver::try_handle_signal_deliver,
 };
 
-use aya_ebpf::{macros::tracepoint, programs::TracePointContext};
+use aya_ebpf::{
+    macros::{raw_tracepoint, tracepoint},
+    programs::{RawTracePointContext, TracePointContext},
+};
 
-#[tracepoint]
-pub fn handle_signal_deliver(ctx: TracePointContext) -> u32 {
+#[raw_tracepoint(tracepoint = "signal_deliver")]
+pub fn handle_signal_deliver(ctx: RawTracePointContext) -> u32 {
     match unsafe { try_handle_signal_deliver(ctx) } {
         Ok(()) => 0,
         Err(e) => e as u32,
@@ -37,6 +46,54 @@ pub fn handle_sched_process_exit(ctx: TracePointContext) -> u32 {
     }
 }
 
+#[tracepoint]
+pub fn handle_sys_enter_openat(ctx: TracePointContext) -> u32 {
+    match try_handle_sys_enter_openat(ctx) {
+        Ok(()) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+#[tracepoint]
+pub fn handle_sys_exit_openat(ctx: TracePointContext) -> u32 {
+    match try_handle_sys_exit_openat(ctx) {
+        Ok(()) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+#[tracepoint]
+pub fn handle_sys_enter_write(ctx: TracePointContext) -> u32 {
+    match try_handle_sys_enter_write(ctx) {
+        Ok(()) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+#[tracepoint]
+pub fn handle_sys_enter_close(ctx: TracePointContext) -> u32 {
+    match try_handle_sys_enter_close(ctx) {
+        Ok(()) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+#[tracepoint]
+pub fn handle_sys_enter_mmap(ctx: TracePointContext) -> u32 {
+    match try_handle_sys_enter_mmap(ctx) {
+        Ok(()) => 0,
+        Err(e) => e as u32,
+    }
+}
+
+#[tracepoint]
+pub fn handle_sys_exit_mmap(ctx: TracePointContext) -> u32 {
+    match try_handle_sys_exit_mmap(ctx) {
+        Ok(()) => 0,
+        Err(e) => e as u32,
+    }
+}
+
 #[cfg(not(test))]
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
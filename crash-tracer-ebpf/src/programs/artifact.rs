@@ -0,0 +1,423 @@
+use aya_ebpf::{
+    helpers::{
+        bpf_get_current_pid_tgid, bpf_probe_read_kernel, bpf_probe_read_kernel_buf,
+        bpf_probe_read_user_buf, bpf_probe_read_user_str_bytes,
+        generated::bpf_get_current_task_btf,
+    },
+    macros::map,
+    maps::{HashMap, LruHashMap, PerCpuArray},
+    programs::TracePointContext,
+};
+use aya_log_ebpf::{debug, warn};
+use crash_tracer_common::{
+    ARTIFACT_FILENAME_MAX, ArtifactFds, ArtifactInfo, CRASH_OUTPUT_CHUNK, CrashTracerEvent,
+    EventType, FdTrackKey, MAX_OPEN_ARTIFACTS,
+};
+
+use crate::{
+    programs::{CRASH_TRACER_EVENTS, CRASHING_PROCESSES, fd_file, is_exit_watched},
+    vmlinux::{file, task_struct},
+};
+
+/// Runtimes write these when they die, usually before the fatal signal, so
+/// they are matched by name for every process rather than only crashing ones.
+/// Each entry is (basename prefix, basename suffix, whether only digits and
+/// dots may come between them); the last keeps names as common as
+/// `report.*.json` from matching.
+const ARTIFACT_PATTERNS: &[(&[u8], &[u8], bool)] = &[
+    // JVM fatal error log, `hs_err_pid<pid>.log`.
+    (b"hs_err_pid", b".log", true),
+    // Node.js diagnostic report, `report.<date>.<time>.<pid>.<seq>.json`.
+    (b"report.", b".json", true),
+    // Python faulthandler writes to stderr, which the output tail covers; this
+    // picks up `faulthandler.enable(file)` only when the file is so named.
+    (b"faulthandler", b".log", false),
+    // .NET createdump, `/tmp/coredump.<pid>` unless DOTNET_DbgMiniDumpName is set.
+    (b"coredump.", b"", true),
+    // .NET createdump with DOTNET_CrashReportEnabled=1.
+    (b"", b".crashreport.json", false),
+];
+
+const O_ACCMODE: u64 = 0o3;
+const AT_FDCWD: i32 = -100;
+const STDERR_FILENO: u64 = 2;
+
+/// Bytes of stderr kept per process; a power of two.
+const OUTPUT_TAIL_SIZE: usize = 16 * 1024;
+
+/// Artifacts between `sys_enter_openat` and `sys_exit_openat`, keyed by pid_tgid.
+#[map]
+static PENDING_OPENS: HashMap<u64, ArtifactInfo> = HashMap::with_max_entries(64, 0);
+
+/// Open artifact descriptors. Userspace drains what is left when a process
+/// exits without closing them.
+#[map]
+static TRACKED_FDS: HashMap<FdTrackKey, ArtifactInfo> = HashMap::with_max_entries(256, 0);
+
+/// The descriptors in `TRACKED_FDS`, by process (tgid).
+#[map]
+static ARTIFACT_FDS: HashMap<u32, ArtifactFds> = HashMap::with_max_entries(64, 0);
+
+/// The end of what crashing processes and watched commands wrote to stderr,
+/// by process (tgid), sent to userspace once they exit rather than as it is
+/// written. LRU, as watched commands may be many.
+#[map]
+static OUTPUT_TAILS: LruHashMap<u32, OutputTail> = LruHashMap::with_max_entries(128, 0);
+
+/// Starts a process' entry in `OUTPUT_TAILS`, too big for the stack.
+#[map]
+static OUTPUT_TAIL_SCRATCH: PerCpuArray<OutputTail> = PerCpuArray::with_max_entries(1, 0);
+
+#[repr(C)]
+struct OutputTail {
+    boottime: u64,
+    /// Bytes added in total. Byte `i` of the output is at
+    /// `data[i % OUTPUT_TAIL_SIZE]` while among the last `OUTPUT_TAIL_SIZE`.
+    written: u64,
+    /// The ring, then room for a chunk running past its end until it is
+    /// wrapped around to the front.
+    data: [u8; OUTPUT_TAIL_SIZE + CRASH_OUTPUT_CHUNK],
+}
+
+pub fn try_handle_sys_enter_openat(ctx: TracePointContext) -> Result<(), i64> {
+    // See /sys/kernel/debug/tracing/events/syscalls/sys_enter_openat/format
+    let dfd: i64 = unsafe { ctx.read_at(16)? };
+    let filename: *const u8 = unsafe { ctx.read_at(24)? };
+    let flags: u64 = unsafe { ctx.read_at(32)? };
+
+    // Artifacts are written, never only read.
+    if flags & O_ACCMODE == 0 {
+        return Ok(());
+    }
+
+    let mut info = ArtifactInfo {
+        filename: [0u8; ARTIFACT_FILENAME_MAX],
+        filename_len: 0,
+        bytes_written: 0,
+        file: 0,
+    };
+    let len = match unsafe { bpf_probe_read_user_str_bytes(filename, &mut info.filename) } {
+        Ok(name) => name.len(),
+        Err(_) => return Ok(()),
+    };
+    // Userspace resolves relative names against the cwd only.
+    if dfd as i32 != AT_FDCWD && info.filename[0] != b'/' {
+        return Ok(());
+    }
+    if !is_artifact_name(&info.filename, len) {
+        return Ok(());
+    }
+    info.filename_len = len as u32;
+
+    let _ = PENDING_OPENS.insert(&bpf_get_current_pid_tgid(), &info, 0);
+    Ok(())
+}
+
+pub fn try_handle_sys_exit_openat(ctx: TracePointContext) -> Result<(), i64> {
+    let pid_tgid = bpf_get_current_pid_tgid();
+    let Some(mut info) = (unsafe { PENDING_OPENS.get(&pid_tgid) }).copied() else {
+        return Ok(());
+    };
+    let _ = PENDING_OPENS.remove(&pid_tgid);
+
+    let ret: i64 = unsafe { ctx.read_at(16)? };
+    if ret < 0 {
+        return Ok(());
+    }
+
+    let key = FdTrackKey {
+        pid: (pid_tgid >> 32) as u32,
+        fd: ret as u32,
+    };
+    let task: *const task_struct = unsafe { bpf_get_current_task_btf() as *const task_struct };
+    // Still tracked, so the artifact was closed without `close`, as by `dup2`
+    // onto its descriptor or at exec, and is as complete as it gets.
+    if let Some(stale) = (unsafe { TRACKED_FDS.get(&key) }).copied() {
+        unsafe { report_ready(&ctx, task, key.pid, &stale) };
+    } else if !index_fd(&key) {
+        debug!(&ctx, "too many open artifacts: pid={}", key.pid);
+        return Ok(());
+    }
+
+    info.file = unsafe { fd_file(task, key.fd) }.map_or(0, |file| file as u64);
+    let _ = TRACKED_FDS.insert(&key, &info, 0);
+    debug!(&ctx, "tracking artifact: pid={} fd={}", key.pid, key.fd);
+    Ok(())
+}
+
+pub fn try_handle_sys_enter_write(ctx: TracePointContext) -> Result<(), i64> {
+    let fd: u64 = unsafe { ctx.read_at(16)? };
+    let pid = (bpf_get_current_pid_tgid() >> 32) as u32;
+
+    if fd == STDERR_FILENO {
+        let task: *const task_struct = unsafe { bpf_get_current_task_btf() as *const task_struct };
+        let boottime = unsafe { (*(*task).group_leader).start_boottime };
+        // The entry may be left from an earlier process with the same pid
+        // whose exit was missed.
+        let crashing = unsafe { CRASHING_PROCESSES.get(&pid) }.copied() == Some(boottime);
+        if crashing || unsafe { is_exit_watched(task) } {
+            return unsafe { append_output_tail(&ctx, pid, boottime) };
+        }
+        return Ok(());
+    }
+
+    let key = FdTrackKey {
+        pid,
+        fd: fd as u32,
+    };
+    let Some(info) = TRACKED_FDS.get_ptr_mut(&key) else {
+        return Ok(());
+    };
+
+    // The descriptor now refers to another file; the artifact was closed
+    // without `close`. Writes through a dup of it are not counted, the file
+    // is read from disk anyway.
+    let task: *const task_struct = unsafe { bpf_get_current_task_btf() as *const task_struct };
+    let open = unsafe { fd_file(task, key.fd) }.map_or(0, |file| file as u64);
+    if open != unsafe { (*info).file } {
+        let stale = unsafe { *info };
+        untrack(&key);
+        unsafe { report_ready(&ctx, task, pid, &stale) };
+        return Ok(());
+    }
+
+    let count: u64 = unsafe { ctx.read_at(32)? };
+    unsafe {
+        (*info).bytes_written = (*info).bytes_written.saturating_add(count as u32);
+    }
+    Ok(())
+}
+
+/// Add what a crashing process writes to stderr, typically from its fatal
+/// signal handler (Python faulthandler, Go runtime, JVM), to its tail in
+/// [`OUTPUT_TAILS`]. Also used for everything watched commands write. Only
+/// the last [`OUTPUT_TAIL_SIZE`] bytes of a write can end up in the tail, so
+/// the rest is not read.
+unsafe fn append_output_tail(ctx: &TracePointContext, pid: u32, boottime: u64) -> Result<(), i64> {
+    let buf: *const u8 = unsafe { ctx.read_at(24)? };
+    let count: u64 = unsafe { ctx.read_at(32)? };
+
+    let tail = match OUTPUT_TAILS.get_ptr_mut(&pid) {
+        Some(tail) if unsafe { (*tail).boottime } == boottime => tail,
+        _ => {
+            let Some(scratch) = OUTPUT_TAIL_SCRATCH.get_ptr_mut(0) else {
+                return Ok(());
+            };
+            unsafe {
+                (*scratch).boottime = boottime;
+                (*scratch).written = 0;
+                OUTPUT_TAILS.insert(&pid, &*scratch, 0)?;
+            }
+            OUTPUT_TAILS.get_ptr_mut(&pid).ok_or(0)?
+        }
+    };
+    let tail = unsafe { &mut *tail };
+
+    let len = count.min(OUTPUT_TAIL_SIZE as u64);
+    let src = unsafe { buf.add((count - len) as usize) };
+    for chunk in 0..OUTPUT_TAIL_SIZE / CRASH_OUTPUT_CHUNK {
+        let done = chunk * CRASH_OUTPUT_CHUNK;
+        if done as u64 >= len {
+            break;
+        }
+        let chunk_len = ((len - done as u64) as usize).min(CRASH_OUTPUT_CHUNK);
+        let offset = tail.written as usize & (OUTPUT_TAIL_SIZE - 1);
+        let dest = &mut tail.data[offset..offset + chunk_len];
+        if unsafe { bpf_probe_read_user_buf(src.add(done), dest) }.is_err() {
+            break;
+        }
+        // Wrap what ran past the end of the ring around to its front.
+        if offset + chunk_len > OUTPUT_TAIL_SIZE {
+            let spill = offset + chunk_len - OUTPUT_TAIL_SIZE;
+            let past_end = unsafe { tail.data.as_ptr().add(OUTPUT_TAIL_SIZE) };
+            let _ = unsafe { bpf_probe_read_kernel_buf(past_end, &mut tail.data[..spill]) };
+        }
+        tail.written += chunk_len as u64;
+    }
+    Ok(())
+}
+
+/// Send the stderr tail kept for `pid`, oldest bytes first, as it exits.
+pub unsafe fn send_output_tail(ctx: &TracePointContext, pid: u32, boottime: u64) {
+    let Some(tail) = (unsafe { OUTPUT_TAILS.get(&pid) }) else {
+        return;
+    };
+    if tail.boottime == boottime {
+        let mut pos = tail.written.saturating_sub(OUTPUT_TAIL_SIZE as u64);
+        // One more than fits, as the tail may start mid-chunk.
+        for _ in 0..OUTPUT_TAIL_SIZE / CRASH_OUTPUT_CHUNK + 1 {
+            if pos >= tail.written {
+                break;
+            }
+            let offset = pos as usize & (OUTPUT_TAIL_SIZE - 1);
+            let len = ((tail.written - pos) as usize)
+                .min(CRASH_OUTPUT_CHUNK)
+                .min(OUTPUT_TAIL_SIZE - offset);
+
+            let Some(mut entry) = CRASH_TRACER_EVENTS.reserve::<CrashTracerEvent>(0) else {
+                warn!(ctx, "The buffer is currently full. Cannot send crash output.");
+                break;
+            };
+            let ptr = entry.as_mut_ptr();
+            unsafe {
+                (*ptr).tag = EventType::CrashOutput;
+                let output = &mut (*ptr).payload.output;
+                output.pid = pid;
+                output.boottime = boottime;
+                output.len = 0;
+                let src = tail.data.as_ptr().add(offset);
+                if bpf_probe_read_kernel_buf(src, &mut output.data[..len]).is_ok() {
+                    output.len = len as u32;
+                }
+            }
+            entry.submit(0);
+            pos += len as u64;
+        }
+    }
+    let _ = OUTPUT_TAILS.remove(&pid);
+}
+
+pub fn try_handle_sys_enter_close(ctx: TracePointContext) -> Result<(), i64> {
+    let fd: u64 = unsafe { ctx.read_at(16)? };
+    let key = FdTrackKey {
+        pid: (bpf_get_current_pid_tgid() >> 32) as u32,
+        fd: fd as u32,
+    };
+    let Some(info) = (unsafe { TRACKED_FDS.get(&key) }).copied() else {
+        return Ok(());
+    };
+
+    let task: *const task_struct = unsafe { bpf_get_current_task_btf() as *const task_struct };
+    // Still open through a dup or in a child, and possibly still written:
+    // the entry stays until the process exits or the descriptor is reused.
+    if let Ok(file) = unsafe { fd_file(task, key.fd) }
+        && file as u64 == info.file
+        && unsafe { has_other_refs(file) }
+    {
+        return Ok(());
+    }
+
+    untrack(&key);
+    unsafe { report_ready(&ctx, task, key.pid, &info) };
+    Ok(())
+}
+
+/// Tell userspace the artifact `info` of `pid` is complete.
+unsafe fn report_ready(
+    ctx: &TracePointContext,
+    task: *const task_struct,
+    pid: u32,
+    info: &ArtifactInfo,
+) {
+    let Some(mut entry) = CRASH_TRACER_EVENTS.reserve::<CrashTracerEvent>(0) else {
+        warn!(ctx, "The buffer is currently full. Cannot report artifact.");
+        return;
+    };
+
+    let ptr = entry.as_mut_ptr();
+    unsafe {
+        (*ptr).tag = EventType::ArtifactReady;
+        (*ptr).payload.artifact.pid = pid;
+        (*ptr).payload.artifact.boottime = (*(*task).group_leader).start_boottime;
+        (*ptr).payload.artifact.filename = info.filename;
+        (*ptr).payload.artifact.filename_len = info.filename_len;
+        (*ptr).payload.artifact.bytes_written = info.bytes_written;
+        (*ptr).payload.artifact._pad = 0;
+    }
+    entry.submit(0);
+}
+
+/// Whether anything besides the descriptor being closed holds `file`.
+unsafe fn has_other_refs(file: *mut file) -> bool {
+    // `f_ref` counts the references beyond the first; it goes negative once
+    // the file is released.
+    unsafe { bpf_probe_read_kernel(&raw const (*file).f_ref.refcnt.counter) }
+        .is_ok_and(|refs| refs > 0)
+}
+
+/// Add the descriptor of `key` to its process' entry in [`ARTIFACT_FDS`];
+/// false if that is full.
+fn index_fd(key: &FdTrackKey) -> bool {
+    let Some(open) = ARTIFACT_FDS.get_ptr_mut(&key.pid) else {
+        let mut open = ArtifactFds {
+            fds: [-1; MAX_OPEN_ARTIFACTS],
+        };
+        open.fds[0] = key.fd as i32;
+        return ARTIFACT_FDS.insert(&key.pid, &open, 0).is_ok();
+    };
+    for slot in 0..MAX_OPEN_ARTIFACTS {
+        unsafe {
+            if (*open).fds[slot] < 0 {
+                (*open).fds[slot] = key.fd as i32;
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Stop tracking the descriptor of `key`.
+fn untrack(key: &FdTrackKey) {
+    let _ = TRACKED_FDS.remove(key);
+    let Some(open) = ARTIFACT_FDS.get_ptr_mut(&key.pid) else {
+        return;
+    };
+    for slot in 0..MAX_OPEN_ARTIFACTS {
+        unsafe {
+            if (*open).fds[slot] == key.fd as i32 {
+                (*open).fds[slot] = -1;
+            }
+        }
+    }
+}
+
+/// Whether the basename of `name` (`len` bytes, without NUL) matches one of
+/// [`ARTIFACT_PATTERNS`].
+fn is_artifact_name(name: &[u8; ARTIFACT_FILENAME_MAX], len: usize) -> bool {
+    let mut base = 0;
+    for i in 0..ARTIFACT_FILENAME_MAX {
+        if i >= len {
+            break;
+        }
+        if name[i] == b'/' {
+            base = i + 1;
+        }
+    }
+
+    for (prefix, suffix, numeric) in ARTIFACT_PATTERNS {
+        if base + prefix.len() + suffix.len() <= len
+            && matches_at(name, base, prefix)
+            && matches_at(name, len - suffix.len(), suffix)
+            && (!numeric || is_numeric(name, base + prefix.len(), len - suffix.len()))
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// Whether `name[start..end]` is non-empty and only digits and dots.
+fn is_numeric(name: &[u8; ARTIFACT_FILENAME_MAX], start: usize, end: usize) -> bool {
+    if start >= end {
+        return false;
+    }
+    for i in 0..ARTIFACT_FILENAME_MAX {
+        if i >= end {
+            break;
+        }
+        if i >= start && !(name[i].is_ascii_digit() || name[i] == b'.') {
+            return false;
+        }
+    }
+    true
+}
+
+fn matches_at(name: &[u8; ARTIFACT_FILENAME_MAX], start: usize, pattern: &[u8]) -> bool {
+    for (i, byte) in pattern.iter().enumerate() {
+        match name.get(start + i) {
+            Some(b) if b == byte => {}
+            _ => return false,
+        }
+    }
+    true
+}
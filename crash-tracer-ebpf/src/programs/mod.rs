@@ -1,20 +1,229 @@
 use aya_ebpf::{
+    bindings::BPF_NOEXIST,
+    helpers::{bpf_probe_read_kernel, bpf_probe_read_kernel_str_bytes},
     macros::map,
-    maps::{HashMap, RingBuf},
+    maps::{Array, HashMap, LruHashMap, RingBuf},
 };
-use crash_tracer_common::{SignalDeliverEvent, StackDump, StackDumpKey};
+use crash_tracer_common::{
+    CONFIG_LEN, MAX_SIGNAL, NS_PER_TICK, SignalDeliverEvent, StackDump, StackDumpKey,
+};
+
+use crate::vmlinux::{dentry, fdtable, file, mount, path, task_struct, vfsmount};
 
+pub mod artifact;
+pub mod module;
 pub mod sched_process_exec;
 pub mod sched_process_exit;
 pub mod signal_deliver;
 
+/// Deepest pid namespace nesting the kernel allows (`MAX_PID_NS_LEVEL`).
+const MAX_PID_NS_LEVEL: u32 = 32;
+
+/// Longest path component kept by [`read_path`]; a longer name leaves the
+/// path incomplete.
+const PATH_COMPONENT_MAX: usize = 64;
+/// Directory levels and mount points walked up by [`read_path`].
+const MAX_PATH_DEPTH: usize = 24;
+
+/// Sized for exec storms: each event takes as much room as the largest kind,
+/// an exec with its command line and working directory.
 #[map]
-static CRASH_TRACER_EVENTS: RingBuf = RingBuf::with_byte_size(512 * 1024, 0);
+static CRASH_TRACER_EVENTS: RingBuf = RingBuf::with_byte_size(2 * 1024 * 1024, 0);
 
 #[map]
 static PENDING_SIGNALS: HashMap<StackDumpKey, SignalDeliverEvent> =
     HashMap::with_max_entries(64, 0);
 
 /// Stack dumps keyed by (pid, tid). Userspace reads and deletes after processing.
+/// Sized for the sibling threads captured in all-threads mode.
+#[map]
+static STACK_DUMP_MAP: HashMap<StackDumpKey, StackDump> = HashMap::with_max_entries(256, 0);
+
+/// Runtime options set by userspace, indexed by `crash_tracer_common::CONFIG_*`.
+#[map]
+static CONFIG: Array<u32> = Array::with_max_entries(CONFIG_LEN, 0);
+
+/// Signals treated as crashes, indexed by signal number; non-zero means
+/// enabled. Filled by userspace at startup.
+#[map]
+static CRASH_SIGNALS: Array<u8> = Array::with_max_entries(MAX_SIGNAL + 1, 0);
+
+/// Processes (tgid -> boottime) that took a crash signal. Their other
+/// threads are captured as the kernel kills them and the tail of their
+/// stderr is sent to userspace when they exit.
+#[map]
+static CRASHING_PROCESSES: HashMap<u32, u64> = HashMap::with_max_entries(64, 0);
+
+/// Commands (`comm`) whose non-zero exits userspace records as crashes. The
+/// tail of their stderr is kept too, and sent when they exit.
+#[map]
+static EXIT_WATCH_COMMS: HashMap<[u8; 16], u8> = HashMap::with_max_entries(64, 0);
+
+/// Go programs (tgid -> boottime), added by userspace as it comes across
+/// them. Those found running at startup have their boottime from
+/// `/proc/<pid>/stat`, rounded down to a clock tick. Their stderr tail is kept as for `EXIT_WATCH_COMMS`, since panics are
+/// recorded from it. Removed when the process exits.
+#[map]
+static GO_PROCESSES: LruHashMap<u32, u64> = LruHashMap::with_max_entries(4096, 0);
+
+/// Processes already reported as exited. Several threads can see
+/// `signal->live` at zero when they exit together; only the first to claim
+/// the process here sends `SchedExit`.
 #[map]
-static STACK_DUMP_MAP: HashMap<StackDumpKey, StackDump> = HashMap::with_max_entries(64, 0);
+static EXITED_PROCESSES: LruHashMap<ProcessKey, u8> = LruHashMap::with_max_entries(1024, 0);
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ProcessKey {
+    pid: u32,
+    _pad: u32,
+    boottime: u64,
+}
+
+/// Whether this is the first call for the process: the insert fails if
+/// another thread already made it.
+fn claim_exit(pid: u32, boottime: u64) -> bool {
+    let key = ProcessKey {
+        pid,
+        _pad: 0,
+        boottime,
+    };
+    EXITED_PROCESSES
+        .insert(&key, &0, BPF_NOEXIST as u64)
+        .is_ok()
+}
+
+fn config(index: u32) -> u32 {
+    CONFIG.get(index).copied().unwrap_or(0)
+}
+
+fn is_crash_signal(signal: i32) -> bool {
+    signal > 0
+        && CRASH_SIGNALS
+            .get(signal as u32)
+            .is_some_and(|enabled| *enabled != 0)
+}
+
+/// `comm` of the thread group leader; threads may have renamed themselves.
+unsafe fn process_comm(task: *const task_struct) -> [u8; 16] {
+    unsafe { (*(*task).group_leader).comm }.map(|c| c as u8)
+}
+
+/// Inodes of the pid and mount namespaces of `task`, as
+/// `/proc/<pid>/ns/{pid,mnt}` show them. The pid namespace is the one for
+/// children, which is the task's own unless it called `unshare` without
+/// forking since.
+unsafe fn namespaces(task: *const task_struct) -> (u32, u32) {
+    let nsproxy = unsafe { (*task).nsproxy };
+    if nsproxy.is_null() {
+        return (0, 0);
+    }
+    unsafe {
+        (
+            (*(*nsproxy).pid_ns_for_children).ns.inum,
+            (*(*nsproxy).mnt_ns).ns.inum,
+        )
+    }
+}
+
+/// Number of `task` in its own pid namespace, as `getpid`/`gettid` return
+/// it there: the entry for the deepest level of its `struct pid`. Pass the
+/// group leader for the process id.
+unsafe fn ns_pid(task: *const task_struct) -> u32 {
+    let pid = unsafe { (*task).thread_pid };
+    if pid.is_null() {
+        return 0;
+    }
+    let level = unsafe { (*pid).level }.min(MAX_PID_NS_LEVEL);
+    let upid = unsafe { (*pid).numbers.as_ptr().add(level as usize) };
+    unsafe { bpf_probe_read_kernel(&raw const (*upid).nr) }.map_or(0, |nr| nr as u32)
+}
+
+unsafe fn is_exit_watched(task: *const task_struct) -> bool {
+    unsafe { is_go(task) || EXIT_WATCH_COMMS.get(&process_comm(task)).is_some() }
+}
+
+unsafe fn is_go(task: *const task_struct) -> bool {
+    let pid = unsafe { (*task).tgid } as u32;
+    let boottime = unsafe { (*(*task).group_leader).start_boottime };
+    unsafe { GO_PROCESSES.get(&pid) }
+        .is_some_and(|go| *go == boottime || *go == boottime / NS_PER_TICK * NS_PER_TICK)
+}
+
+/// The file open as `fd` in `task`.
+unsafe fn fd_file(task: *const task_struct, fd: u32) -> Result<*mut file, i64> {
+    let files = unsafe { (*task).files };
+    if files.is_null() {
+        return Err(0);
+    }
+    let fdt: *mut fdtable = unsafe { bpf_probe_read_kernel(&raw const (*files).fdt)? };
+    let max_fds: u32 = unsafe { bpf_probe_read_kernel(&raw const (*fdt).max_fds)? };
+    if fd >= max_fds {
+        return Err(0);
+    }
+    let fds: *mut *mut file = unsafe { bpf_probe_read_kernel(&raw const (*fdt).fd)? };
+    let file: *mut file = unsafe { bpf_probe_read_kernel(fds.add(fd as usize))? };
+    if file.is_null() {
+        return Err(0);
+    }
+    Ok(file)
+}
+
+/// Walk from `target` up to the root directory of `task`, crossing mount
+/// points, writing each component NUL-terminated into `out`, innermost
+/// first. Returns the bytes written and whether the root was reached.
+unsafe fn read_path<const N: usize>(
+    task: *const task_struct,
+    target: &path,
+    out: &mut [u8; N],
+) -> Result<(usize, bool), i64> {
+    let fs = unsafe { (*task).fs };
+    if fs.is_null() {
+        return Ok((0, false));
+    }
+    let root: path = unsafe { bpf_probe_read_kernel(&raw const (*fs).root)? };
+
+    let mut dentry = target.dentry;
+    let mut mnt = unsafe { real_mount(target.mnt) };
+    let mut len = 0;
+    for _ in 0..MAX_PATH_DEPTH {
+        let vfsmnt = unsafe { &raw mut (*mnt).mnt };
+        if dentry == root.dentry && vfsmnt == root.mnt {
+            return Ok((len, true));
+        }
+        let mnt_root: *mut dentry =
+            unsafe { bpf_probe_read_kernel(&raw const (*vfsmnt).mnt_root)? };
+        if dentry == mnt_root {
+            let parent: *mut mount =
+                unsafe { bpf_probe_read_kernel(&raw const (*mnt).mnt_parent)? };
+            // The top of the mount tree, above the process' root; the
+            // path was reached some other way, as after a chroot.
+            if parent == mnt {
+                return Ok((len, true));
+            }
+            dentry = unsafe { bpf_probe_read_kernel(&raw const (*mnt).mnt_mountpoint)? };
+            mnt = parent;
+            continue;
+        }
+
+        if len > N - PATH_COMPONENT_MAX {
+            return Ok((len, false));
+        }
+        let name: *const u8 = unsafe { bpf_probe_read_kernel(&raw const (*dentry).d_name.name)? };
+        let component = unsafe {
+            bpf_probe_read_kernel_str_bytes(name, &mut out[len..len + PATH_COMPONENT_MAX])?
+        };
+        // Possibly cut short.
+        if component.len() >= PATH_COMPONENT_MAX - 1 {
+            return Ok((len, false));
+        }
+        len += component.len() + 1;
+        dentry = unsafe { bpf_probe_read_kernel(&raw const (*dentry).d_parent)? };
+    }
+    Ok((len, false))
+}
+
+/// The `struct mount` embedding `vfsmnt`, as the kernel's `real_mount`.
+unsafe fn real_mount(vfsmnt: *mut vfsmount) -> *mut mount {
+    unsafe { (vfsmnt as *mut u8).sub(core::mem::offset_of!(mount, mnt)) as *mut mount }
+}
@@ -0,0 +1,132 @@
+use aya_ebpf::{
+    helpers::{
+        bpf_get_current_pid_tgid, bpf_probe_read_kernel, generated::bpf_get_current_task_btf,
+    },
+    macros::map,
+    maps::HashMap,
+    programs::TracePointContext,
+};
+use aya_log_ebpf::warn;
+use crash_tracer_common::{CrashTracerEvent, EventType, ModuleLoadEvent};
+use vmlinux::{inode, path, super_block, task_struct};
+
+use crate::{
+    programs::{CRASH_TRACER_EVENTS, fd_file, read_path},
+    vmlinux,
+};
+
+const PROT_EXEC: u64 = 0x4;
+const MAP_ANONYMOUS: u64 = 0x20;
+const PAGE_SIZE: u64 = 4096;
+/// Return values of this size and above are `-errno`.
+const MAX_ERRNO: u64 = 4095;
+
+/// Arguments of an executable file `mmap`, between `sys_enter_mmap` and
+/// `sys_exit_mmap`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PendingMmap {
+    len: u64,
+    offset: u64,
+    prot: u32,
+    flags: u32,
+    fd: u32,
+    _pad: u32,
+}
+
+/// Keyed by pid_tgid.
+#[map]
+static PENDING_MMAPS: HashMap<u64, PendingMmap> = HashMap::with_max_entries(256, 0);
+
+pub fn try_handle_sys_enter_mmap(ctx: TracePointContext) -> Result<(), i64> {
+    // See /sys/kernel/debug/tracing/events/syscalls/sys_enter_mmap/format
+    let len: u64 = unsafe { ctx.read_at(24)? };
+    let prot: u64 = unsafe { ctx.read_at(32)? };
+    let flags: u64 = unsafe { ctx.read_at(40)? };
+    let fd: i64 = unsafe { ctx.read_at(48)? };
+    let offset: u64 = unsafe { ctx.read_at(56)? };
+
+    // Code from a file; JIT output is anonymous.
+    if prot & PROT_EXEC == 0 || flags & MAP_ANONYMOUS != 0 || fd < 0 {
+        return Ok(());
+    }
+
+    let pending = PendingMmap {
+        len,
+        offset,
+        prot: prot as u32,
+        flags: flags as u32,
+        fd: fd as u32,
+        _pad: 0,
+    };
+    let _ = PENDING_MMAPS.insert(&bpf_get_current_pid_tgid(), &pending, 0);
+    Ok(())
+}
+
+pub fn try_handle_sys_exit_mmap(ctx: TracePointContext) -> Result<(), i64> {
+    let pid_tgid = bpf_get_current_pid_tgid();
+    let Some(pending) = (unsafe { PENDING_MMAPS.get(&pid_tgid) }).copied() else {
+        return Ok(());
+    };
+    let _ = PENDING_MMAPS.remove(&pid_tgid);
+
+    let ret: u64 = unsafe { ctx.read_at(16)? };
+    if ret >= MAX_ERRNO.wrapping_neg() {
+        return Ok(());
+    }
+
+    let task: *const task_struct = unsafe { bpf_get_current_task_btf() as *const task_struct };
+    let pid = (pid_tgid >> 32) as u32;
+    let start = ret;
+    let end = ret + ((pending.len + PAGE_SIZE - 1) & !(PAGE_SIZE - 1));
+
+    let mut entry = match CRASH_TRACER_EVENTS.reserve::<CrashTracerEvent>(0) {
+        Some(e) => e,
+        None => {
+            warn!(
+                &ctx,
+                "The buffer is currently full. Cannot capture module load."
+            );
+            return Ok(());
+        }
+    };
+
+    let ptr = entry.as_mut_ptr();
+    unsafe {
+        (*ptr).tag = EventType::ModuleLoad;
+        let module = &mut (*ptr).payload.module_load;
+        module.pid = pid;
+        module.prot = pending.prot;
+        module.boottime = (*(*task).group_leader).start_boottime;
+        module.start = start;
+        module.end = end;
+        module.offset = pending.offset;
+        module.flags = pending.flags;
+        if read_file(task, pending.fd, module).is_err() {
+            // The descriptor was closed by another thread already.
+            entry.discard(0);
+            return Ok(());
+        }
+    }
+    entry.submit(0);
+    Ok(())
+}
+
+/// Path, inode and device of the file open as `fd` in `task`.
+unsafe fn read_file(
+    task: *const task_struct,
+    fd: u32,
+    module: &mut ModuleLoadEvent,
+) -> Result<(), i64> {
+    let file = unsafe { fd_file(task, fd)? };
+    let inode: *mut inode = unsafe { bpf_probe_read_kernel(&raw const (*file).f_inode)? };
+    module.inode = unsafe { bpf_probe_read_kernel(&raw const (*inode).i_ino)? };
+    let sb: *mut super_block = unsafe { bpf_probe_read_kernel(&raw const (*inode).i_sb)? };
+    module.dev = unsafe { bpf_probe_read_kernel(&raw const (*sb).s_dev)? };
+
+    let f_path: path = unsafe { bpf_probe_read_kernel(&raw const (*file).f_path)? };
+    let (len, complete) = unsafe { read_path(task, &f_path, &mut module.path)? };
+    module.path_len = len as u32;
+    module.path_complete = complete as u32;
+    Ok(())
+}
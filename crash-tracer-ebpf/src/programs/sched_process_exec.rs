@@ -1,15 +1,29 @@
-use aya_ebpf::{helpers::generated::bpf_get_current_task_btf, programs::TracePointContext};
+use aya_ebpf::{
+    helpers::{
+        bpf_probe_read_kernel, bpf_probe_read_kernel_str_bytes, bpf_probe_read_user_buf,
+        generated::{bpf_get_current_cgroup_id, bpf_get_current_task_btf},
+    },
+    programs::TracePointContext,
+};
 
 use aya_log_ebpf::warn;
-use crash_tracer_common::{CrashTracerEvent, EventType};
-use vmlinux::task_struct;
+use crash_tracer_common::{
+    CrashTracerEvent, EXEC_ARGS_MAX, EXEC_CWD_MAX, EventType, SchedExecEvent,
+};
+use vmlinux::{path, task_struct};
 
-use crate::{programs::CRASH_TRACER_EVENTS, vmlinux};
+use crate::{
+    programs::{CRASH_TRACER_EVENTS, namespaces, ns_pid, read_path},
+    vmlinux,
+};
 
 pub fn try_handle_sched_process_exec(ctx: TracePointContext) -> Result<(), i64> {
     let task: *const task_struct = unsafe { bpf_get_current_task_btf() as *const task_struct };
     let start_boottime = unsafe { (*task).start_boottime };
     let pid = unsafe { (*task).tgid } as u32;
+    let (pid_ns, mnt_ns) = unsafe { namespaces(task) };
+    // exec makes the calling thread the group leader.
+    let ns_pid = unsafe { ns_pid(task) };
 
     let mut entry = match CRASH_TRACER_EVENTS.reserve::<CrashTracerEvent>(0) {
         Some(e) => e,
@@ -22,11 +36,67 @@ pub fn try_handle_sched_process_exec(ctx: TracePointContext) -> Result<(), i64>
     let ptr = entry.as_mut_ptr();
     unsafe {
         (*ptr).tag = EventType::SchedExec;
-        (*ptr).payload.exec.pid = pid;
-        (*ptr).payload.exec.boottime = start_boottime;
+        let exec = &mut (*ptr).payload.exec;
+        exec.pid = pid;
+        exec.ns_pid = ns_pid;
+        exec.boottime = start_boottime;
+        exec.cgroup_id = bpf_get_current_cgroup_id();
+        exec.pid_ns = pid_ns;
+        exec.mnt_ns = mnt_ns;
+        // Userspace used to read these from /proc after the fact, by which
+        // time short-lived processes were gone.
+        read_filename(&ctx, exec);
+        read_args(task, exec);
+        let (cwd_len, complete) = read_cwd(task, &mut exec.cwd).unwrap_or((0, false));
+        exec.cwd_len = cwd_len as u32;
+        exec.cwd_complete = complete as u32;
     }
 
     entry.submit(0);
 
     Ok(())
 }
+
+/// The path passed to exec, from the tracepoint's `filename`.
+unsafe fn read_filename(ctx: &TracePointContext, exec: &mut SchedExecEvent) {
+    exec.filename_len = 0;
+    // See /sys/kernel/debug/tracing/events/sched/sched_process_exec/format:
+    // `__data_loc char[] filename` at offset 8, the low half of which is the
+    // string's offset into the record.
+    let Ok(loc) = (unsafe { ctx.read_at::<u32>(8) }) else {
+        return;
+    };
+    let filename = unsafe { (ctx.as_ptr() as *const u8).add((loc & 0xffff) as usize) };
+    if let Ok(name) = unsafe { bpf_probe_read_kernel_str_bytes(filename, &mut exec.filename) } {
+        exec.filename_len = name.len() as u32;
+    }
+}
+
+/// `argv`, which the kernel just copied to the top of the new stack.
+unsafe fn read_args(task: *const task_struct, exec: &mut SchedExecEvent) {
+    exec.args_len = 0;
+    let mm = unsafe { (*task).mm };
+    if mm.is_null() {
+        return;
+    }
+    let start = unsafe { (*mm).__bindgen_anon_1.arg_start };
+    let end = unsafe { (*mm).__bindgen_anon_1.arg_end };
+    // The environment follows; never read past the arguments.
+    let len = (end.saturating_sub(start) as usize).min(EXEC_ARGS_MAX);
+    if unsafe { bpf_probe_read_user_buf(start as *const u8, &mut exec.args[..len]) }.is_ok() {
+        exec.args_len = len as u32;
+    }
+}
+
+/// Working directory of `task`; see [`read_path`].
+unsafe fn read_cwd(
+    task: *const task_struct,
+    cwd: &mut [u8; EXEC_CWD_MAX],
+) -> Result<(usize, bool), i64> {
+    let fs = unsafe { (*task).fs };
+    if fs.is_null() {
+        return Ok((0, false));
+    }
+    let pwd: path = unsafe { bpf_probe_read_kernel(&raw const (*fs).pwd)? };
+    unsafe { read_path(task, &pwd, cwd) }
+}
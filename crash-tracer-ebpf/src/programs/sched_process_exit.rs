@@ -1,23 +1,47 @@
-use aya_ebpf::{helpers::generated::bpf_get_current_task_btf, programs::TracePointContext};
+use aya_ebpf::{
+    helpers::generated::{bpf_get_current_task_btf, bpf_ktime_get_ns},
+    programs::TracePointContext,
+};
 
 use aya_log_ebpf::warn;
 use crash_tracer_common::{CrashTracerEvent, EventType, StackDumpKey};
 use vmlinux::task_struct;
 
 use crate::{
-    programs::{CRASH_TRACER_EVENTS, PENDING_SIGNALS, STACK_DUMP_MAP},
+    programs::{
+        CRASH_TRACER_EVENTS, CRASHING_PROCESSES, GO_PROCESSES, PENDING_SIGNALS, STACK_DUMP_MAP,
+        artifact::send_output_tail, claim_exit, is_go, process_comm,
+    },
     vmlinux,
 };
 
+/// Exit status of a Go program that died of a panic, including one the
+/// runtime made of a crash signal (a nil dereference's SIGSEGV) it handled
+/// rather than re-raised.
+const GO_PANIC_EXIT_STATUS: u32 = 2;
+
 pub fn try_handle_sched_process_exit(ctx: TracePointContext) -> Result<(), i64> {
     let task: *const task_struct = unsafe { bpf_get_current_task_btf() as *const task_struct };
-    let boottime = unsafe { (*task).start_boottime };
+    // Fires for every exiting thread; the process is keyed by its leader's start time.
+    let boottime = unsafe { (*(*task).group_leader).start_boottime };
     let pid = unsafe { (*task).tgid } as u32;
     let tid = unsafe { (*task).pid } as u32;
     let exit_code = unsafe { (*task).exit_code } as u32;
+    // `signal->live` is decremented before this tracepoint, so zero means this
+    // is the last thread of the process. Threads exiting together can all
+    // read zero; `claim_exit` below lets only one of them through.
+    let group_dead = unsafe { (*(*task).signal).live.counter } == 0;
+    // Threads captured alongside a crash leave their stack dump for userspace,
+    // which deletes it once read.
+    let keep_dump = unsafe { CRASHING_PROCESSES.get(&pid) }.is_some();
 
-    // determine if the exit code is non-zero
-    if exit_code & 0x7f != 0 {
+    // Killed by a signal, or a Go panic after a crash signal: the signal
+    // pending for this thread is the crash. A Go program may have recovered
+    // from it, which userspace tells from the panic.
+    let killed = exit_code & 0x7f != 0;
+    let go_panic =
+        !killed && (exit_code >> 8) & 0xff == GO_PANIC_EXIT_STATUS && unsafe { is_go(task) };
+    if killed || go_panic {
         let entry = PENDING_SIGNALS.get_ptr_mut(StackDumpKey { pid, tid, boottime });
         match entry {
             Some(entry) => {
@@ -27,6 +51,7 @@ pub fn try_handle_sched_process_exit(ctx: TracePointContext) -> Result<(), i64>
                         unsafe {
                             (*crash_event_ptr).tag = EventType::SignalDeliver;
                             (*crash_event_ptr).payload.signal = *entry;
+                            (*crash_event_ptr).payload.signal.go_panic = go_panic as u32;
                         }
                         // commit the crash for userspace to retrieve.
                         crash_event.submit(0);
@@ -37,6 +62,7 @@ pub fn try_handle_sched_process_exit(ctx: TracePointContext) -> Result<(), i64>
                     }
                 };
             }
+            _ if keep_dump => {}
             _ => {
                 let _ = STACK_DUMP_MAP.remove(StackDumpKey { pid, tid, boottime });
             }
@@ -45,6 +71,17 @@ pub fn try_handle_sched_process_exit(ctx: TracePointContext) -> Result<(), i64>
         let _ = STACK_DUMP_MAP.remove(StackDumpKey { pid, tid, boottime });
     }
 
+    // clean up the maps, regardless of the exit type.
+    let _ = PENDING_SIGNALS.remove(StackDumpKey { pid, tid, boottime });
+
+    if !group_dead || !claim_exit(pid, boottime) {
+        return Ok(());
+    }
+    let _ = CRASHING_PROCESSES.remove(&pid);
+    let _ = GO_PROCESSES.remove(&pid);
+    // Ahead of the exit, so userspace has it at hand.
+    unsafe { send_output_tail(&ctx, pid, boottime) };
+
     match CRASH_TRACER_EVENTS.reserve::<CrashTracerEvent>(0) {
         Some(mut event) => {
             let ptr = event.as_mut_ptr();
@@ -53,6 +90,8 @@ pub fn try_handle_sched_process_exit(ctx: TracePointContext) -> Result<(), i64>
                 (*ptr).payload.exit.pid = pid;
                 (*ptr).payload.exit.exit_code = exit_code;
                 (*ptr).payload.exit.boottime = boottime;
+                (*ptr).payload.exit.timestamp_ns = bpf_ktime_get_ns();
+                (*ptr).payload.exit.cmd = process_comm(task);
             }
 
             event.submit(0);
@@ -62,8 +101,5 @@ pub fn try_handle_sched_process_exit(ctx: TracePointContext) -> Result<(), i64>
         }
     };
 
-    // clean up the maps, regardless of the exit type.
-    let _ = PENDING_SIGNALS.remove(StackDumpKey { pid, tid, boottime });
-
     Ok(())
 }
@@ -1,21 +1,36 @@
 use aya_ebpf::{
-    bindings::{BPF_F_USER_STACK, pt_regs},
+    bindings::{BPF_F_USER_STACK, BPF_NOEXIST, pt_regs},
     helpers::{
-        bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_probe_read_user_buf,
-        generated::{bpf_get_current_task_btf, bpf_ktime_get_ns, bpf_task_pt_regs},
+        bpf_get_current_comm, bpf_get_current_pid_tgid, bpf_probe_read_kernel,
+        bpf_probe_read_user_buf,
+        generated::{
+            bpf_get_current_cgroup_id, bpf_get_current_task_btf, bpf_ktime_get_ns,
+            bpf_task_pt_regs,
+        },
     },
     macros::map,
     maps::{PerCpuArray, StackTrace},
-    programs::TracePointContext,
+    programs::RawTracePointContext,
+};
+use aya_log_ebpf::{info, warn};
+use crash_tracer_common::{
+    CONFIG_ALL_THREADS, CrashTracerEvent, EventType, SIGKILL, SIGSYS, STACK_DUMP_SIZE,
+    SignalDeliverEvent, StackDump, StackDumpKey, THREAD_STACK_DUMP_SIZE, ThreadStateEvent,
 };
-use aya_log_ebpf::info;
-use crash_tracer_common::{SignalDeliverEvent, StackDump, StackDumpKey};
 
 use crate::{
-    programs::{PENDING_SIGNALS, STACK_DUMP_MAP},
-    vmlinux::task_struct,
+    programs::{
+        CRASH_TRACER_EVENTS, CRASHING_PROCESSES, PENDING_SIGNALS, STACK_DUMP_MAP, config,
+        is_crash_signal, namespaces, ns_pid,
+    },
+    vmlinux::{k_sigaction, kernel_siginfo, task_struct},
 };
 
+/// `SIG_DFL`, the handler value for a signal's default action.
+const SIG_DFL: u64 = 0;
+/// `si_code` of a SIGSYS raised by a seccomp filter.
+const SYS_SECCOMP: i32 = 1;
+
 #[map]
 static SIGNAL_DELIVER_STACKS: StackTrace = StackTrace::with_max_entries(1024, 0);
 
@@ -24,20 +39,43 @@ static SIGNAL_DELIVER_STACKS: StackTrace = StackTrace::with_max_entries(1024, 0)
 #[map]
 static STACK_DUMP_SCRATCH: PerCpuArray<StackDump> = PerCpuArray::with_max_entries(1, 0);
 
-pub unsafe fn try_handle_signal_deliver(ctx: TracePointContext) -> Result<(), i64> {
-    // For signal:signal_deliver tracepoint, the signal number is at offset 8
-    // See: /sys/kernel/debug/tracing/events/signal/signal_deliver/format
-    let signal: i32 = unsafe { ctx.read_at(8)? };
+pub unsafe fn try_handle_signal_deliver(ctx: RawTracePointContext) -> Result<(), i64> {
+    // Raw, for the siginfo the classic tracepoint leaves out:
+    // TP_PROTO(int sig, struct kernel_siginfo *info, struct k_sigaction *ka)
+    let signal: i32 = unsafe { ctx.arg(0) };
+    let info: *const kernel_siginfo = unsafe { ctx.arg(1) };
 
-    // Only process crash signals - ignore normal signals like SIGCHLD (17), etc.
-    if !SignalDeliverEvent::is_crash_signal(signal) {
+    let task: *const task_struct = unsafe { bpf_get_current_task_btf() as *const task_struct };
+
+    if signal == SIGKILL {
+        // When a thread dies of a crash signal the kernel SIGKILLs the rest of
+        // the group; each sibling passes through here on its way out.
+        let pid = (bpf_get_current_pid_tgid() >> 32) as u32;
+        if unsafe { CRASHING_PROCESSES.get(&pid) }.is_some() {
+            return unsafe { try_capture_sibling_thread(&ctx, task) };
+        }
+        // Otherwise only an OOM kill counts, and only when asked for. Plain
+        // `kill -9` leaves `oom_mm` unset.
+        if !is_crash_signal(signal) || unsafe { (*(*task).signal).oom_mm.is_null() } {
+            return Ok(());
+        }
+        // The OOM killer SIGKILLs every thread, and several can get here at
+        // once; the first to claim the process records the crash and the
+        // rest are its siblings.
+        let boottime = unsafe { (*(*task).group_leader).start_boottime };
+        if CRASHING_PROCESSES
+            .insert(&pid, &boottime, BPF_NOEXIST as u64)
+            .is_err()
+        {
+            return unsafe { try_capture_sibling_thread(&ctx, task) };
+        }
+    } else if !is_crash_signal(signal) {
+        // Ignore normal signals like SIGCHLD (17) and anything userspace disabled.
         return Ok(());
     }
 
-    // si_code is at offset 16 (check with: sudo cat /sys/kernel/debug/tracing/events/signal/signal_deliver/format)
-    let si_code: i32 = unsafe { ctx.read_at(16)? };
-
-    let task: *const task_struct = unsafe { bpf_get_current_task_btf() as *const task_struct };
+    let si_code: i32 =
+        unsafe { bpf_probe_read_kernel(&raw const (*info).__bindgen_anon_1.si_code)? };
 
     let mut event = SignalDeliverEvent::zeroed();
     unsafe {
@@ -47,17 +85,24 @@ pub unsafe fn try_handle_signal_deliver(ctx: TracePointContext) -> Result<(), i6
         event.signal = signal;
         event.si_code = si_code;
         event.timestamp_ns = bpf_ktime_get_ns();
-        event.boottime = (*task).start_boottime;
+        // The process is identified by its leader's start time; this may be
+        // any thread of it.
+        event.boottime = (*(*task).group_leader).start_boottime;
         event.fault_addr = (*task).thread.cr2;
 
         // Process name - if this fails, just use empty name rather than failing
         event.cmd = bpf_get_current_comm().unwrap_or([0u8; 16]);
 
+        event.cgroup_id = bpf_get_current_cgroup_id();
+        (event.pid_ns, event.mnt_ns) = namespaces(task);
+        event.ns_pid = ns_pid((*task).group_leader);
+        event.ns_tid = ns_pid(task);
+
         event.kernel_stack_id = SIGNAL_DELIVER_STACKS
-            .get_stackid::<TracePointContext>(&ctx, 0)
+            .get_stackid::<RawTracePointContext>(&ctx, 0)
             .unwrap_or(-1);
         event.user_stack_id = SIGNAL_DELIVER_STACKS
-            .get_stackid::<TracePointContext>(&ctx, BPF_F_USER_STACK.into())
+            .get_stackid::<RawTracePointContext>(&ctx, BPF_F_USER_STACK.into())
             .unwrap_or(-1);
 
         let regs = bpf_task_pt_regs(task as *mut _) as *const pt_regs;
@@ -66,6 +111,8 @@ pub unsafe fn try_handle_signal_deliver(ctx: TracePointContext) -> Result<(), i6
         event.rbp = (*regs).rbp;
         event.rflags = (*regs).eflags;
         event.rax = (*regs).rax;
+        event.rbx = (*regs).rbx;
+        event.rcx = (*regs).rcx;
         event.rsi = (*regs).rsi;
         event.rdi = (*regs).rdi;
         event.rdx = (*regs).rdx;
@@ -77,33 +124,26 @@ pub unsafe fn try_handle_signal_deliver(ctx: TracePointContext) -> Result<(), i6
         event.r13 = (*regs).r13;
         event.r14 = (*regs).r14;
         event.r15 = (*regs).r15;
+        // Only a seccomp filter says which syscall; `orig_rax` is not it for a
+        // SIGSYS sent with `kill`, nor for compat syscalls.
+        if signal == SIGSYS && si_code == SYS_SECCOMP {
+            let syscall = &raw const (*info).__bindgen_anon_1._sifields._sigsys._syscall;
+            event.syscall_nr = bpf_probe_read_kernel(syscall).map_or(-1, i64::from);
+        }
+
+        let key = StackDumpKey {
+            pid: event.pid,
+            tid: event.tid,
+            boottime: event.boottime,
+        };
+        capture_stack_dump(&key, event.rsp, STACK_DUMP_SIZE);
 
-        // Capture raw user stack memory.
-        // bpf_probe_read_user is all-or-nothing: if the read extends past
-        // mapped memory it fails entirely. Cascade through decreasing sizes
-        // so we still capture what we can when RSP is near the stack top.
-        if event.rsp != 0 {
-            if let Some(scratch) = STACK_DUMP_SCRATCH.get_ptr_mut(0) {
-                let scratch = &mut *scratch;
-                scratch.rsp = event.rsp;
-                scratch.len = 0;
-                let src = event.rsp as *const u8;
-                if bpf_probe_read_user_buf(src, &mut scratch.data[..16384]).is_ok() {
-                    scratch.len = 16384;
-                } else if bpf_probe_read_user_buf(src, &mut scratch.data[..8192]).is_ok() {
-                    scratch.len = 8192;
-                } else if bpf_probe_read_user_buf(src, &mut scratch.data[..4096]).is_ok() {
-                    scratch.len = 4096;
-                } else if bpf_probe_read_user_buf(src, &mut scratch.data[..2048]).is_ok() {
-                    scratch.len = 2048;
-                }
-                let key = StackDumpKey {
-                    pid: event.pid,
-                    tid: event.tid,
-                    boottime: event.boottime,
-                };
-                let _ = STACK_DUMP_MAP.insert(&key, scratch, 0);
-            }
+        // Also marks the SIGKILLs that follow as part of this crash rather than
+        // new ones. A handler may recover, so only when the kernel is about
+        // to kill the process; a handler that re-raises comes back here with
+        // the default action restored.
+        if signal != SIGKILL && default_action(task, signal) {
+            let _ = CRASHING_PROCESSES.insert(&event.pid, &event.boottime, 0);
         }
 
         info!(&ctx, "crash detected: pid={} sig={}", event.pid, signal);
@@ -119,3 +159,138 @@ pub unsafe fn try_handle_signal_deliver(ctx: TracePointContext) -> Result<(), i6
 
     Ok(())
 }
+
+/// Record registers, user stack and a bounded stack dump of a thread being
+/// killed because another thread of its process crashed.
+unsafe fn try_capture_sibling_thread(
+    ctx: &RawTracePointContext,
+    task: *const task_struct,
+) -> Result<(), i64> {
+    if config(CONFIG_ALL_THREADS) == 0 {
+        return Ok(());
+    }
+
+    let pid_tgid = bpf_get_current_pid_tgid();
+    let pid = (pid_tgid >> 32) as u32;
+    let tid = pid_tgid as u32;
+
+    let Some(crash_boottime) = (unsafe { CRASHING_PROCESSES.get(&pid) }).copied() else {
+        return Ok(());
+    };
+
+    let boottime = unsafe { (*(*task).group_leader).start_boottime };
+    if boottime != crash_boottime {
+        return Ok(());
+    }
+
+    let Some(mut entry) = CRASH_TRACER_EVENTS.reserve::<CrashTracerEvent>(0) else {
+        warn!(ctx, "The buffer is currently full. Cannot capture thread.");
+        return Ok(());
+    };
+
+    let mut thread = ThreadStateEvent {
+        pid,
+        tid,
+        cmd: bpf_get_current_comm().unwrap_or([0u8; 16]),
+        boottime,
+        rip: 0,
+        rsp: 0,
+        rbp: 0,
+        rax: 0,
+        rbx: 0,
+        rcx: 0,
+        rdx: 0,
+        rsi: 0,
+        rdi: 0,
+        r8: 0,
+        r9: 0,
+        r10: 0,
+        r11: 0,
+        r12: 0,
+        r13: 0,
+        r14: 0,
+        r15: 0,
+        rflags: 0,
+        user_stack_id: SIGNAL_DELIVER_STACKS
+            .get_stackid::<RawTracePointContext>(ctx, BPF_F_USER_STACK.into())
+            .unwrap_or(-1),
+    };
+
+    unsafe {
+        let regs = bpf_task_pt_regs(task as *mut _) as *const pt_regs;
+        thread.rip = (*regs).rip;
+        thread.rsp = (*regs).rsp;
+        thread.rbp = (*regs).rbp;
+        thread.rflags = (*regs).eflags;
+        thread.rax = (*regs).rax;
+        thread.rbx = (*regs).rbx;
+        thread.rcx = (*regs).rcx;
+        thread.rsi = (*regs).rsi;
+        thread.rdi = (*regs).rdi;
+        thread.rdx = (*regs).rdx;
+        thread.r8 = (*regs).r8;
+        thread.r9 = (*regs).r9;
+        thread.r10 = (*regs).r10;
+        thread.r11 = (*regs).r11;
+        thread.r12 = (*regs).r12;
+        thread.r13 = (*regs).r13;
+        thread.r14 = (*regs).r14;
+        thread.r15 = (*regs).r15;
+
+        let key = StackDumpKey { pid, tid, boottime };
+        capture_stack_dump(&key, thread.rsp, THREAD_STACK_DUMP_SIZE);
+
+        let ptr = entry.as_mut_ptr();
+        (*ptr).tag = EventType::ThreadState;
+        (*ptr).payload.thread = thread;
+    }
+    entry.submit(0);
+
+    Ok(())
+}
+
+/// Whether `signal` is left to its default action in `task`'s process rather
+/// than caught by a handler (or ignored).
+unsafe fn default_action(task: *const task_struct, signal: i32) -> bool {
+    let index = (signal - 1) as usize;
+    if index >= 64 {
+        return false;
+    }
+    unsafe {
+        let sighand = (*task).sighand;
+        let action = (&raw const (*sighand).action as *const k_sigaction).add(index);
+        bpf_probe_read_kernel(&raw const (*action).sa.sa_handler as *const u64)
+            .is_ok_and(|handler| handler == SIG_DFL)
+    }
+}
+
+/// Copy up to `max_len` bytes of user stack from `rsp` into `STACK_DUMP_MAP`.
+///
+/// bpf_probe_read_user is all-or-nothing: if the read extends past mapped
+/// memory it fails entirely. Cascade through decreasing sizes so we still
+/// capture what we can when RSP is near the stack top.
+unsafe fn capture_stack_dump(key: &StackDumpKey, rsp: u64, max_len: usize) {
+    if rsp == 0 {
+        return;
+    }
+    let Some(scratch) = STACK_DUMP_SCRATCH.get_ptr_mut(0) else {
+        return;
+    };
+    let scratch = unsafe { &mut *scratch };
+    scratch.rsp = rsp;
+    scratch.len = 0;
+    let src = rsp as *const u8;
+    unsafe {
+        if max_len >= 16384 && bpf_probe_read_user_buf(src, &mut scratch.data[..16384]).is_ok() {
+            scratch.len = 16384;
+        } else if max_len >= 8192 && bpf_probe_read_user_buf(src, &mut scratch.data[..8192]).is_ok()
+        {
+            scratch.len = 8192;
+        } else if bpf_probe_read_user_buf(src, &mut scratch.data[..4096]).is_ok() {
+            scratch.len = 4096;
+        } else if bpf_probe_read_user_buf(src, &mut scratch.data[..2048]).is_ok() {
+            scratch.len = 2048;
+        }
+    }
+    let _ = STACK_DUMP_MAP.insert(key, scratch, 0);
+}
@@ -0,0 +1,129 @@
+//! Files runtimes write as they die (JVM `hs_err_pid*.log`, Node diagnostic
+//! reports, ...). The eBPF side matches them by name at `openat` and reports
+//! them on `close`; here they are located and read. What a crashing process
+//! prints to stderr is kept as an artifact too.
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use aya::maps::{HashMap, MapData};
+use crash_tracer_common::{ArtifactFds, ArtifactInfo, CrashOutputEvent, FdTrackKey};
+use log::{debug, warn};
+
+use crate::db::ArtifactData;
+use crate::state::map::ProcessInfo;
+
+/// Largest artifact stored in the database. Bigger files, such as cores from
+/// .NET `createdump`, are recorded by path only; perf maps are cut down to
+/// the entries the crash's frames are in instead.
+const MAX_ARTIFACT_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Stderr kept per crash; fatal signal handlers print a few KiB at most. Of
+/// longer output, from commands watched for their exit, the tail is kept.
+const MAX_CRASH_OUTPUT: usize = 64 * 1024;
+
+/// Name under which a crashing process' stderr is stored.
+const CRASH_OUTPUT_FILENAME: &str = "stderr";
+
+/// Read the artifact `name` (as passed to `openat`) written by `pid`, known
+/// as `info` if tracked.
+///
+/// Relative names are resolved against the live cwd of the process, falling
+/// back to the one from exec time once it has exited. The file is read
+/// below the process' root for processes in another mount namespace, and
+/// only if it belongs to the process; see [`ProcessInfo::open_file`]. The
+/// path stored is the one the process knows it by.
+pub fn collect(pid: u32, name: &[u8], info: Option<&ProcessInfo>) -> ArtifactData {
+    let cwd = info.and_then(|info| info.cwd.as_deref());
+    let name = Path::new(std::str::from_utf8(name).unwrap_or_default());
+    let path = if name.is_absolute() {
+        name.to_path_buf()
+    } else {
+        std::fs::read_link(format!("/proc/{pid}/cwd"))
+            .ok()
+            .or_else(|| cwd.map(PathBuf::from))
+            .map(|cwd| cwd.join(name))
+            .unwrap_or_else(|| name.to_path_buf())
+    };
+
+    let file = match info {
+        Some(info) => info.open_file(&path),
+        None => Err(io::Error::other("process not tracked")),
+    };
+    let content = match file.and_then(|file| Ok((file.metadata()?.len(), file))) {
+        Ok((len, _)) if len > MAX_ARTIFACT_SIZE => {
+            debug!(
+                "artifact {} is {len} bytes, storing path only",
+                path.display()
+            );
+            None
+        }
+        Ok((_, file)) => {
+            let mut content = Vec::new();
+            file.take(MAX_ARTIFACT_SIZE)
+                .read_to_end(&mut content)
+                .ok()
+                .map(|_| content)
+        }
+        Err(e) => {
+            warn!("cannot read artifact {}: {e}", path.display());
+            None
+        }
+    };
+
+    ArtifactData {
+        filename: path
+            .file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        full_path: path.to_string_lossy().into_owned(),
+        content,
+    }
+}
+
+/// Remove and return the artifacts `pid` still had open. A process that dies
+/// mid-write never calls `close`, but its file is as complete as it gets.
+pub fn take_unclosed(
+    fds: &mut HashMap<MapData, FdTrackKey, ArtifactInfo>,
+    index: &mut HashMap<MapData, u32, ArtifactFds>,
+    pid: u32,
+) -> Vec<ArtifactInfo> {
+    let Ok(open) = index.get(&pid, 0) else {
+        return Vec::new();
+    };
+    let _ = index.remove(&pid);
+
+    open.fds
+        .iter()
+        .filter(|fd| **fd >= 0)
+        .filter_map(|fd| {
+            let key = FdTrackKey {
+                pid,
+                fd: *fd as u32,
+            };
+            let info = fds.get(&key, 0).ok();
+            let _ = fds.remove(&key);
+            info
+        })
+        .collect()
+}
+
+/// Append a chunk of stderr, written after the crash signal or by a command
+/// watched for its exit, to `output`, dropping the oldest output beyond
+/// [`MAX_CRASH_OUTPUT`].
+pub fn append_output(output: &mut VecDeque<u8>, event: &CrashOutputEvent) {
+    let len = (event.len as usize).min(event.data.len());
+    output.extend(&event.data[..len]);
+    let excess = output.len().saturating_sub(MAX_CRASH_OUTPUT);
+    output.drain(..excess);
+}
+
+/// The stderr a crashing process wrote, as an artifact.
+pub fn crash_output(pid: u32, output: Vec<u8>) -> ArtifactData {
+    ArtifactData {
+        filename: CRASH_OUTPUT_FILENAME.to_string(),
+        full_path: format!("/proc/{pid}/fd/2"),
+        content: Some(output),
+    }
+}
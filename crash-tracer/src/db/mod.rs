@@ -1,19 +1,36 @@
 use aya::maps::stack_trace::StackTrace;
-use crash_tracer_common::{SignalDeliverEvent, StackDump};
+use crash_tracer_common::{SchedExitEvent, SignalDeliverEvent, StackDump};
 use sqlx::Row;
 use sqlx::SqlitePool;
+use sqlx::sqlite::SqliteRow;
 
 use crate::db::query::insert::INSERT_ARTIFACT;
 use crate::{
-    db::query::insert::{
-        INSERT_CRASHES, INSERT_PROCESS, INSERT_PROCESS_MAPS, INSERT_STACK_DUMP, INSERT_STACK_FRAMES,
+    db::query::{
+        insert::{
+            INSERT_CRASHES, INSERT_EXIT_CRASH, INSERT_KERNEL_STACK_FRAMES, INSERT_MODULE,
+            INSERT_PROCESS, INSERT_PROCESS_MAPS, INSERT_PROCESS_RUNTIME, INSERT_STACK_DUMP,
+            INSERT_STACK_FRAMES, INSERT_THREAD, INSERT_THREAD_STACK_FRAMES, UPDATE_PROCESS_BOOTTIME,
+            UPDATE_PROCESS_CONTAINER, UPSERT_CRASH_GROUP,
+        },
+        select::SELECT_CRASHES,
     },
-    state::map::ProcessInfo,
+    signature::CrashSignature,
+    state::{
+        container::ContainerInfo,
+        map::{DetectedRuntime, ModuleInfo, ProcessInfo, RuntimeKind, runtime_names},
+    },
+    symbolize::kernel::KernelFrame,
 };
 
 mod query;
 mod schema;
 
+/// `stack_frames.source` for frames from the BPF stack map (frame-pointer walk).
+pub const FRAME_SOURCE_BPF: &str = "bpf";
+/// `stack_frames.source` for frames recovered by replaying DWARF CFI over the stack dump.
+pub const FRAME_SOURCE_CFI: &str = "cfi";
+
 pub struct Registers {
     pub rip: u64,
     pub rsp: u64,
@@ -41,20 +58,106 @@ pub struct ArtifactData {
     pub content: Option<Vec<u8>>,
 }
 
+/// A thread of the crashing process other than the one that took the signal.
+pub struct ThreadData {
+    pub tid: u32,
+    pub cmd: String,
+    pub registers: Registers,
+    pub stack_frames: Vec<u64>,
+    pub unwound_frames: Vec<u64>,
+    pub stack_dump: Option<(u64, Vec<u8>)>, // (rsp, data)
+}
+
+/// What made a process count as crashed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrashKind {
+    /// A fatal signal; the crash has registers and stacks.
+    Signal,
+    /// An exit status matching `--exit-codes` for one of `--exit-commands`.
+    /// Nothing is known but the status and what the process left behind.
+    Exit,
+}
+
+impl CrashKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CrashKind::Signal => "signal",
+            CrashKind::Exit => "exit",
+        }
+    }
+
+    /// `crashes.kind`, absent in databases written before the column existed.
+    fn from_row(row: &SqliteRow) -> Self {
+        match row.try_get::<String, _>("kind").as_deref() {
+            Ok("exit") => CrashKind::Exit,
+            _ => CrashKind::Signal,
+        }
+    }
+}
+
+pub struct CrashGroup {
+    pub signature: String,
+    pub count: i64,
+    pub first_seen: String,
+    pub last_seen: String,
+    /// Stack the signature was computed from: `cfi` or `bpf`.
+    pub source: Option<String>,
+}
+
 pub struct CrashReportData {
+    pub kind: CrashKind,
     pub cmd: String,
     pub pid: u32,
     pub tid: u32,
+    pub ns_pid: Option<u32>, // pid and tid inside the process' pid namespace
+    pub ns_tid: Option<u32>,
     pub signal: i32,
     pub si_code: i32,
     pub fault_addr: u64,
+    pub syscall_nr: Option<i64>, // syscall a seccomp filter rejected, SIGSYS only
     pub exit_code: Option<u32>,
     pub runtime: String,
+    pub runtimes: Vec<DetectedRuntime>, // empty for native code and older databases
+    pub container: ContainerInfo, // empty outside containers and for older databases
+    pub created_at: String, // UTC, `YYYY-MM-DD HH:MM:SS`
     pub registers: Registers,   // sub-struct with all register values
     pub stack_frames: Vec<u64>, // instruction pointers in order
+    pub unwound_frames: Vec<u64>, // same, recovered from the stack dump via CFI
+    pub kernel_stack: Vec<KernelFrame>,
     pub stack_dump: Option<(u64, Vec<u8>)>, // (rsp, data)
     pub memory_maps: Vec<String>,
+    pub modules: Vec<ModuleInfo>,
     pub artifacts: Vec<ArtifactData>,
+    pub group: Option<CrashGroup>,
+    pub threads: Vec<ThreadData>,
+}
+
+/// Criteria for [`CrashDb::list_crashes`]; `None` matches everything.
+#[derive(Default)]
+pub struct CrashFilter {
+    pub cmd: Option<String>,
+    pub signal: Option<i32>,
+    pub runtime: Option<String>,
+    /// Inclusive lower bound on `created_at`, as `YYYY-MM-DD HH:MM:SS` UTC.
+    pub since: Option<String>,
+    /// Exclusive upper bound on `created_at`, same format.
+    pub until: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// One row of a crash listing.
+pub struct CrashSummary {
+    pub id: i64,
+    pub created_at: String,
+    pub cmd: String,
+    pub pid: u32,
+    pub status: String,
+    pub kind: CrashKind,
+    pub signal: i32,
+    pub si_code: i32,
+    pub exit_code: Option<u32>,
+    pub runtime: String,
+    pub signature: Option<String>,
 }
 
 pub struct CrashDb {
@@ -70,8 +173,35 @@ impl CrashDb {
         Ok(db)
     }
 
+    /// Open an existing database without creating or migrating it, so crashes
+    /// can be inspected by users who cannot write to the daemon's files.
+    pub async fn open_read_only(db_path: &std::path::Path) -> anyhow::Result<Self> {
+        if !db_path.exists() {
+            anyhow::bail!("no crash database at {}", db_path.display());
+        }
+        let url = format!("sqlite:{}?mode=ro", db_path.display());
+        let pool = SqlitePool::connect(&url).await?;
+        Ok(Self { pool })
+    }
+
     async fn run_migrations(&self) -> anyhow::Result<()> {
         sqlx::raw_sql(schema::SCHEMA).execute(&self.pool).await?;
+
+        for (table, column, definition) in schema::ADDED_COLUMNS {
+            let exists = sqlx::query("SELECT 1 FROM pragma_table_info($1) WHERE name = $2")
+                .bind(table)
+                .bind(column)
+                .fetch_optional(&self.pool)
+                .await?
+                .is_some();
+            if !exists {
+                let sql = format!("ALTER TABLE {table} ADD COLUMN {column} {definition}");
+                sqlx::raw_sql(&sql).execute(&self.pool).await?;
+            }
+        }
+        sqlx::raw_sql(schema::ADDED_INDEXES)
+            .execute(&self.pool)
+            .await?;
         Ok(())
     }
 
@@ -81,9 +211,20 @@ impl CrashDb {
         sqlx::query(INSERT_PROCESS)
             .bind(info.pid as i64)
             .bind(info.boottime as i64)
-            .bind(info.runtime.to_string())
+            .bind(runtime_names(&info.runtimes))
             .bind(&info.cwd)
             .bind(&info.cmdline)
+            .bind(info.container.cgroup_id as i64)
+            .bind(&info.container.cgroup_path)
+            .bind(&info.container.container_id)
+            .bind(&info.container.container_runtime)
+            .bind(&info.container.pod_uid)
+            .bind(&info.container.pod_name)
+            .bind(&info.container.pod_namespace)
+            .bind(info.container.pid_ns as i64)
+            .bind(info.container.mnt_ns as i64)
+            .bind(info.ns_pid as i64)
+            .bind(&info.exe)
             .execute(&mut *tx)
             .await?;
 
@@ -109,15 +250,87 @@ impl CrashDb {
                 .await?;
         }
 
+        sqlx::query("DELETE FROM modules WHERE process_id=$1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        for module in &info.modules {
+            sqlx::query(INSERT_MODULE)
+                .bind(id)
+                .bind(&module.path)
+                .bind(&module.build_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query("DELETE FROM process_runtimes WHERE process_id=$1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        for runtime in &info.runtimes {
+            sqlx::query(INSERT_PROCESS_RUNTIME)
+                .bind(id)
+                .bind(runtime.kind.to_string())
+                .bind(&runtime.version)
+                .bind(&runtime.path)
+                .bind(&runtime.module)
+                .execute(&mut *tx)
+                .await?;
+        }
+
         tx.commit().await?;
         Ok(id)
     }
 
+    /// Move a process found running at startup from the start time it was
+    /// `seeded` under to the exact one in `info`, once an event told it.
+    pub async fn settle_process(&self, seeded: u64, info: &ProcessInfo) -> anyhow::Result<()> {
+        let moved = sqlx::query(UPDATE_PROCESS_BOOTTIME)
+            .bind(info.pid as i64)
+            .bind(seeded as i64)
+            .bind(info.boottime as i64)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        if moved == 0 {
+            // Recorded under its exact start time already, by an earlier run
+            // that saw its exec, or never recorded at all.
+            self.cleanup_process(info.pid, seeded).await?;
+            self.insert_process(info).await?;
+        }
+        Ok(())
+    }
+
+    /// Record the container `info` is in now, after it moved since exec.
+    pub async fn update_process_container(&self, info: &ProcessInfo) -> anyhow::Result<()> {
+        let container = &info.container;
+        sqlx::query(UPDATE_PROCESS_CONTAINER)
+            .bind(info.pid as i64)
+            .bind(info.boottime as i64)
+            .bind(container.cgroup_id as i64)
+            .bind(&container.cgroup_path)
+            .bind(&container.container_id)
+            .bind(&container.container_runtime)
+            .bind(&container.pod_uid)
+            .bind(&container.pod_name)
+            .bind(&container.pod_namespace)
+            .bind(container.pid_ns as i64)
+            .bind(container.mnt_ns as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn insert_crash(
         &self,
         crash: &SignalDeliverEvent,
         stack_trace: Option<&StackTrace>,
+        unwound_frames: &[u64],
+        kernel_stack: &[KernelFrame],
         stack_dump: Option<&StackDump>,
+        signature: &CrashSignature,
     ) -> anyhow::Result<Option<i64>> {
         let mut tx = self.pool.begin().await?;
 
@@ -135,6 +348,24 @@ impl CrashDb {
         };
 
         let id: i64 = result.try_get("id")?;
+        let cmd = std::str::from_utf8(&crash.cmd)
+            .unwrap_or("<unknown>")
+            .trim_end_matches('\0');
+
+        sqlx::query(UPSERT_CRASH_GROUP)
+            .bind(&signature.hash)
+            .bind(crash.signal)
+            .bind(crash.si_code)
+            .bind(cmd)
+            .bind(signature.frames.join(" <- "))
+            .execute(&mut *tx)
+            .await?;
+
+        let group_id: i64 = sqlx::query("SELECT id FROM crash_groups WHERE signature=$1")
+            .bind(&signature.hash)
+            .fetch_one(&mut *tx)
+            .await?
+            .try_get("id")?;
 
         let crash_id = sqlx::query(INSERT_CRASHES)
             .bind(id)
@@ -143,11 +374,7 @@ impl CrashDb {
             .bind(crash.fault_addr as i64)
             .bind(crash.timestamp_ns as i64)
             .bind(crash.tid)
-            .bind(
-                std::str::from_utf8(&crash.cmd)
-                    .unwrap_or("<unknown>")
-                    .trim_end_matches('\0'),
-            )
+            .bind(cmd)
             .bind(None::<i64>)
             .bind(crash.rip as i64)
             .bind(crash.rsp as i64)
@@ -170,6 +397,10 @@ impl CrashDb {
             .bind(crash.kernel_stack_id)
             .bind(crash.user_stack_id)
             .bind(crash.boottime as i64)
+            .bind(group_id)
+            .bind((crash.syscall_nr >= 0).then_some(crash.syscall_nr))
+            .bind((crash.ns_tid != 0).then_some(crash.ns_tid as i64))
+            .bind(signature.source)
             .execute(&mut *tx)
             .await?
             .last_insert_rowid();
@@ -180,11 +411,34 @@ impl CrashDb {
                     .bind(crash_id)
                     .bind(idx as i64)
                     .bind(frame.ip as i64)
+                    .bind(FRAME_SOURCE_BPF)
                     .execute(&mut *tx)
                     .await?;
             }
         }
 
+        for (idx, ip) in unwound_frames.iter().enumerate() {
+            sqlx::query(INSERT_STACK_FRAMES)
+                .bind(crash_id)
+                .bind(idx as i64)
+                .bind(*ip as i64)
+                .bind(FRAME_SOURCE_CFI)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        for (idx, frame) in kernel_stack.iter().enumerate() {
+            sqlx::query(INSERT_KERNEL_STACK_FRAMES)
+                .bind(crash_id)
+                .bind(idx as i64)
+                .bind(frame.ip as i64)
+                .bind(&frame.symbol)
+                .bind(frame.offset as i64)
+                .bind(&frame.module)
+                .execute(&mut *tx)
+                .await?;
+        }
+
         if let Some(dump) = stack_dump {
             sqlx::query(INSERT_STACK_DUMP)
                 .bind(crash_id)
@@ -200,6 +454,147 @@ impl CrashDb {
         Ok(Some(crash_id))
     }
 
+    /// Record the exit of a process as a pending crash, unless a signal
+    /// already made it one.
+    pub async fn insert_exit_crash(
+        &self,
+        exit: &SchedExitEvent,
+        signature: &CrashSignature,
+    ) -> anyhow::Result<Option<i64>> {
+        let mut tx = self.pool.begin().await?;
+
+        let Some(result) = sqlx::query("SELECT id FROM processes WHERE pid=$1 AND boottime=$2")
+            .bind(exit.pid as i64)
+            .bind(exit.boottime as i64)
+            .fetch_optional(&mut *tx)
+            .await?
+        else {
+            log::warn!(
+                "No process found for exit pid={}, likely missed exec event",
+                exit.pid
+            );
+            return Ok(None);
+        };
+
+        let id: i64 = result.try_get("id")?;
+
+        if sqlx::query("SELECT id FROM crashes WHERE process_id=$1")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some()
+        {
+            return Ok(None);
+        }
+
+        let cmd = std::str::from_utf8(&exit.cmd)
+            .unwrap_or("<unknown>")
+            .trim_end_matches('\0');
+
+        sqlx::query(UPSERT_CRASH_GROUP)
+            .bind(&signature.hash)
+            .bind(0)
+            .bind(0)
+            .bind(cmd)
+            .bind(signature.frames.join(" <- "))
+            .execute(&mut *tx)
+            .await?;
+
+        let group_id: i64 = sqlx::query("SELECT id FROM crash_groups WHERE signature=$1")
+            .bind(&signature.hash)
+            .fetch_one(&mut *tx)
+            .await?
+            .try_get("id")?;
+
+        let crash_id = sqlx::query(INSERT_EXIT_CRASH)
+            .bind(id)
+            .bind(exit.timestamp_ns as i64)
+            .bind(exit.pid)
+            .bind(cmd)
+            .bind(exit.boottime as i64)
+            .bind(group_id)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+
+        tx.commit().await?;
+
+        Ok(Some(crash_id))
+    }
+
+    /// Attach the other threads captured for the pending crash of a process.
+    pub async fn insert_threads(
+        &self,
+        pid: u32,
+        boottime: u64,
+        threads: &[ThreadData],
+    ) -> anyhow::Result<()> {
+        let Some(crash_row) = sqlx::query(
+            "SELECT c.id FROM crashes c JOIN processes p ON c.process_id = p.id
+             WHERE p.pid = $1 AND p.boottime = $2 AND c.status = 'pending'",
+        )
+        .bind(pid as i64)
+        .bind(boottime as i64)
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(());
+        };
+        let crash_id: i64 = crash_row.try_get("id")?;
+
+        let mut tx = self.pool.begin().await?;
+
+        for thread in threads {
+            let r = &thread.registers;
+            let thread_id = sqlx::query(INSERT_THREAD)
+                .bind(crash_id)
+                .bind(thread.tid)
+                .bind(&thread.cmd)
+                .bind(r.rip as i64)
+                .bind(r.rsp as i64)
+                .bind(r.rbp as i64)
+                .bind(r.rax as i64)
+                .bind(r.rbx as i64)
+                .bind(r.rcx as i64)
+                .bind(r.rdx as i64)
+                .bind(r.rsi as i64)
+                .bind(r.rdi as i64)
+                .bind(r.r8 as i64)
+                .bind(r.r9 as i64)
+                .bind(r.r10 as i64)
+                .bind(r.r11 as i64)
+                .bind(r.r12 as i64)
+                .bind(r.r13 as i64)
+                .bind(r.r14 as i64)
+                .bind(r.r15 as i64)
+                .bind(r.rflags as i64)
+                .bind(thread.stack_dump.as_ref().map(|(rsp, _)| *rsp as i64))
+                .bind(thread.stack_dump.as_ref().map(|(_, data)| data.as_slice()))
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+
+            let frames = [
+                (FRAME_SOURCE_BPF, &thread.stack_frames),
+                (FRAME_SOURCE_CFI, &thread.unwound_frames),
+            ];
+            for (source, ips) in frames {
+                for (idx, ip) in ips.iter().enumerate() {
+                    sqlx::query(INSERT_THREAD_STACK_FRAMES)
+                        .bind(thread_id)
+                        .bind(idx as i64)
+                        .bind(*ip as i64)
+                        .bind(source)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
     pub async fn complete_crash(
         &self,
         pid: u32,
@@ -234,16 +629,24 @@ impl CrashDb {
             .execute(&self.pool)
             .await?;
 
+        sqlx::query("UPDATE artifacts SET crash_id = $1 WHERE process_id = $2 AND crash_id IS NULL")
+            .bind(crash_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
         Ok(Some(crash_id))
     }
 
+    /// Store an artifact written by a process, replacing an earlier copy of
+    /// the same file. It is attached to the process' crash if there already
+    /// is one, otherwise [`Self::complete_crash`] attaches it later; runtimes
+    /// typically write theirs before the fatal signal.
     pub async fn insert_artifact(
         &self,
-        pid: u64,
+        pid: u32,
         boottime: u64,
-        filename: String,
-        full_path: String,
-        content: Option<&[u8]>,
+        artifact: &ArtifactData,
     ) -> anyhow::Result<()> {
         let Some(proc_row) = sqlx::query("SELECT id FROM processes WHERE pid=$1 AND boottime=$2")
             .bind(pid as i64)
@@ -256,33 +659,69 @@ impl CrashDb {
 
         let process_id: i64 = proc_row.try_get("id")?;
 
-        let Some(crash_row) = sqlx::query("SELECT id FROM crashes WHERE process_id=$1")
+        let crash_id: Option<i64> = sqlx::query("SELECT id FROM crashes WHERE process_id=$1")
             .bind(process_id)
             .fetch_optional(&self.pool)
             .await?
-        else {
-            return Ok(());
-        };
+            .map(|row| row.try_get("id"))
+            .transpose()?;
 
-        let crash_id: i64 = crash_row.try_get("id")?;
+        sqlx::query("DELETE FROM artifacts WHERE process_id=$1 AND full_path=$2")
+            .bind(process_id)
+            .bind(&artifact.full_path)
+            .execute(&self.pool)
+            .await?;
 
         sqlx::query(INSERT_ARTIFACT)
             .bind(crash_id)
             .bind(process_id)
-            .bind(filename)
-            .bind(full_path)
-            .bind(content)
+            .bind(&artifact.filename)
+            .bind(&artifact.full_path)
+            .bind(artifact.content.as_deref())
             .execute(&self.pool)
             .await?;
 
         Ok(())
     }
 
+    pub async fn list_crashes(&self, filter: &CrashFilter) -> anyhow::Result<Vec<CrashSummary>> {
+        let rows = sqlx::query(SELECT_CRASHES)
+            .bind(&filter.cmd)
+            .bind(filter.signal)
+            .bind(&filter.runtime)
+            .bind(&filter.since)
+            .bind(&filter.until)
+            .bind(filter.limit.unwrap_or(-1))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|r| {
+                Ok(CrashSummary {
+                    id: r.try_get("id")?,
+                    created_at: r.try_get("created_at")?,
+                    cmd: r.try_get("cmd")?,
+                    pid: r.try_get::<i64, _>("pid")? as u32,
+                    status: r.try_get("status")?,
+                    kind: CrashKind::from_row(r),
+                    signal: r.try_get("signal")?,
+                    si_code: r.try_get("si_code")?,
+                    exit_code: r.try_get::<Option<i64>, _>("exit_code")?.map(|c| c as u32),
+                    runtime: r.try_get("runtime")?,
+                    signature: r.try_get("signature")?,
+                })
+            })
+            .collect::<Result<Vec<_>, sqlx::Error>>()?)
+    }
+
     pub async fn get_crash_report_data(&self, crash_id: i64) -> anyhow::Result<CrashReportData> {
         let crash_row = sqlx::query(
-            "SELECT c.*, p.runtime, p.cwd, p.cmdline, p.pid as process_pid
+            "SELECT c.*, p.runtime, p.cwd, p.cmdline, p.pid as process_pid,
+                    g.signature, g.count as group_count, g.first_seen, g.last_seen
              FROM crashes c
              JOIN processes p ON c.process_id = p.id
+             LEFT JOIN crash_groups g ON c.group_id = g.id
              WHERE c.id = $1",
         )
         .bind(crash_id)
@@ -292,36 +731,28 @@ impl CrashDb {
 
         let process_id: i64 = crash_row.try_get("process_id")?;
 
-        let registers = Registers {
-            rip: crash_row.try_get::<i64, _>("rip")? as u64,
-            rsp: crash_row.try_get::<i64, _>("rsp")? as u64,
-            rbp: crash_row.try_get::<i64, _>("rbp")? as u64,
-            rax: crash_row.try_get::<i64, _>("rax")? as u64,
-            rbx: crash_row.try_get::<i64, _>("rbx")? as u64,
-            rcx: crash_row.try_get::<i64, _>("rcx")? as u64,
-            rdx: crash_row.try_get::<i64, _>("rdx")? as u64,
-            rsi: crash_row.try_get::<i64, _>("rsi")? as u64,
-            rdi: crash_row.try_get::<i64, _>("rdi")? as u64,
-            r8: crash_row.try_get::<i64, _>("r8")? as u64,
-            r9: crash_row.try_get::<i64, _>("r9")? as u64,
-            r10: crash_row.try_get::<i64, _>("r10")? as u64,
-            r11: crash_row.try_get::<i64, _>("r11")? as u64,
-            r12: crash_row.try_get::<i64, _>("r12")? as u64,
-            r13: crash_row.try_get::<i64, _>("r13")? as u64,
-            r14: crash_row.try_get::<i64, _>("r14")? as u64,
-            r15: crash_row.try_get::<i64, _>("r15")? as u64,
-            rflags: crash_row.try_get::<i64, _>("rflags")? as u64,
-        };
+        let registers = registers_from_row(&crash_row)?;
 
-        let frame_rows =
-            sqlx::query("SELECT ip FROM stack_frames WHERE crash_id = $1 ORDER BY frame_index ASC")
-                .bind(crash_id)
-                .fetch_all(&self.pool)
-                .await?;
+        let stack_frames = self.get_stack_frames(crash_id, FRAME_SOURCE_BPF).await?;
+        let unwound_frames = self.get_stack_frames(crash_id, FRAME_SOURCE_CFI).await?;
+
+        let kernel_frame_rows = sqlx::query(
+            "SELECT ip, symbol, symbol_offset, module FROM kernel_stack_frames WHERE crash_id = $1 ORDER BY frame_index ASC",
+        )
+        .bind(crash_id)
+        .fetch_all(&self.pool)
+        .await?;
 
-        let stack_frames: Vec<u64> = frame_rows
+        let kernel_stack: Vec<KernelFrame> = kernel_frame_rows
             .iter()
-            .map(|r| Ok(r.try_get::<i64, _>("ip")? as u64))
+            .map(|r| {
+                Ok(KernelFrame {
+                    ip: r.try_get::<i64, _>("ip")? as u64,
+                    symbol: r.try_get("symbol")?,
+                    offset: r.try_get::<i64, _>("symbol_offset")? as u64,
+                    module: r.try_get("module")?,
+                })
+            })
             .collect::<Result<Vec<_>, sqlx::Error>>()?;
 
         let stack_dump = match sqlx::query(
@@ -352,6 +783,53 @@ impl CrashDb {
             .map(|r| r.try_get("content"))
             .collect::<Result<Vec<_>, _>>()?;
 
+        let module_rows =
+            sqlx::query("SELECT path, build_id FROM modules WHERE process_id = $1 ORDER BY id ASC")
+                .bind(process_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        let modules: Vec<ModuleInfo> = module_rows
+            .iter()
+            .map(|r| {
+                Ok(ModuleInfo {
+                    path: r.try_get("path")?,
+                    build_id: r.try_get("build_id")?,
+                })
+            })
+            .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+        // Absent in databases written before the table existed and opened
+        // read-only since.
+        let runtime_rows = sqlx::query(
+            "SELECT * FROM process_runtimes WHERE process_id = $1 ORDER BY id ASC",
+        )
+        .bind(process_id)
+        .fetch_all(&self.pool)
+        .await
+        .unwrap_or_default();
+
+        let mut runtimes = Vec::new();
+        for r in &runtime_rows {
+            // Written by a newer version that knows more runtimes.
+            let Some(kind) = RuntimeKind::from_name(&r.try_get::<String, _>("kind")?) else {
+                continue;
+            };
+            runtimes.push(DetectedRuntime {
+                kind,
+                version: r.try_get("version")?,
+                path: r.try_get("path")?,
+                // Absent in databases written before the column existed.
+                module: r.try_get("module").ok().flatten(),
+            });
+        }
+
+        let process_row = sqlx::query("SELECT * FROM processes WHERE id = $1")
+            .bind(process_id)
+            .fetch_one(&self.pool)
+            .await?;
+        let container = container_from_row(&process_row);
+
         let artifact_rows =
             sqlx::query("SELECT filename, full_path, content FROM artifacts WHERE crash_id = $1")
                 .bind(crash_id)
@@ -369,25 +847,137 @@ impl CrashDb {
             })
             .collect::<Result<Vec<_>, sqlx::Error>>()?;
 
+        let threads = self.get_threads(crash_id).await?;
+
         let exit_code: Option<i32> = crash_row.try_get("exit_code").ok();
 
+        let group = match crash_row.try_get::<Option<i64>, _>("group_id")? {
+            Some(_) => Some(CrashGroup {
+                signature: crash_row.try_get("signature")?,
+                count: crash_row.try_get("group_count")?,
+                first_seen: crash_row.try_get("first_seen")?,
+                last_seen: crash_row.try_get("last_seen")?,
+                source: crash_row.try_get("signature_source").ok().flatten(),
+            }),
+            None => None,
+        };
+
         Ok(CrashReportData {
+            kind: CrashKind::from_row(&crash_row),
             cmd: crash_row.try_get("cmd")?,
             pid: crash_row.try_get::<i32, _>("process_pid")? as u32,
             tid: crash_row.try_get::<i32, _>("tid")? as u32,
+            // Both absent in databases written before the columns existed.
+            ns_pid: process_row
+                .try_get::<Option<i64>, _>("ns_pid")
+                .ok()
+                .flatten()
+                .map(|pid| pid as u32),
+            ns_tid: crash_row
+                .try_get::<Option<i64>, _>("ns_tid")
+                .ok()
+                .flatten()
+                .map(|tid| tid as u32),
             signal: crash_row.try_get("signal")?,
             si_code: crash_row.try_get("si_code")?,
             fault_addr: crash_row.try_get::<i64, _>("fault_addr")? as u64,
+            // Absent in databases written before the column existed.
+            syscall_nr: crash_row.try_get("syscall_nr").ok().flatten(),
             exit_code: exit_code.map(|c| c as u32),
             runtime: crash_row.try_get("runtime")?,
+            runtimes,
+            container,
+            created_at: crash_row.try_get("created_at")?,
             registers,
             stack_frames,
+            unwound_frames,
+            kernel_stack,
             stack_dump,
             memory_maps,
+            modules,
             artifacts,
+            group,
+            threads,
         })
     }
 
+    async fn get_threads(&self, crash_id: i64) -> anyhow::Result<Vec<ThreadData>> {
+        let rows = sqlx::query("SELECT * FROM threads WHERE crash_id = $1 ORDER BY tid ASC")
+            .bind(crash_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut threads = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let thread_id: i64 = row.try_get("id")?;
+            let stack_rsp: Option<i64> = row.try_get("stack_rsp")?;
+            let stack_data: Option<Vec<u8>> = row.try_get("stack_data")?;
+            threads.push(ThreadData {
+                tid: row.try_get::<i64, _>("tid")? as u32,
+                cmd: row.try_get("cmd")?,
+                registers: registers_from_row(row)?,
+                stack_frames: self
+                    .get_thread_stack_frames(thread_id, FRAME_SOURCE_BPF)
+                    .await?,
+                unwound_frames: self
+                    .get_thread_stack_frames(thread_id, FRAME_SOURCE_CFI)
+                    .await?,
+                stack_dump: stack_rsp.zip(stack_data).map(|(rsp, data)| (rsp as u64, data)),
+            });
+        }
+        Ok(threads)
+    }
+
+    async fn get_thread_stack_frames(
+        &self,
+        thread_id: i64,
+        source: &str,
+    ) -> anyhow::Result<Vec<u64>> {
+        let frame_rows = sqlx::query(
+            "SELECT ip FROM thread_stack_frames WHERE thread_id = $1 AND source = $2 ORDER BY frame_index ASC",
+        )
+        .bind(thread_id)
+        .bind(source)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(frame_rows
+            .iter()
+            .map(|r| Ok(r.try_get::<i64, _>("ip")? as u64))
+            .collect::<Result<Vec<_>, sqlx::Error>>()?)
+    }
+
+    async fn get_stack_frames(&self, crash_id: i64, source: &str) -> anyhow::Result<Vec<u64>> {
+        let frame_rows = sqlx::query(
+            "SELECT ip FROM stack_frames WHERE crash_id = $1 AND source = $2 ORDER BY frame_index ASC",
+        )
+        .bind(crash_id)
+        .bind(source)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(frame_rows
+            .iter()
+            .map(|r| Ok(r.try_get::<i64, _>("ip")? as u64))
+            .collect::<Result<Vec<_>, sqlx::Error>>()?)
+    }
+
+    /// Every user-space instruction pointer of a crash and its threads,
+    /// sorted and deduplicated.
+    pub async fn crash_ips(&self, crash_id: i64) -> anyhow::Result<Vec<u64>> {
+        let frame_rows = sqlx::query(
+            "SELECT ip FROM stack_frames WHERE crash_id = $1 UNION SELECT ip FROM thread_stack_frames WHERE thread_id IN (SELECT id FROM threads WHERE crash_id = $1) ORDER BY ip",
+        )
+        .bind(crash_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(frame_rows
+            .iter()
+            .map(|r| Ok(r.try_get::<i64, _>("ip")? as u64))
+            .collect::<Result<Vec<_>, sqlx::Error>>()?)
+    }
+
     pub async fn cleanup_process(&self, pid: u32, boottime: u64) -> anyhow::Result<()> {
         let result = sqlx::query("SELECT id FROM processes WHERE pid=$1 AND boottime=$2")
             .bind(pid as i64)
@@ -416,6 +1006,20 @@ impl CrashDb {
                 .bind(crash_id)
                 .execute(&mut *tx)
                 .await?;
+            sqlx::query("DELETE FROM kernel_stack_frames WHERE crash_id=$1")
+                .bind(crash_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                "DELETE FROM thread_stack_frames WHERE thread_id IN (SELECT id FROM threads WHERE crash_id=$1)",
+            )
+            .bind(crash_id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("DELETE FROM threads WHERE crash_id=$1")
+                .bind(crash_id)
+                .execute(&mut *tx)
+                .await?;
             sqlx::query("DELETE FROM stack_dumps WHERE crash_id=$1")
                 .bind(crash_id)
                 .execute(&mut *tx)
@@ -426,6 +1030,12 @@ impl CrashDb {
                 .await?;
         }
 
+        // Artifacts of a process that never crashed have no crash_id.
+        sqlx::query("DELETE FROM artifacts WHERE process_id=$1")
+            .bind(process_id)
+            .execute(&mut *tx)
+            .await?;
+
         sqlx::query("DELETE FROM crashes WHERE process_id=$1")
             .bind(process_id)
             .execute(&mut *tx)
@@ -436,6 +1046,16 @@ impl CrashDb {
             .execute(&mut *tx)
             .await?;
 
+        sqlx::query("DELETE FROM modules WHERE process_id=$1")
+            .bind(process_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM process_runtimes WHERE process_id=$1")
+            .bind(process_id)
+            .execute(&mut *tx)
+            .await?;
+
         sqlx::query("DELETE FROM processes WHERE id=$1")
             .bind(process_id)
             .execute(&mut *tx)
@@ -445,3 +1065,45 @@ impl CrashDb {
         Ok(())
     }
 }
+
+/// The container columns of a `processes` row, absent in databases written
+/// before they existed.
+fn container_from_row(row: &SqliteRow) -> ContainerInfo {
+    let get = |column: &str| row.try_get::<Option<String>, _>(column).ok().flatten();
+    let get_int = |column: &str| row.try_get::<Option<i64>, _>(column).ok().flatten();
+    ContainerInfo {
+        cgroup_id: get_int("cgroup_id").unwrap_or(0) as u64,
+        cgroup_path: get("cgroup_path"),
+        container_id: get("container_id"),
+        container_runtime: get("container_runtime"),
+        pod_uid: get("pod_uid"),
+        pod_name: get("pod_name"),
+        pod_namespace: get("pod_namespace"),
+        pid_ns: get_int("pid_ns").unwrap_or(0) as u32,
+        mnt_ns: get_int("mnt_ns").unwrap_or(0) as u32,
+    }
+}
+
+/// Register columns shared by `crashes` and `threads`.
+fn registers_from_row(row: &SqliteRow) -> Result<Registers, sqlx::Error> {
+    Ok(Registers {
+        rip: row.try_get::<i64, _>("rip")? as u64,
+        rsp: row.try_get::<i64, _>("rsp")? as u64,
+        rbp: row.try_get::<i64, _>("rbp")? as u64,
+        rax: row.try_get::<i64, _>("rax")? as u64,
+        rbx: row.try_get::<i64, _>("rbx")? as u64,
+        rcx: row.try_get::<i64, _>("rcx")? as u64,
+        rdx: row.try_get::<i64, _>("rdx")? as u64,
+        rsi: row.try_get::<i64, _>("rsi")? as u64,
+        rdi: row.try_get::<i64, _>("rdi")? as u64,
+        r8: row.try_get::<i64, _>("r8")? as u64,
+        r9: row.try_get::<i64, _>("r9")? as u64,
+        r10: row.try_get::<i64, _>("r10")? as u64,
+        r11: row.try_get::<i64, _>("r11")? as u64,
+        r12: row.try_get::<i64, _>("r12")? as u64,
+        r13: row.try_get::<i64, _>("r13")? as u64,
+        r14: row.try_get::<i64, _>("r14")? as u64,
+        r15: row.try_get::<i64, _>("r15")? as u64,
+        rflags: row.try_get::<i64, _>("rflags")? as u64,
+    })
+}
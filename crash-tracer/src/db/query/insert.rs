@@ -1,15 +1,45 @@
-pub const INSERT_PROCESS: &str =
-    "INSERT INTO processes (pid, boottime, runtime, cwd, cmdline) VALUES ($1, $2, $3, $4, $5) ON CONFLICT(pid, boottime) DO UPDATE SET runtime=excluded.runtime, cwd=excluded.cwd, cmdline=excluded.cmdline";
+pub const INSERT_PROCESS: &str = "INSERT INTO processes (pid, boottime, runtime, cwd, cmdline, cgroup_id, cgroup_path, container_id, container_runtime, pod_uid, pod_name, pod_namespace, pid_ns, mnt_ns, ns_pid, exe)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+ON CONFLICT(pid, boottime) DO UPDATE SET runtime=excluded.runtime, cwd=excluded.cwd, cmdline=excluded.cmdline, cgroup_id=excluded.cgroup_id, cgroup_path=excluded.cgroup_path,
+container_id=excluded.container_id, container_runtime=excluded.container_runtime, pod_uid=excluded.pod_uid, pod_name=excluded.pod_name, pod_namespace=excluded.pod_namespace,
+pid_ns=excluded.pid_ns, mnt_ns=excluded.mnt_ns, ns_pid=excluded.ns_pid, exe=excluded.exe";
+
+pub const UPDATE_PROCESS_CONTAINER: &str = "UPDATE processes SET cgroup_id=$3, cgroup_path=$4, container_id=$5, container_runtime=$6, pod_uid=$7, pod_name=$8, pod_namespace=$9,
+pid_ns=$10, mnt_ns=$11 WHERE pid=$1 AND boottime=$2";
+
+pub const UPDATE_PROCESS_BOOTTIME: &str =
+    "UPDATE OR IGNORE processes SET boottime=$3 WHERE pid=$1 AND boottime=$2";
 
 pub const INSERT_PROCESS_MAPS: &str =
     "INSERT INTO memory_maps (process_id, line_num, content) VALUES ($1, $2, $3)";
 
+pub const INSERT_MODULE: &str =
+    "INSERT INTO modules (process_id, path, build_id) VALUES ($1, $2, $3)";
+
+pub const INSERT_PROCESS_RUNTIME: &str =
+    "INSERT INTO process_runtimes (process_id, kind, version, path, module) VALUES ($1, $2, $3, $4, $5)";
+
+pub const UPSERT_CRASH_GROUP: &str = "INSERT INTO crash_groups (signature, signal, si_code, cmd, frames) VALUES ($1, $2, $3, $4, $5) ON CONFLICT(signature) DO UPDATE SET count=count+1, last_seen=datetime('now')";
+
 pub const INSERT_CRASHES: &str = "INSERT INTO crashes (process_id, signal, si_code, fault_addr, timestamp_ns, tid, cmd, exit_code, rip, rsp, rbp, rax, 
-rbx, rcx, rdx, rsi, rdi, r8, r9, r10, r11, r12, r13, r14, r15, rflags, kernel_stack_id, user_stack_id, boottime) 
-VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29)";
+rbx, rcx, rdx, rsi, rdi, r8, r9, r10, r11, r12, r13, r14, r15, rflags, kernel_stack_id, user_stack_id, boottime, group_id, syscall_nr, ns_tid, signature_source) 
+VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31, $32, $33)";
+
+/// A crash without a signal, so without registers or stacks.
+pub const INSERT_EXIT_CRASH: &str = "INSERT INTO crashes (process_id, kind, signal, si_code, fault_addr, timestamp_ns, tid, cmd, rip, rsp, rbp, rax,
+rbx, rcx, rdx, rsi, rdi, r8, r9, r10, r11, r12, r13, r14, r15, rflags, boottime, group_id)
+VALUES ($1, 'exit', 0, 0, 0, $2, $3, $4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, $5, $6)";
 
 pub const INSERT_STACK_FRAMES: &str =
-    "INSERT INTO stack_frames (crash_id, frame_index, ip) VALUES ($1, $2, $3)";
+    "INSERT INTO stack_frames (crash_id, frame_index, ip, source) VALUES ($1, $2, $3, $4)";
+
+pub const INSERT_KERNEL_STACK_FRAMES: &str = "INSERT INTO kernel_stack_frames (crash_id, frame_index, ip, symbol, symbol_offset, module) VALUES ($1, $2, $3, $4, $5, $6)";
+
+pub const INSERT_THREAD: &str = "INSERT INTO threads (crash_id, tid, cmd, rip, rsp, rbp, rax, rbx, rcx, rdx, rsi, rdi, r8, r9, r10, r11, r12, r13, r14, r15, rflags, stack_rsp, stack_data)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23)";
+
+pub const INSERT_THREAD_STACK_FRAMES: &str =
+    "INSERT INTO thread_stack_frames (thread_id, frame_index, ip, source) VALUES ($1, $2, $3, $4)";
 
 pub const INSERT_STACK_DUMP: &str =
     "INSERT INTO stack_dumps (crash_id, rsp, length, data) VALUES ($1, $2, $3, $4)";
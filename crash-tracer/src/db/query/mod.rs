@@ -1 +1,2 @@
 pub mod insert;
+pub mod select;
@@ -0,0 +1,15 @@
+/// Crash summaries, newest first. Each filter is skipped when bound to NULL;
+/// the runtime matches any of the `+`-joined names in `processes.runtime`,
+/// with `instr` rather than `LIKE` so `%` and `_` in it are not wildcards.
+/// `c.*` rather than a column list, as older databases lack `c.kind`.
+pub const SELECT_CRASHES: &str = "SELECT c.*, p.pid, p.runtime, g.signature
+FROM crashes c
+JOIN processes p ON c.process_id = p.id
+LEFT JOIN crash_groups g ON c.group_id = g.id
+WHERE ($1 IS NULL OR c.cmd = $1)
+  AND ($2 IS NULL OR c.signal = $2)
+  AND ($3 IS NULL OR instr('+' || lower(p.runtime) || '+', '+' || lower($3) || '+') > 0)
+  AND ($4 IS NULL OR c.created_at >= $4)
+  AND ($5 IS NULL OR c.created_at < $5)
+ORDER BY c.id DESC
+LIMIT $6";
@@ -9,6 +9,17 @@ pub const SCHEMA: &str = "
           runtime     TEXT NOT NULL,                                                                                                                                                            
           cwd         TEXT,                                                                                                                                                                     
           cmdline     TEXT,                                                                                                                                                                     
+          cgroup_id         INTEGER,
+          cgroup_path       TEXT,
+          container_id      TEXT,
+          container_runtime TEXT,
+          pod_uid           TEXT,
+          pod_name          TEXT,
+          pod_namespace     TEXT,
+          pid_ns            INTEGER,
+          mnt_ns            INTEGER,
+          ns_pid            INTEGER,
+          exe               TEXT,
           created_at  TEXT NOT NULL DEFAULT (datetime('now')),                                                                                                                                  
           UNIQUE(pid, boottime)                                                                                                                                                                 
       );                                                                                                                                                                                        
@@ -20,6 +31,34 @@ pub const SCHEMA: &str = "
           content     TEXT NOT NULL                                                                                                                                                             
       );                                                                                                                                                                                        
                                                                                                                                                                                                 
+      CREATE TABLE IF NOT EXISTS modules (
+          id          INTEGER PRIMARY KEY AUTOINCREMENT,
+          process_id  INTEGER NOT NULL REFERENCES processes(id),
+          path        TEXT NOT NULL,
+          build_id    TEXT
+      );
+
+      CREATE TABLE IF NOT EXISTS process_runtimes (
+          id          INTEGER PRIMARY KEY AUTOINCREMENT,
+          process_id  INTEGER NOT NULL REFERENCES processes(id),
+          kind        TEXT NOT NULL,
+          version     TEXT,
+          path        TEXT NOT NULL,
+          module      TEXT
+      );
+
+      CREATE TABLE IF NOT EXISTS crash_groups (
+          id          INTEGER PRIMARY KEY AUTOINCREMENT,
+          signature   TEXT NOT NULL UNIQUE,
+          signal      INTEGER NOT NULL,
+          si_code     INTEGER NOT NULL,
+          cmd         TEXT NOT NULL,
+          frames      TEXT NOT NULL,
+          count       INTEGER NOT NULL DEFAULT 1,
+          first_seen  TEXT NOT NULL DEFAULT (datetime('now')),
+          last_seen   TEXT NOT NULL DEFAULT (datetime('now'))
+      );
+
       CREATE TABLE IF NOT EXISTS crashes (                                                                                                                                                      
           id              INTEGER PRIMARY KEY AUTOINCREMENT,                                                                                                                                    
           process_id      INTEGER NOT NULL REFERENCES processes(id),
@@ -40,16 +79,55 @@ pub const SCHEMA: &str = "
           r14 INTEGER NOT NULL, r15 INTEGER NOT NULL, rflags INTEGER NOT NULL,                                                                                                                  
           kernel_stack_id INTEGER,                                                                                                                                                              
           user_stack_id   INTEGER,                                                                                                                                                              
+          group_id        INTEGER REFERENCES crash_groups(id),
+          syscall_nr      INTEGER,
+          kind            TEXT NOT NULL DEFAULT 'signal',
+          ns_tid          INTEGER,
+          signature_source TEXT,
           created_at      TEXT NOT NULL DEFAULT (datetime('now'))                                                                                                                               
       );                                                                                                                                                                                        
                                                                                                                                                                                                 
-      CREATE TABLE IF NOT EXISTS stack_frames (                                                                                                                                                 
-          id          INTEGER PRIMARY KEY AUTOINCREMENT,                                                                                                                                        
-          crash_id    INTEGER NOT NULL REFERENCES crashes(id),                                                                                                                                  
-          frame_index INTEGER NOT NULL,                                                                                                                                                         
-          ip          INTEGER NOT NULL                                                                                                                                                          
+      CREATE TABLE IF NOT EXISTS stack_frames (
+          id          INTEGER PRIMARY KEY AUTOINCREMENT,
+          crash_id    INTEGER NOT NULL REFERENCES crashes(id),
+          frame_index INTEGER NOT NULL,
+          ip          INTEGER NOT NULL,
+          source      TEXT NOT NULL DEFAULT 'bpf'
       );                                                                                                                                                                                        
                                                                                                                                                                                                 
+      CREATE TABLE IF NOT EXISTS kernel_stack_frames (
+          id            INTEGER PRIMARY KEY AUTOINCREMENT,
+          crash_id      INTEGER NOT NULL REFERENCES crashes(id),
+          frame_index   INTEGER NOT NULL,
+          ip            INTEGER NOT NULL,
+          symbol        TEXT,
+          symbol_offset INTEGER NOT NULL,
+          module        TEXT
+      );
+
+      CREATE TABLE IF NOT EXISTS threads (
+          id          INTEGER PRIMARY KEY AUTOINCREMENT,
+          crash_id    INTEGER NOT NULL REFERENCES crashes(id),
+          tid         INTEGER NOT NULL,
+          cmd         TEXT NOT NULL,
+          rip INTEGER NOT NULL, rsp INTEGER NOT NULL, rbp INTEGER NOT NULL,
+          rax INTEGER NOT NULL, rbx INTEGER NOT NULL, rcx INTEGER NOT NULL,
+          rdx INTEGER NOT NULL, rsi INTEGER NOT NULL, rdi INTEGER NOT NULL,
+          r8  INTEGER NOT NULL, r9  INTEGER NOT NULL, r10 INTEGER NOT NULL,
+          r11 INTEGER NOT NULL, r12 INTEGER NOT NULL, r13 INTEGER NOT NULL,
+          r14 INTEGER NOT NULL, r15 INTEGER NOT NULL, rflags INTEGER NOT NULL,
+          stack_rsp   INTEGER,
+          stack_data  BLOB
+      );
+
+      CREATE TABLE IF NOT EXISTS thread_stack_frames (
+          id          INTEGER PRIMARY KEY AUTOINCREMENT,
+          thread_id   INTEGER NOT NULL REFERENCES threads(id),
+          frame_index INTEGER NOT NULL,
+          ip          INTEGER NOT NULL,
+          source      TEXT NOT NULL
+      );
+
       CREATE TABLE IF NOT EXISTS stack_dumps (                                                                                                                                                  
           id          INTEGER PRIMARY KEY AUTOINCREMENT,                                                                                                                                        
           crash_id    INTEGER NOT NULL REFERENCES crashes(id),                                                                                                                                  
@@ -69,8 +147,42 @@ pub const SCHEMA: &str = "
       );                                                                                                                                                                                        
                                                                                                                                                                                                 
       CREATE INDEX IF NOT EXISTS idx_crashes_process ON crashes(process_id);                                                                                                                    
-      CREATE INDEX IF NOT EXISTS idx_crashes_status ON crashes(status);                                                                                                                         
+      CREATE INDEX IF NOT EXISTS idx_crashes_status ON crashes(status);
+      CREATE INDEX IF NOT EXISTS idx_threads_crash ON threads(crash_id);
+      CREATE INDEX IF NOT EXISTS idx_thread_stack_frames_thread ON thread_stack_frames(thread_id);
       CREATE INDEX IF NOT EXISTS idx_artifacts_process ON artifacts(process_id);                                                                                                                
       CREATE INDEX IF NOT EXISTS idx_artifacts_crash ON artifacts(crash_id);                                                                                                                    
-      CREATE INDEX IF NOT EXISTS idx_memory_maps_process ON memory_maps(process_id);                                                                                                            
+      CREATE INDEX IF NOT EXISTS idx_memory_maps_process ON memory_maps(process_id);
+      CREATE INDEX IF NOT EXISTS idx_modules_process ON modules(process_id);
+      CREATE INDEX IF NOT EXISTS idx_process_runtimes_process ON process_runtimes(process_id);
+      CREATE INDEX IF NOT EXISTS idx_modules_build_id ON modules(build_id);
+      ";
+
+/// Columns added to tables after they first shipped. `CREATE TABLE IF NOT EXISTS`
+/// leaves existing databases untouched, so these are added with `ALTER TABLE`
+/// when missing: (table, column, definition).
+pub const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("stack_frames", "source", "TEXT NOT NULL DEFAULT 'bpf'"),
+    ("crashes", "group_id", "INTEGER REFERENCES crash_groups(id)"),
+    ("crashes", "syscall_nr", "INTEGER"),
+    ("crashes", "kind", "TEXT NOT NULL DEFAULT 'signal'"),
+    ("process_runtimes", "module", "TEXT"),
+    ("processes", "cgroup_id", "INTEGER"),
+    ("processes", "cgroup_path", "TEXT"),
+    ("processes", "container_id", "TEXT"),
+    ("processes", "container_runtime", "TEXT"),
+    ("processes", "pod_uid", "TEXT"),
+    ("processes", "pod_name", "TEXT"),
+    ("processes", "pod_namespace", "TEXT"),
+    ("processes", "pid_ns", "INTEGER"),
+    ("processes", "mnt_ns", "INTEGER"),
+    ("processes", "ns_pid", "INTEGER"),
+    ("processes", "exe", "TEXT"),
+    ("crashes", "ns_tid", "INTEGER"),
+    ("crashes", "signature_source", "TEXT"),
+];
+
+/// Indexes on [`ADDED_COLUMNS`], created once those exist.
+pub const ADDED_INDEXES: &str = "
+      CREATE INDEX IF NOT EXISTS idx_crashes_group ON crashes(group_id);
       ";
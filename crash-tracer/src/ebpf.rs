@@ -1,12 +1,19 @@
 use anyhow::Context;
-use aya::programs::TracePoint;
+use aya::maps::{Array, HashMap};
+use aya::programs::{RawTracePoint, TracePoint};
+use crash_tracer_common::{CONFIG_ALL_THREADS, MAX_SIGNAL};
 use log::info;
 
 /// (ebpf function name, tracepoint category, tracepoint name)
 const TRACEPOINTS: &[(&str, &str, &str)] = &[
-    ("handle_signal_deliver", "signal", "signal_deliver"),
     ("handle_sched_process_exec", "sched", "sched_process_exec"),
     ("handle_sched_process_exit", "sched", "sched_process_exit"),
+    ("handle_sys_enter_openat", "syscalls", "sys_enter_openat"),
+    ("handle_sys_exit_openat", "syscalls", "sys_exit_openat"),
+    ("handle_sys_enter_write", "syscalls", "sys_enter_write"),
+    ("handle_sys_enter_close", "syscalls", "sys_enter_close"),
+    ("handle_sys_enter_mmap", "syscalls", "sys_enter_mmap"),
+    ("handle_sys_exit_mmap", "syscalls", "sys_exit_mmap"),
 ];
 
 pub fn attach_tracepoints(bpf: &mut aya::Ebpf) -> anyhow::Result<()> {
@@ -20,5 +27,51 @@ pub fn attach_tracepoints(bpf: &mut aya::Ebpf) -> anyhow::Result<()> {
             .with_context(|| format!("failed to attach {category}/{name}"))?;
         info!("Attached {category}/{name}");
     }
+
+    // Raw, to read the siginfo the tracepoint's fields leave out.
+    let tp: &mut RawTracePoint = bpf
+        .program_mut("handle_signal_deliver")
+        .context("program not found: handle_signal_deliver")?
+        .try_into()?;
+    tp.load()?;
+    tp.attach("signal_deliver")
+        .context("failed to attach raw tracepoint signal_deliver")?;
+    info!("Attached raw tracepoint signal_deliver");
+    Ok(())
+}
+
+/// Write runtime options into the eBPF `CONFIG` array, the set of signals
+/// to treat as crashes into `CRASH_SIGNALS` and the commands whose exits are
+/// watched into `EXIT_WATCH_COMMS`.
+pub fn configure(
+    bpf: &mut aya::Ebpf,
+    all_threads: bool,
+    signals: &[i32],
+    exit_commands: &[String],
+) -> anyhow::Result<()> {
+    let mut config: Array<_, u32> = Array::try_from(
+        bpf.map_mut("CONFIG")
+            .ok_or_else(|| anyhow::anyhow!("eBPF map not found: CONFIG"))?,
+    )?;
+    config.set(CONFIG_ALL_THREADS, u32::from(all_threads), 0)?;
+
+    let mut crash_signals: Array<_, u8> = Array::try_from(
+        bpf.map_mut("CRASH_SIGNALS")
+            .ok_or_else(|| anyhow::anyhow!("eBPF map not found: CRASH_SIGNALS"))?,
+    )?;
+    for signal in 1..=MAX_SIGNAL {
+        let enabled = signals.contains(&(signal as i32));
+        crash_signals.set(signal, u8::from(enabled), 0)?;
+    }
+
+    let mut exit_watch: HashMap<_, [u8; 16], u8> = HashMap::try_from(
+        bpf.map_mut("EXIT_WATCH_COMMS")
+            .ok_or_else(|| anyhow::anyhow!("eBPF map not found: EXIT_WATCH_COMMS"))?,
+    )?;
+    for command in exit_commands {
+        exit_watch
+            .insert(crate::exit::comm_key(command), 1, 0)
+            .with_context(|| format!("watching exits of {command}"))?;
+    }
     Ok(())
 }
@@ -1,14 +1,36 @@
-use crash_tracer_common::{ArtifactReadyEvent, SchedExecEvent, SchedExitEvent, SignalDeliverEvent};
+use crash_tracer_common::{
+    ArtifactReadyEvent, CrashOutputEvent, ModuleLoadEvent, SchedExecEvent, SchedExitEvent,
+    SignalDeliverEvent, ThreadStateEvent,
+};
 
 pub mod unified_source;
 
 pub enum Event {
     SignalDeliver(SignalDeliverEvent),
-    SchedExec(SchedExecEvent),
+    /// Boxed: it carries the command line and dwarfs the others.
+    SchedExec(Box<SchedExecEvent>),
     SchedExit(SchedExitEvent),
     ArtifactReady(ArtifactReadyEvent),
+    ThreadState(ThreadStateEvent),
+    CrashOutput(CrashOutputEvent),
+    ModuleLoad(ModuleLoadEvent),
 }
 
 pub trait EventSource {
     async fn next_event(&mut self) -> Option<Event>;
 }
+
+impl Event {
+    /// The process the event is about, as (pid, boottime).
+    pub fn process(&self) -> (u32, u64) {
+        match self {
+            Event::SignalDeliver(e) => (e.pid, e.boottime),
+            Event::SchedExec(e) => (e.pid, e.boottime),
+            Event::SchedExit(e) => (e.pid, e.boottime),
+            Event::ArtifactReady(e) => (e.pid, e.boottime),
+            Event::ThreadState(e) => (e.pid, e.boottime),
+            Event::CrashOutput(e) => (e.pid, e.boottime),
+            Event::ModuleLoad(e) => (e.pid, e.boottime),
+        }
+    }
+}
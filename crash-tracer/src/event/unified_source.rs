@@ -35,7 +35,7 @@ impl EventSource for UnifiedEventSource {
                 match event.tag {
                     EventType::SchedExec => {
                         if let Some(exec) = event.as_exec() {
-                            return Some(Event::SchedExec(*exec));
+                            return Some(Event::SchedExec(Box::new(*exec)));
                         }
                     }
                     EventType::SignalDeliver => {
@@ -53,6 +53,21 @@ impl EventSource for UnifiedEventSource {
                             return Some(Event::ArtifactReady(*artifact));
                         }
                     }
+                    EventType::ThreadState => {
+                        if let Some(thread) = event.as_thread() {
+                            return Some(Event::ThreadState(*thread));
+                        }
+                    }
+                    EventType::CrashOutput => {
+                        if let Some(output) = event.as_output() {
+                            return Some(Event::CrashOutput(*output));
+                        }
+                    }
+                    EventType::ModuleLoad => {
+                        if let Some(module) = event.as_module_load() {
+                            return Some(Event::ModuleLoad(*module));
+                        }
+                    }
                 }
             }
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
@@ -0,0 +1,62 @@
+//! Crashes without a signal: non-zero exits of commands listed with
+//! `--exit-commands`, such as an unhandled Python exception (status 1), a Go
+//! panic (status 2) or `exit(134)` from a wrapper that caught an abort.
+
+use std::ops::RangeInclusive;
+
+/// Exit statuses that count as a crash.
+#[derive(Debug, Clone)]
+pub enum ExitCodePolicy {
+    NonZero,
+    Codes(Vec<RangeInclusive<u8>>),
+}
+
+impl ExitCodePolicy {
+    pub fn matches(&self, status: u8) -> bool {
+        match self {
+            ExitCodePolicy::NonZero => status != 0,
+            ExitCodePolicy::Codes(ranges) => ranges.iter().any(|r| r.contains(&status)),
+        }
+    }
+}
+
+/// Parse `nonzero` or a list of codes and ranges, e.g. `1,2,134-255`.
+pub fn parse_policy(s: &str) -> Result<ExitCodePolicy, String> {
+    if s.eq_ignore_ascii_case("nonzero") {
+        return Ok(ExitCodePolicy::NonZero);
+    }
+    let parse_code = |code: &str| {
+        code.trim()
+            .parse::<u8>()
+            .map_err(|_| format!("invalid exit code '{code}', expected 0-255"))
+    };
+    s.split(',')
+        .map(|part| match part.split_once('-') {
+            Some((start, end)) => {
+                let range = parse_code(start)?..=parse_code(end)?;
+                if range.is_empty() {
+                    return Err(format!(
+                        "invalid exit code range '{part}', start exceeds end"
+                    ));
+                }
+                Ok(range)
+            }
+            None => parse_code(part).map(|code| code..=code),
+        })
+        .collect::<Result<_, _>>()
+        .map(ExitCodePolicy::Codes)
+}
+
+/// The status passed to `exit()`, from a `task->exit_code` wait status, or
+/// `None` if the process was killed by a signal.
+pub fn exit_status(exit_code: u32) -> Option<u8> {
+    (exit_code & 0x7f == 0).then_some((exit_code >> 8) as u8)
+}
+
+/// `name` as the kernel stores it in `comm`: at most 15 bytes, NUL padded.
+pub fn comm_key(name: &str) -> [u8; 16] {
+    let mut comm = [0u8; 16];
+    let len = name.len().min(comm.len() - 1);
+    comm[..len].copy_from_slice(&name.as_bytes()[..len]);
+    comm
+}
@@ -1,35 +1,125 @@
 #[cfg(not(target_arch = "x86_64"))]
 compile_error!("crash-tracer currently only supports x86_64");
 
+mod artifact;
 mod db;
 mod ebpf;
 mod event;
+mod exit;
+mod query;
 mod report;
+mod runtime;
+mod signature;
 mod state;
-use crate::db::CrashDb;
+mod symbolize;
+use crate::db::{ArtifactData, CrashDb};
 use crate::event::unified_source::UnifiedEventSource;
 use crate::event::{Event, EventSource};
-use crate::state::map::MemoryMap;
+use crate::runtime::go::{self, GoPanic};
+use crate::signature::CrashSignature;
+use crate::state::map::{MemoryMap, ProcessInfo, RuntimeKind};
+use crate::symbolize::{
+    Symbolizer,
+    kernel::KernelSymbolizer,
+    perf_map::PerfMap,
+    store::SymbolStore,
+    unwind::{RegisterSet, StackMemory},
+};
 
-use std::path::PathBuf;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
-use aya::maps::{HashMap, RingBuf, StackTraceMap};
+use aya::maps::{HashMap, MapData, RingBuf, StackTraceMap};
 use aya_log::EbpfLogger;
-use clap::Parser;
-use crash_tracer_common::{SignalDeliverEvent, StackDump, StackDumpKey};
+use clap::{Parser, Subcommand};
+use crash_tracer_common::{
+    ArtifactFds, ArtifactInfo, FdTrackKey, SchedExitEvent, SignalDeliverEvent, StackDump,
+    StackDumpKey, ThreadStateEvent,
+};
 use log::{debug, info, warn};
 use tokio::signal;
 
+const DB_FILE: &str = "crash-tracer.db";
+
 #[derive(Debug, Parser)]
 #[command(name = "crash-tracer")]
 #[command(about = "eBPF-based crash tracer", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
-    #[clap(short, long, default_value = "/tmp/crash-tracer/")]
+    #[clap(short, long, default_value = "/tmp/crash-tracer/", global = true)]
     output_dir: PathBuf,
 
-    #[clap(short, long)]
+    #[clap(short, long, global = true)]
     verbose: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Running without a subcommand is the same as `daemon`.
+    #[clap(flatten)]
+    daemon: DaemonArgs,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Trace crashes system-wide and write reports (the default; needs root).
+    Daemon(DaemonArgs),
+    /// List recorded crashes, newest first.
+    List(query::FilterArgs),
+    /// Print the report of a recorded crash.
+    Show(query::ShowArgs),
+    /// Write report files for recorded crashes.
+    Export(query::ExportArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct DaemonArgs {
+    /// Directory of debug files laid out as `<build-id>/debuginfo` and
+    /// `<build-id>/executable`, consulted before the files on disk. IL2CPP
+    /// symbol files go in `<build-id>/il2cpp/`.
+    #[clap(long)]
+    symbol_store: Option<PathBuf>,
+
+    /// Report file format to write for each crash.
+    #[clap(long, value_enum, default_value = "text")]
+    format: report::ReportFormat,
+
+    /// Also write a `.dmp` minidump for each crash, for Breakpad/Crashpad tooling.
+    #[clap(long)]
+    minidump: bool,
+
+    /// Stop writing report files for a crash group after this many reports.
+    /// Crashes are still recorded in the database and counted.
+    #[clap(long)]
+    reports_per_group: Option<i64>,
+
+    /// On a crash, also capture registers and stacks of every other thread
+    /// of the process.
+    #[clap(long)]
+    all_threads: bool,
+
+    /// Signals to treat as crashes, by name or number. SIGKILL is off by
+    /// default and, when listed, only counts if sent by the OOM killer.
+    #[clap(
+        long,
+        value_delimiter = ',',
+        value_parser = report::parse_signal,
+        default_value = "SIGILL,SIGTRAP,SIGABRT,SIGBUS,SIGFPE,SIGSEGV,SIGXCPU,SIGXFSZ,SIGSYS"
+    )]
+    signals: Vec<i32>,
+
+    /// Also record exits of these commands, matched by process name (`comm`,
+    /// the first 15 bytes), as crashes when `--exit-codes` matches the
+    /// status. What they last wrote to stderr is kept with the crash. Go
+    /// programs that die of a panic are recorded whether listed or not.
+    #[clap(long, value_delimiter = ',')]
+    exit_commands: Vec<String>,
+
+    /// Exit statuses of `--exit-commands` that count as crashes: `nonzero`
+    /// or a list of codes and ranges, e.g. `1,2,134-255`.
+    #[clap(long, value_parser = exit::parse_policy, default_value = "nonzero")]
+    exit_codes: exit::ExitCodePolicy,
 }
 
 #[tokio::main]
@@ -41,6 +131,17 @@ async fn main() -> Result<(), anyhow::Error> {
     )
     .init();
 
+    let db_path = args.output_dir.join(DB_FILE);
+    match &args.command {
+        None => run_daemon(&args.output_dir, &args.daemon).await,
+        Some(Command::Daemon(daemon)) => run_daemon(&args.output_dir, daemon).await,
+        Some(Command::List(filter)) => query::list(&db_path, filter).await,
+        Some(Command::Show(show)) => query::show(&db_path, show).await,
+        Some(Command::Export(export)) => query::export(&db_path, export).await,
+    }
+}
+
+async fn run_daemon(output_dir: &Path, args: &DaemonArgs) -> anyhow::Result<()> {
     // Bump memlock rlimit for eBPF maps
     let rlim = libc::rlimit {
         rlim_cur: libc::RLIM_INFINITY,
@@ -54,7 +155,7 @@ async fn main() -> Result<(), anyhow::Error> {
 
     info!(
         "Starting crash-tracer and reporting in : {:?}",
-        args.output_dir.to_str()
+        output_dir.to_str()
     );
 
     info!("Loading eBPF program...");
@@ -88,12 +189,13 @@ async fn main() -> Result<(), anyhow::Error> {
             });
         }
     }
+    ebpf::configure(&mut bpf, args.all_threads, &args.signals, &args.exit_commands)?;
     ebpf::attach_tracepoints(&mut bpf)?;
 
     info!("Programs attached. Waiting for events...");
 
-    std::fs::create_dir_all(&args.output_dir)?;
-    let db_path = args.output_dir.join("crash-tracer.db");
+    std::fs::create_dir_all(output_dir)?;
+    let db_path = output_dir.join(DB_FILE);
     let db = db::CrashDb::new(&db_path).await?;
 
     // Get handles to maps - now using unified ring buffer
@@ -109,9 +211,34 @@ async fn main() -> Result<(), anyhow::Error> {
         bpf.take_map("STACK_DUMP_MAP")
             .ok_or_else(|| anyhow::anyhow!("eBPF map not found: STACK_DUMP_MAP"))?,
     )?;
+    let mut tracked_fds: HashMap<_, FdTrackKey, ArtifactInfo> = HashMap::try_from(
+        bpf.take_map("TRACKED_FDS")
+            .ok_or_else(|| anyhow::anyhow!("eBPF map not found: TRACKED_FDS"))?,
+    )?;
+    let mut artifact_fds: HashMap<_, u32, ArtifactFds> = HashMap::try_from(
+        bpf.take_map("ARTIFACT_FDS")
+            .ok_or_else(|| anyhow::anyhow!("eBPF map not found: ARTIFACT_FDS"))?,
+    )?;
+    let mut go_processes: HashMap<_, u32, u64> = HashMap::try_from(
+        bpf.take_map("GO_PROCESSES")
+            .ok_or_else(|| anyhow::anyhow!("eBPF map not found: GO_PROCESSES"))?,
+    )?;
 
-    let output_dir = args.output_dir.clone();
     let mut memory_map = MemoryMap::new();
+    // After attaching, so processes started meanwhile are not missed either.
+    let mut bootstrap = Bootstrap::new();
+    let mut symbolizer = Symbolizer::new(args.symbol_store.clone().map(SymbolStore::new));
+    let mut kernel_symbolizer = KernelSymbolizer::new();
+    // Signals sent for Go panic exits, held until the panic can be checked to name them.
+    let mut go_panic_signals: std::collections::HashMap<(u32, u64), Vec<SignalDeliverEvent>> =
+        std::collections::HashMap::new();
+    // Sibling threads of a crash, held until the process is gone and the crash is complete.
+    let mut captured_threads: std::collections::HashMap<(u32, u64), Vec<db::ThreadData>> =
+        std::collections::HashMap::new();
+    // What crashing processes wrote to stderr after the signal, e.g. a Python traceback,
+    // and the tail of what commands watched for their exit wrote; sent as they exit.
+    let mut crash_output: std::collections::HashMap<(u32, u64), std::collections::VecDeque<u8>> =
+        std::collections::HashMap::new();
 
     // Single event loop processes events in FIFO order
     // This guarantees exec events are processed before signal events for the same process
@@ -122,12 +249,42 @@ async fn main() -> Result<(), anyhow::Error> {
             info!("Exiting...");
         }
         _ = async {
-            while let Some(event) = event_source.next_event().await {
+            loop {
+                // Running processes are seeded while the ring buffer is empty, so it does not fill
+                // up meanwhile. `next_event` only awaits once it is, so dropping it loses nothing.
+                let event = if bootstrap.is_done() {
+                    event_source.next_event().await
+                } else {
+                    tokio::select! {
+                        biased;
+                        event = event_source.next_event() => event,
+                        _ = std::future::ready(()) => {
+                            bootstrap.seed_batch(&db, &mut memory_map, &mut go_processes).await;
+                            continue;
+                        }
+                    }
+                };
+                let Some(event) = event else {
+                    break;
+                };
+                let (pid, boottime) = event.process();
+                // An exec replaces whatever the process was running.
+                if bootstrap.take(pid) && !matches!(event, Event::SchedExec(_)) {
+                    bootstrap.seed(&db, &mut memory_map, &mut go_processes, pid).await;
+                }
+                if let Some(seeded) = memory_map.settle(pid, boottime)
+                    && let Some(info) = memory_map.get(pid, boottime)
+                    && let Err(e) = db.settle_process(seeded, info).await
+                        .with_context(|| format!("updating start time pid={pid}"))
+                {
+                    log::error!("{e:#}");
+                }
                 match event {
                     Event::SchedExec(exec) => {
                         debug!("exec event: pid={}, boottime={}", exec.pid, exec.boottime);
-                        memory_map.insert(exec.pid, exec.boottime);
+                        memory_map.insert(&exec);
                         if let Some(info) = memory_map.get(exec.pid, exec.boottime) {
+                            watch_go(&mut go_processes, info);
                             if let Err(e) = db.insert_process(info).await
                                 .with_context(|| format!("inserting process pid={}", exec.pid))
                             {
@@ -137,23 +294,85 @@ async fn main() -> Result<(), anyhow::Error> {
                     }
                     Event::SignalDeliver(signal) => {
                         debug!("signal event: pid={}, boottime={}", signal.pid, signal.boottime);
-                        handle_signal_deliver_event(&db, &signal, &signal_deliver_stacks, &mut stack_dumps, &memory_map).await;
+                        // Mappings the module events missed, while the process is still there to ask.
+                        memory_map.refresh_maps(signal.pid, signal.boottime);
+                        if let Some(info) = memory_map.update_container(signal.pid, signal.boottime, signal.cgroup_id, signal.pid_ns, signal.mnt_ns)
+                            && let Err(e) = db.update_process_container(info).await
+                                .with_context(|| format!("updating container pid={}", signal.pid))
+                        {
+                            log::error!("{e:#}");
+                        }
+                        if signal.go_panic != 0 {
+                            go_panic_signals.entry((signal.pid, signal.boottime)).or_default().push(signal);
+                        } else {
+                            handle_signal_deliver_event(&db, &signal, &signal_deliver_stacks, &mut stack_dumps, &memory_map, &mut symbolizer, &mut kernel_symbolizer).await;
+                        }
                     }
                     Event::SchedExit(exit) => {
                         debug!("exit event: pid={}, boottime={} exit_code={}", exit.pid, exit.boottime, exit.exit_code);
+         // Watched commands write stderr whether or not they crash; it is only stored for crashes.
+         let output = crash_output.remove(&(exit.pid, exit.boottime)).map(Vec::from);
+         let panic = output.as_deref().and_then(|output| GoPanic::parse(&String::from_utf8_lossy(output)));
+         for signal in go_panic_signals.remove(&(exit.pid, exit.boottime)).unwrap_or_default() {
+             if panic.as_ref().is_some_and(|panic| panic.names_signal(signal.signal)) {
+                 handle_signal_deliver_event(&db, &signal, &signal_deliver_stacks, &mut stack_dumps, &memory_map, &mut symbolizer, &mut kernel_symbolizer).await;
+             } else {
+                 debug!("pid={} recovered from signal {} before its panic", signal.pid, signal.signal);
+                 let _ = stack_dumps.remove(&StackDumpKey { pid: signal.pid, tid: signal.tid, boottime: signal.boottime });
+             }
+         }
+         record_exit_crash(&db, &exit, output.as_deref(), args).await;
+         for info in artifact::take_unclosed(&mut tracked_fds, &mut artifact_fds, exit.pid) {
+             store_artifact(&db, exit.pid, exit.boottime, &info.filename[..info.filename_len as usize], &memory_map).await;
+         }
+         if let Some(threads) = captured_threads.remove(&(exit.pid, exit.boottime))
+             && let Err(e) = db.insert_threads(exit.pid, exit.boottime, &threads).await
+                 .with_context(|| format!("inserting threads pid={}", exit.pid))
+         {
+             log::error!("{e:#}");
+         }
          match db.complete_crash(exit.pid, exit.boottime, exit.exit_code).await
              .with_context(|| format!("completing crash pid={}", exit.pid))
          {
             Ok(Some(crash_id)) => {
+              if let Some(info) = memory_map.take_unsaved(exit.pid, exit.boottime)
+                  && let Err(e) = db.insert_process(info).await
+                      .with_context(|| format!("updating maps pid={}", exit.pid))
+              {
+                  log::error!("{e:#}");
+              }
+              if let Some(output) = output
+                  && let Err(e) = db.insert_artifact(exit.pid, exit.boottime, &artifact::crash_output(exit.pid, output)).await
+                      .with_context(|| format!("inserting crash output pid={}", exit.pid))
+              {
+                  log::error!("{e:#}");
+              }
+              collect_runtime_artifacts(&db, exit.pid, exit.boottime, &memory_map, crash_id).await;
               match db.get_crash_report_data(crash_id).await
                   .with_context(|| format!("retrieving report data crash_id={}", crash_id))
               {
-                  Ok(data) => match report::save_from_db(&output_dir, &data)
-                      .context("writing report file")
-                  {
-                      Ok(path) => info!("Report saved: {}", path.display()),
-                      Err(e) => log::error!("{e:#}"),
-                  },
+                  Ok(data) if exceeds_report_limit(&data, args.reports_per_group) => {
+                      if let Some(group) = &data.group {
+                          info!(
+                              "Crash group {} seen {} times, not writing another report",
+                              group.signature, group.count
+                          );
+                      }
+                  }
+                  Ok(data) => {
+                      let root = memory_map.get(exit.pid, exit.boottime).and_then(|info| info.root.as_deref());
+                      let stacks = symbolizer.symbolize_report(&data, root);
+                      match report::save_from_db(output_dir, &data, &stacks, args.format, args.minidump)
+                          .context("writing report file")
+                      {
+                          Ok(paths) => {
+                              for path in paths {
+                                  info!("Report saved: {}", path.display());
+                              }
+                          }
+                          Err(e) => log::error!("{e:#}"),
+                      }
+                  }
                   Err(e) => log::error!("{e:#}"),
               }
             }
@@ -169,8 +388,23 @@ async fn main() -> Result<(), anyhow::Error> {
 
           memory_map.remove(exit.pid, exit.boottime);                     }
                     Event::ArtifactReady(artifact) => {
-                        debug!("artifact event: pid={}, boottime={}, file={}", artifact.pid, artifact.boottime, std::str::from_utf8(&artifact.filename[..artifact.filename_len as usize])
-      .unwrap_or("<invalid>"));
+                        let name = &artifact.filename[..(artifact.filename_len as usize).min(artifact.filename.len())];
+                        debug!("artifact event: pid={}, boottime={}, file={}, written={}", artifact.pid, artifact.boottime, std::str::from_utf8(name)
+      .unwrap_or("<invalid>"), artifact.bytes_written);
+                        store_artifact(&db, artifact.pid, artifact.boottime, name, &memory_map).await;
+                    }
+                    Event::ThreadState(thread) => {
+                        debug!("thread event: pid={}, tid={}, boottime={}", thread.pid, thread.tid, thread.boottime);
+                        let data = capture_thread(&thread, &signal_deliver_stacks, &mut stack_dumps, &memory_map, &mut symbolizer);
+                        captured_threads.entry((thread.pid, thread.boottime)).or_default().push(data);
+                    }
+                    Event::CrashOutput(output) => {
+                        debug!("crash output event: pid={}, boottime={}, len={}", output.pid, output.boottime, output.len);
+                        artifact::append_output(crash_output.entry((output.pid, output.boottime)).or_default(), &output);
+                    }
+                    Event::ModuleLoad(module) => {
+                        debug!("module load event: pid={}, boottime={}, start={:#x}", module.pid, module.boottime, module.start);
+                        memory_map.load_module(&module);
                     }
                 }
             }
@@ -183,12 +417,146 @@ async fn main() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Processes already running in a batch seeded between drains of the ring buffer.
+const SEED_BATCH: usize = 64;
+
+/// The processes that were running before the tracer started, whose exec it
+/// never saw, to track from /proc so that their crashes are not dropped.
+/// They are seeded a batch at a time while no events are waiting, and any
+/// an event comes for ahead of its turn.
+struct Bootstrap {
+    pending: std::collections::BTreeSet<u32>,
+    seeded: usize,
+}
+
+impl Bootstrap {
+    fn new() -> Self {
+        let pending = match std::fs::read_dir("/proc") {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+                .collect(),
+            Err(e) => {
+                warn!("Failed to list running processes: {e}");
+                Default::default()
+            }
+        };
+        Self { pending, seeded: 0 }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Whether `pid` was still waiting to be seeded; it no longer is.
+    fn take(&mut self, pid: u32) -> bool {
+        self.pending.remove(&pid)
+    }
+
+    async fn seed_batch(
+        &mut self,
+        db: &CrashDb,
+        memory_map: &mut MemoryMap,
+        go_processes: &mut HashMap<MapData, u32, u64>,
+    ) {
+        for _ in 0..SEED_BATCH {
+            let Some(pid) = self.pending.pop_first() else {
+                break;
+            };
+            self.seed(db, memory_map, go_processes, pid).await;
+        }
+        if memory_map.is_full() && !self.pending.is_empty() {
+            warn!(
+                "Already tracking as many processes as are kept; {} of those running are not tracked",
+                self.pending.len()
+            );
+            self.pending.clear();
+        }
+        if self.pending.is_empty() {
+            info!("Tracking {} processes already running", self.seeded);
+        }
+    }
+
+    async fn seed(
+        &mut self,
+        db: &CrashDb,
+        memory_map: &mut MemoryMap,
+        go_processes: &mut HashMap<MapData, u32, u64>,
+        pid: u32,
+    ) {
+        let Some(info) = memory_map.seed(pid) else {
+            return;
+        };
+        self.seeded += 1;
+        watch_go(go_processes, info);
+        if let Err(e) = db
+            .insert_process(info)
+            .await
+            .with_context(|| format!("inserting running process pid={pid}"))
+        {
+            log::error!("{e:#}");
+        }
+    }
+}
+
+/// Have the eBPF side keep the stderr tail of Go programs, from which their
+/// panics are recorded whether or not `--exit-commands` lists them.
+fn watch_go(go_processes: &mut HashMap<MapData, u32, u64>, info: &ProcessInfo) {
+    if info.runtimes.iter().any(|r| r.kind == RuntimeKind::Go)
+        && let Err(e) = go_processes.insert(info.pid, info.boottime, 0)
+    {
+        warn!("Cannot watch Go program pid={}: {e}", info.pid);
+    }
+}
+
+/// Make the exit of a watched command a crash if `--exit-codes` matches its
+/// status, and that of any Go program that died of a panic.
+async fn record_exit_crash(
+    db: &CrashDb,
+    event: &SchedExitEvent,
+    output: Option<&[u8]>,
+    args: &DaemonArgs,
+) {
+    let Some(status) = exit::exit_status(event.exit_code) else {
+        return;
+    };
+    let watched = args
+        .exit_commands
+        .iter()
+        .any(|command| exit::comm_key(command) == event.cmd);
+    let go_panic = status == go::PANIC_EXIT_STATUS
+        && output.is_some_and(|output| GoPanic::parse(&String::from_utf8_lossy(output)).is_some());
+    let crashed = go_panic || (watched && args.exit_codes.matches(status));
+    if !crashed {
+        return;
+    }
+
+    let cmd = std::str::from_utf8(&event.cmd)
+        .unwrap_or("<unknown>")
+        .trim_end_matches('\0');
+    let signature = CrashSignature::exit(cmd, status);
+    match db
+        .insert_exit_crash(event, &signature)
+        .await
+        .with_context(|| format!("inserting exit crash pid={}", event.pid))
+    {
+        Ok(Some(crash_id)) => info!(
+            "Exit status {status} of {cmd} (pid {}) recorded as crash {crash_id}",
+            event.pid
+        ),
+        Ok(None) => {}
+        Err(e) => log::error!("{e:#}"),
+    }
+}
+
 async fn handle_signal_deliver_event(
     db: &CrashDb,
     event: &SignalDeliverEvent,
     stacks: &StackTraceMap<aya::maps::MapData>,
     stack_dumps: &mut HashMap<aya::maps::MapData, StackDumpKey, StackDump>,
     map: &MemoryMap,
+    symbolizer: &mut Symbolizer,
+    kernel_symbolizer: &mut KernelSymbolizer,
 ) {
     info!("\n{}", "=".repeat(60));
     info!("CRASH DETECTED");
@@ -198,6 +566,14 @@ async fn handle_signal_deliver_event(
         .then(|| stacks.get(&(event.user_stack_id as u32), 0).ok())
         .flatten();
 
+    // Kernel addresses are only meaningful for this boot and module set, so
+    // resolve them now rather than when the report is rendered.
+    let kernel_stack = (event.kernel_stack_id >= 0)
+        .then(|| stacks.get(&(event.kernel_stack_id as u32), 0).ok())
+        .flatten()
+        .map(|trace| kernel_symbolizer.symbolize(trace.frames().iter().map(|f| f.ip)))
+        .unwrap_or_default();
+
     // Retrieve raw stack dump from eBPF HashMap
     let dump_key = StackDumpKey {
         pid: event.pid,
@@ -209,13 +585,196 @@ async fn handle_signal_deliver_event(
 
     let process_info = map.get(event.pid, event.boottime);
 
+    // The BPF stack walk relies on frame pointers; replay CFI over the stack
+    // copy while the binaries it ran are guaranteed to still be on disk.
+    let unwound_frames = match (stack_dump.as_ref(), process_info) {
+        (Some(dump), Some(info)) => {
+            let len = (dump.len as usize).min(dump.data.len());
+            let stack = StackMemory {
+                base: dump.rsp,
+                data: &dump.data[..len],
+            };
+            let root = info.root.as_deref();
+            symbolizer.unwind(RegisterSet::from(event), &stack, &info.maps, &info.modules, root)
+        }
+        _ => Vec::new(),
+    };
+
+    let bpf_frames: Vec<u64> = stack_trace
+        .as_ref()
+        .map(|trace| trace.frames().iter().map(|f| f.ip).collect())
+        .unwrap_or_default();
+    let (source, signature_frames) = CrashSignature::pick_frames(&unwound_frames, &bpf_frames);
+    let signature = CrashSignature::compute(
+        event.signal,
+        event.si_code,
+        signature_frames,
+        source,
+        process_info.map(|info| info.maps.as_slice()).unwrap_or_default(),
+    );
+
     if let Err(e) = db
-        .insert_crash(&event, stack_trace.as_ref(), stack_dump.as_ref())
+        .insert_crash(
+            &event,
+            stack_trace.as_ref(),
+            &unwound_frames,
+            &kernel_stack,
+            stack_dump.as_ref(),
+            &signature,
+        )
         .await
         .with_context(|| format!("inserting crash pid={} sig={}", event.pid, event.signal))
     {
         log::error!("{e:#}");
     }
     // Console output for real-time feedback; file report is generated on exit from DB
-    report::print_to_console(event, stack_trace.as_ref(), process_info);
+    report::print_to_console(event, stack_trace.as_ref(), &kernel_stack, process_info);
+}
+
+/// Read an artifact written by `pid` and store it for its crash.
+async fn store_artifact(db: &CrashDb, pid: u32, boottime: u64, name: &[u8], map: &MemoryMap) {
+    let data = artifact::collect(pid, name, map.get(pid, boottime));
+    save_artifact(db, pid, boottime, &data).await;
+}
+
+async fn save_artifact(db: &CrashDb, pid: u32, boottime: u64, data: &ArtifactData) {
+    info!("Artifact from pid={}: {}", pid, data.full_path);
+    if let Err(e) = db
+        .insert_artifact(pid, boottime, data)
+        .await
+        .with_context(|| format!("inserting artifact pid={pid} file={}", data.full_path))
+    {
+        log::error!("{e:#}");
+    }
+}
+
+/// Pick up artifacts the eBPF programs don't follow by name, such as an
+/// hs_err log redirected with `-XX:ErrorFile` or a JIT perf map.
+async fn collect_runtime_artifacts(
+    db: &CrashDb,
+    pid: u32,
+    boottime: u64,
+    map: &MemoryMap,
+    crash_id: i64,
+) {
+    let Some(info) = map.get(pid, boottime) else {
+        return;
+    };
+    for path in runtime::locate_artifacts(info) {
+        let mut data = artifact::collect(pid, path.as_os_str().as_bytes(), Some(info));
+        // Too big to store whole; keep the entries the crash's frames are in.
+        if data.content.is_none() && is_perf_map(&data.filename) {
+            data.content = reduce_perf_map(db, crash_id, info, &path).await;
+        }
+        save_artifact(db, pid, boottime, &data).await;
+    }
+}
+
+fn is_perf_map(filename: &str) -> bool {
+    filename.starts_with("perf-") && filename.ends_with(".map")
+}
+
+async fn reduce_perf_map(
+    db: &CrashDb,
+    crash_id: i64,
+    info: &ProcessInfo,
+    path: &Path,
+) -> Option<Vec<u8>> {
+    let ips = match db.crash_ips(crash_id).await {
+        Ok(ips) => ips,
+        Err(e) => {
+            warn!("cannot read frames of crash_id={crash_id}: {e:#}");
+            return None;
+        }
+    };
+    let kept = info
+        .open_file(path)
+        .and_then(|file| PerfMap::filter_file(file, &ips));
+    match kept {
+        Ok(kept) => {
+            info!(
+                "perf map {} too big to store, kept {} bytes covering the crash's frames",
+                path.display(),
+                kept.len()
+            );
+            Some(kept)
+        }
+        Err(e) => {
+            warn!("cannot read perf map {}: {e}, JIT frames stay unnamed", path.display());
+            None
+        }
+    }
+}
+
+/// Resolve a sibling thread's stacks while its process's binaries are still mapped.
+fn capture_thread(
+    event: &ThreadStateEvent,
+    stacks: &StackTraceMap<aya::maps::MapData>,
+    stack_dumps: &mut HashMap<aya::maps::MapData, StackDumpKey, StackDump>,
+    map: &MemoryMap,
+    symbolizer: &mut Symbolizer,
+) -> db::ThreadData {
+    let stack_frames = (event.user_stack_id >= 0)
+        .then(|| stacks.get(&(event.user_stack_id as u32), 0).ok())
+        .flatten()
+        .map(|trace| trace.frames().iter().map(|f| f.ip).collect())
+        .unwrap_or_default();
+
+    let dump_key = StackDumpKey {
+        pid: event.pid,
+        tid: event.tid,
+        boottime: event.boottime,
+    };
+    let stack_dump = stack_dumps.get(&dump_key, 0).ok().map(|dump| {
+        let len = (dump.len as usize).min(dump.data.len());
+        (dump.rsp, dump.data[..len].to_vec())
+    });
+    let _ = stack_dumps.remove(&dump_key);
+
+    let unwound_frames = match (stack_dump.as_ref(), map.get(event.pid, event.boottime)) {
+        (Some((rsp, data)), Some(info)) => {
+            let stack = StackMemory { base: *rsp, data };
+            let root = info.root.as_deref();
+            symbolizer.unwind(RegisterSet::from(event), &stack, &info.maps, &info.modules, root)
+        }
+        _ => Vec::new(),
+    };
+
+    db::ThreadData {
+        tid: event.tid,
+        cmd: std::str::from_utf8(&event.cmd)
+            .unwrap_or("<unknown>")
+            .trim_end_matches('\0')
+            .to_string(),
+        registers: db::Registers {
+            rip: event.rip,
+            rsp: event.rsp,
+            rbp: event.rbp,
+            rax: event.rax,
+            rbx: event.rbx,
+            rcx: event.rcx,
+            rdx: event.rdx,
+            rsi: event.rsi,
+            rdi: event.rdi,
+            r8: event.r8,
+            r9: event.r9,
+            r10: event.r10,
+            r11: event.r11,
+            r12: event.r12,
+            r13: event.r13,
+            r14: event.r14,
+            r15: event.r15,
+            rflags: event.rflags,
+        },
+        stack_frames,
+        unwound_frames,
+        stack_dump,
+    }
+}
+
+fn exceeds_report_limit(data: &db::CrashReportData, limit: Option<i64>) -> bool {
+    match (&data.group, limit) {
+        (Some(group), Some(limit)) => group.count > limit,
+        _ => false,
+    }
 }
@@ -0,0 +1,214 @@
+//! Offline inspection of the crash database. None of this needs root or the
+//! eBPF programs; it only reads `crash-tracer.db`.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+use crate::db::{CrashDb, CrashFilter, CrashKind};
+use crate::exit::exit_status;
+use crate::report::{self, ReportFormat, parse_signal, si_code_name, signal_name};
+use crate::symbolize::{Symbolizer, store::SymbolStore};
+
+/// Format of `created_at` in the database (SQLite `datetime('now')`, UTC).
+const DB_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, clap::Args)]
+pub struct FilterArgs {
+    /// Only crashes of this command name (comm).
+    #[clap(long)]
+    cmd: Option<String>,
+
+    /// Only crashes from this signal, by name (`SIGSEGV`, `segv`) or number.
+    #[clap(long, value_parser = parse_signal)]
+    signal: Option<i32>,
+
+    /// Only crashes of processes with this detected runtime, e.g. `jvm`.
+    #[clap(long)]
+    runtime: Option<String>,
+
+    /// Only crashes at or after this time (UTC), as `YYYY-MM-DD[ HH:MM:SS]` or RFC 3339.
+    #[clap(long, value_parser = parse_time)]
+    since: Option<String>,
+
+    /// Only crashes before this time, same format as `--since`.
+    #[clap(long, value_parser = parse_time)]
+    until: Option<String>,
+
+    /// Return at most this many crashes, newest first.
+    #[clap(long)]
+    limit: Option<i64>,
+}
+
+impl From<&FilterArgs> for CrashFilter {
+    fn from(args: &FilterArgs) -> Self {
+        Self {
+            cmd: args.cmd.clone(),
+            signal: args.signal,
+            runtime: args.runtime.clone(),
+            since: args.since.clone(),
+            until: args.until.clone(),
+            limit: args.limit,
+        }
+    }
+}
+
+/// `show` prints to stdout, so one format at a time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ShowFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ShowArgs {
+    crash_id: i64,
+
+    #[clap(long, value_enum, default_value = "text")]
+    format: ShowFormat,
+
+    /// Symbol store to resolve frames against, as for the daemon.
+    #[clap(long)]
+    symbol_store: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct ExportArgs {
+    /// Crashes to export; when empty, every crash matching the filters.
+    crash_ids: Vec<i64>,
+
+    #[clap(flatten)]
+    filter: FilterArgs,
+
+    /// Directory to write the report files to.
+    #[clap(long, default_value = ".")]
+    dest: PathBuf,
+
+    #[clap(long, value_enum, default_value = "json")]
+    format: ReportFormat,
+
+    /// Also write a `.dmp` minidump for each crash.
+    #[clap(long)]
+    minidump: bool,
+
+    /// Symbol store to resolve frames against, as for the daemon.
+    #[clap(long)]
+    symbol_store: Option<PathBuf>,
+}
+
+pub async fn list(db_path: &Path, args: &FilterArgs) -> anyhow::Result<()> {
+    let db = CrashDb::open_read_only(db_path).await?;
+    let crashes = db.list_crashes(&CrashFilter::from(args)).await?;
+
+    let mut stdout = std::io::stdout().lock();
+    writeln!(
+        stdout,
+        "{:>6}  {:<19}  {:<16}  {:>7}  {:<20}  {:<8}  {:<8}  GROUP",
+        "ID", "TIME (UTC)", "CMD", "PID", "SIGNAL", "RUNTIME", "STATUS"
+    )?;
+    for crash in &crashes {
+        let signal = match crash.kind {
+            CrashKind::Signal => format!(
+                "{}/{}",
+                signal_name(crash.signal),
+                si_code_name(crash.signal, crash.si_code)
+            ),
+            CrashKind::Exit => match crash.exit_code.and_then(exit_status) {
+                Some(status) => format!("(exit status {status})"),
+                None => "(exit)".to_string(),
+            },
+        };
+        let status = match crash.exit_code {
+            Some(code) => format!("exit {code}"),
+            None => crash.status.clone(),
+        };
+        writeln!(
+            stdout,
+            "{:>6}  {:<19}  {:<16}  {:>7}  {:<20}  {:<8}  {:<8}  {}",
+            crash.id,
+            crash.created_at,
+            crash.cmd,
+            crash.pid,
+            signal,
+            crash.runtime,
+            status,
+            crash.signature.as_deref().unwrap_or("-")
+        )?;
+    }
+    Ok(())
+}
+
+pub async fn show(db_path: &Path, args: &ShowArgs) -> anyhow::Result<()> {
+    let db = CrashDb::open_read_only(db_path).await?;
+    let data = db
+        .get_crash_report_data(args.crash_id)
+        .await
+        .with_context(|| format!("retrieving report data crash_id={}", args.crash_id))?;
+
+    let mut symbolizer = Symbolizer::new(args.symbol_store.clone().map(SymbolStore::new));
+    let stacks = symbolizer.symbolize_report(&data, None);
+
+    let mut stdout = std::io::stdout().lock();
+    match args.format {
+        ShowFormat::Text => report::write_report_from_db(&mut stdout, &data, &stacks)?,
+        ShowFormat::Json => report::json::write_report(&mut stdout, &data, &stacks)?,
+    }
+    Ok(())
+}
+
+pub async fn export(db_path: &Path, args: &ExportArgs) -> anyhow::Result<()> {
+    let db = CrashDb::open_read_only(db_path).await?;
+    let crash_ids = if args.crash_ids.is_empty() {
+        db.list_crashes(&CrashFilter::from(&args.filter))
+            .await?
+            .iter()
+            .map(|c| c.id)
+            .collect()
+    } else {
+        args.crash_ids.clone()
+    };
+
+    std::fs::create_dir_all(&args.dest)
+        .with_context(|| format!("creating {}", args.dest.display()))?;
+
+    let mut symbolizer = Symbolizer::new(args.symbol_store.clone().map(SymbolStore::new));
+    for crash_id in crash_ids {
+        let data = db
+            .get_crash_report_data(crash_id)
+            .await
+            .with_context(|| format!("retrieving report data crash_id={crash_id}"))?;
+        let stacks = symbolizer.symbolize_report(&data, None);
+        // Keyed by crash id so repeated exports overwrite rather than accumulate.
+        let stem = format!("crash_{crash_id}_{}_{}", data.cmd, data.pid);
+        for path in report::save_as(
+            &args.dest,
+            &stem,
+            &data,
+            &stacks,
+            args.format,
+            args.minidump,
+        )? {
+            println!("{}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Normalize a user-supplied time to the database's `created_at` format so the
+/// two compare correctly as strings.
+fn parse_time(s: &str) -> Result<String, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.naive_utc().format(DB_TIME_FORMAT).to_string());
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(s, DB_TIME_FORMAT) {
+        return Ok(time.format(DB_TIME_FORMAT).to_string());
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(format!("{date} 00:00:00"));
+    }
+    Err(format!(
+        "invalid time `{s}`, expected YYYY-MM-DD, YYYY-MM-DD HH:MM:SS or RFC 3339"
+    ))
+}
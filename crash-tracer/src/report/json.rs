@@ -0,0 +1,657 @@
+//! Machine-readable crash reports.
+//!
+//! The document is a single JSON object. `schema_version` is bumped whenever a
+//! field is removed, renamed or changes meaning; new fields may be added within
+//! a version. Addresses and register values are `"0x…"` hex strings because
+//! they routinely exceed the 2^53 integers JSON parsers can represent exactly.
+//! Binary blobs are standard base64.
+//!
+//! Schema version 2:
+//!
+//! ```text
+//! schema_version   2
+//! generated        RFC 3339 timestamp
+//! process          { cmd, pid, tid, ns_pid | null, ns_tid | null, runtime,
+//!                    runtimes: [{ kind, version | null, path, module | null }],
+//!                    container }
+//!                  runtime is the detected runtimes joined by `+`, or `Native`;
+//!                  ns_pid and ns_tid are the ids inside the process' pid
+//!                  namespace, null when unknown
+//! container        { cgroup_id | null, cgroup_path | null, id | null,
+//!                    runtime | null, pod_uid | null, pod_name | null,
+//!                    pod_namespace | null, pid_ns | null, mnt_ns | null }
+//!                  ids are null when unknown; id is the full container id
+//! kind             "signal" | "exit"
+//!                  exit: a non-zero exit of a command under --exit-commands
+//! signal           { number, name, code, code_name, fault_addr | null,
+//!                    syscall | null } | null   syscall: SIGSYS only
+//!                  null for exits
+//! exit_code        integer | null   wait status, as the kernel reports it
+//! exit_status      integer | null   status passed to exit(), exits only
+//! group            { signature, count, first_seen, last_seen,
+//!                    source | null } | null   source: "cfi" or "bpf", the
+//!                  stack the signature was computed from
+//! registers        { rip, rsp, rbp, rax, ..., r15, rflags } | null
+//!                  null for exits
+//! stacks.user      [frame]   BPF stack walk
+//! stacks.unwound   [frame]   CFI unwind of the raw stack
+//! stacks.kernel    [{ ip, symbol | null, offset, module | null }]
+//! stack_dump       { rsp, length, data_base64 } | null
+//! memory_maps      [{ start, end, perms, offset, dev, inode, path | null }]
+//! modules          [{ path, build_id | null }]
+//! artifacts        [{ filename, full_path, size | null, content_base64 | null }]
+//! threads          [{ tid, cmd, registers, stacks: { user, unwound }, stack_dump }]
+//!                  other threads of the process, when captured
+//! jvm              { error, jre_version, vm, problematic_frame, current_thread,
+//!                    java_command, jvm_args, java_frames: [string],
+//!                    native_frames: [string], threads: [string],
+//!                    heap: [string], flags: [{ name, value, origin }] } | null
+//!                  parsed from the hs_err log; strings are null when absent
+//! python           { error | null, threads: [{ id, current,
+//!                    frames: [{ file, line | null, function }] }] } | null
+//!                  faulthandler traceback, crashing thread first
+//! go               { message | null, signal | null, goroutines: [{ id, state,
+//!                    frames: [{ function, file | null, line | null }] }] } | null
+//!                  panic traceback, panicking goroutine first
+//! rust             { thread | null, message | null,
+//!                    location: { file, line, column } | null,
+//!                    source: "stderr" | "stack" } | null
+//!                  panic of a Rust binary; only the location is recovered
+//!                  from the stack when stderr was not captured
+//!
+//! frame            { ip, module | null, module_offset | null, function | null,
+//!                    function_offset | null,
+//!                    locations: [{ function | null, file | null, line | null, inlined }] }
+//! ```
+//!
+//! Changes from version 1:
+//!
+//! - `signal` and `registers` are null for exits, which version 1 did not
+//!   report.
+//! - `process.runtime` joins every detected runtime with `+`, where version 1
+//!   named a single one.
+
+use std::io::Write;
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use crash_tracer_common::SIGSYS;
+use serde::Serialize;
+
+use crate::db::{CrashKind, CrashReportData, Registers};
+use crate::exit::exit_status;
+use crate::report::{si_code_name, signal_name};
+use crate::runtime::go::GoPanic;
+use crate::runtime::jvm::JvmCrashInfo;
+use crate::runtime::python::PythonStack;
+use crate::runtime::rust::RustPanic;
+use crate::symbolize::{
+    SourceLocation, SymbolizedFrame, SymbolizedStacks,
+    kernel::KernelFrame,
+    maps::{MapEntry, parse_maps},
+};
+
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct Report<'a> {
+    schema_version: u32,
+    generated: String,
+    process: Process<'a>,
+    kind: &'static str,
+    signal: Option<Signal>,
+    exit_code: Option<u32>,
+    exit_status: Option<u8>,
+    group: Option<Group<'a>>,
+    registers: Option<RegisterValues>,
+    stacks: Stacks<'a>,
+    stack_dump: Option<StackDump>,
+    memory_maps: Vec<Map<'a>>,
+    modules: Vec<Module<'a>>,
+    artifacts: Vec<Artifact<'a>>,
+    threads: Vec<Thread<'a>>,
+    jvm: Option<Jvm<'a>>,
+    python: Option<Python<'a>>,
+    go: Option<Go<'a>>,
+    rust: Option<Rust<'a>>,
+}
+
+#[derive(Serialize)]
+struct Jvm<'a> {
+    error: Option<&'a str>,
+    jre_version: Option<&'a str>,
+    vm: Option<&'a str>,
+    problematic_frame: Option<&'a str>,
+    current_thread: Option<&'a str>,
+    java_command: Option<&'a str>,
+    jvm_args: Option<&'a str>,
+    java_frames: &'a [String],
+    native_frames: &'a [String],
+    threads: &'a [String],
+    heap: &'a [String],
+    flags: Vec<JvmFlag<'a>>,
+}
+
+#[derive(Serialize)]
+struct JvmFlag<'a> {
+    name: &'a str,
+    value: &'a str,
+    origin: &'a str,
+}
+
+#[derive(Serialize)]
+struct Python<'a> {
+    error: Option<&'a str>,
+    threads: Vec<PythonThread<'a>>,
+}
+
+#[derive(Serialize)]
+struct PythonThread<'a> {
+    id: &'a str,
+    current: bool,
+    frames: Vec<PythonFrame<'a>>,
+}
+
+#[derive(Serialize)]
+struct PythonFrame<'a> {
+    file: &'a str,
+    line: Option<u32>,
+    function: &'a str,
+}
+
+#[derive(Serialize)]
+struct Go<'a> {
+    message: Option<&'a str>,
+    signal: Option<&'a str>,
+    goroutines: Vec<Goroutine<'a>>,
+}
+
+#[derive(Serialize)]
+struct Goroutine<'a> {
+    id: u64,
+    state: &'a str,
+    frames: Vec<GoFrame<'a>>,
+}
+
+#[derive(Serialize)]
+struct GoFrame<'a> {
+    function: &'a str,
+    file: Option<&'a str>,
+    line: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct Rust<'a> {
+    thread: Option<&'a str>,
+    message: Option<&'a str>,
+    location: Option<PanicSite<'a>>,
+    source: &'static str,
+}
+
+#[derive(Serialize)]
+struct PanicSite<'a> {
+    file: &'a str,
+    line: u32,
+    column: u32,
+}
+
+#[derive(Serialize)]
+struct Thread<'a> {
+    tid: u32,
+    cmd: &'a str,
+    registers: RegisterValues,
+    stacks: ThreadStacks<'a>,
+    stack_dump: Option<StackDump>,
+}
+
+#[derive(Serialize)]
+struct ThreadStacks<'a> {
+    user: Vec<Frame<'a>>,
+    unwound: Vec<Frame<'a>>,
+}
+
+#[derive(Serialize)]
+struct Process<'a> {
+    cmd: &'a str,
+    pid: u32,
+    tid: u32,
+    ns_pid: Option<u32>,
+    ns_tid: Option<u32>,
+    runtime: &'a str,
+    runtimes: Vec<Runtime<'a>>,
+    container: Container<'a>,
+}
+
+#[derive(Serialize)]
+struct Container<'a> {
+    cgroup_id: Option<u64>,
+    cgroup_path: Option<&'a str>,
+    id: Option<&'a str>,
+    runtime: Option<&'a str>,
+    pod_uid: Option<&'a str>,
+    pod_name: Option<&'a str>,
+    pod_namespace: Option<&'a str>,
+    pid_ns: Option<u32>,
+    mnt_ns: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct Runtime<'a> {
+    kind: String,
+    version: Option<&'a str>,
+    path: &'a str,
+    module: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Signal {
+    number: i32,
+    name: &'static str,
+    code: i32,
+    code_name: &'static str,
+    fault_addr: Option<Hex>,
+    syscall: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct Group<'a> {
+    signature: &'a str,
+    count: i64,
+    first_seen: &'a str,
+    last_seen: &'a str,
+    source: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct RegisterValues {
+    rip: Hex,
+    rsp: Hex,
+    rbp: Hex,
+    rax: Hex,
+    rbx: Hex,
+    rcx: Hex,
+    rdx: Hex,
+    rsi: Hex,
+    rdi: Hex,
+    r8: Hex,
+    r9: Hex,
+    r10: Hex,
+    r11: Hex,
+    r12: Hex,
+    r13: Hex,
+    r14: Hex,
+    r15: Hex,
+    rflags: Hex,
+}
+
+#[derive(Serialize)]
+struct Stacks<'a> {
+    user: Vec<Frame<'a>>,
+    unwound: Vec<Frame<'a>>,
+    kernel: Vec<KernelStackFrame<'a>>,
+}
+
+#[derive(Serialize)]
+struct Frame<'a> {
+    ip: Hex,
+    module: Option<&'a str>,
+    module_offset: Option<Hex>,
+    function: Option<&'a str>,
+    function_offset: Option<Hex>,
+    locations: Vec<Location<'a>>,
+}
+
+#[derive(Serialize)]
+struct Location<'a> {
+    function: Option<&'a str>,
+    file: Option<&'a str>,
+    line: Option<u32>,
+    inlined: bool,
+}
+
+#[derive(Serialize)]
+struct KernelStackFrame<'a> {
+    ip: Hex,
+    symbol: Option<&'a str>,
+    offset: Hex,
+    module: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct StackDump {
+    rsp: Hex,
+    length: usize,
+    data_base64: String,
+}
+
+#[derive(Serialize)]
+struct Map<'a> {
+    start: Hex,
+    end: Hex,
+    perms: &'a str,
+    offset: Hex,
+    dev: &'a str,
+    inode: u64,
+    path: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Module<'a> {
+    path: &'a str,
+    build_id: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Artifact<'a> {
+    filename: &'a str,
+    full_path: &'a str,
+    size: Option<usize>,
+    content_base64: Option<String>,
+}
+
+/// A `u64` serialized as a `"0x…"` string.
+struct Hex(u64);
+
+impl Serialize for Hex {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("0x{:x}", self.0))
+    }
+}
+
+pub fn write_report(
+    w: &mut impl Write,
+    data: &CrashReportData,
+    stacks: &SymbolizedStacks,
+) -> anyhow::Result<()> {
+    let maps = parse_maps(&data.memory_maps);
+    let jvm = JvmCrashInfo::from_artifacts(&data.artifacts);
+    let python = PythonStack::from_artifacts(&data.artifacts);
+    let go = GoPanic::from_artifacts(&data.artifacts);
+    let rust = RustPanic::recover(data, stacks);
+    let is_signal = data.kind == CrashKind::Signal;
+    let container = &data.container;
+
+    let report = Report {
+        schema_version: SCHEMA_VERSION,
+        generated: chrono::Utc::now().to_rfc3339(),
+        process: Process {
+            cmd: &data.cmd,
+            pid: data.pid,
+            tid: data.tid,
+            ns_pid: data.ns_pid,
+            ns_tid: data.ns_tid,
+            runtime: &data.runtime,
+            runtimes: data
+                .runtimes
+                .iter()
+                .map(|r| Runtime {
+                    kind: r.kind.to_string(),
+                    version: r.version.as_deref(),
+                    path: &r.path,
+                    module: r.module.as_deref(),
+                })
+                .collect(),
+            container: Container {
+                cgroup_id: (container.cgroup_id != 0).then_some(container.cgroup_id),
+                cgroup_path: container.cgroup_path.as_deref(),
+                id: container.container_id.as_deref(),
+                runtime: container.container_runtime.as_deref(),
+                pod_uid: container.pod_uid.as_deref(),
+                pod_name: container.pod_name.as_deref(),
+                pod_namespace: container.pod_namespace.as_deref(),
+                pid_ns: (container.pid_ns != 0).then_some(container.pid_ns),
+                mnt_ns: (container.mnt_ns != 0).then_some(container.mnt_ns),
+            },
+        },
+        kind: data.kind.as_str(),
+        signal: is_signal.then(|| Signal {
+            number: data.signal,
+            name: signal_name(data.signal),
+            code: data.si_code,
+            code_name: si_code_name(data.signal, data.si_code),
+            fault_addr: (data.fault_addr != 0).then_some(Hex(data.fault_addr)),
+            syscall: data
+                .syscall_nr
+                .filter(|nr| data.signal == SIGSYS && *nr >= 0),
+        }),
+        exit_code: data.exit_code,
+        exit_status: data.exit_code.filter(|_| !is_signal).and_then(exit_status),
+        group: data.group.as_ref().map(|g| Group {
+            signature: &g.signature,
+            count: g.count,
+            first_seen: &g.first_seen,
+            last_seen: &g.last_seen,
+            source: g.source.as_deref(),
+        }),
+        registers: is_signal.then(|| RegisterValues::from(&data.registers)),
+        stacks: Stacks {
+            user: stacks.user.iter().map(Frame::from).collect(),
+            unwound: stacks.unwound.iter().map(Frame::from).collect(),
+            kernel: data
+                .kernel_stack
+                .iter()
+                .map(KernelStackFrame::from)
+                .collect(),
+        },
+        stack_dump: data.stack_dump.as_ref().map(StackDump::from),
+        memory_maps: maps.iter().map(Map::from).collect(),
+        modules: data
+            .modules
+            .iter()
+            .map(|m| Module {
+                path: &m.path,
+                build_id: m.build_id.as_deref(),
+            })
+            .collect(),
+        artifacts: data
+            .artifacts
+            .iter()
+            .map(|a| Artifact {
+                filename: &a.filename,
+                full_path: &a.full_path,
+                size: a.content.as_ref().map(Vec::len),
+                content_base64: a.content.as_ref().map(|c| BASE64.encode(c)),
+            })
+            .collect(),
+        threads: data
+            .threads
+            .iter()
+            .zip(&stacks.threads)
+            .map(|(thread, thread_stacks)| Thread {
+                tid: thread.tid,
+                cmd: &thread.cmd,
+                registers: RegisterValues::from(&thread.registers),
+                stacks: ThreadStacks {
+                    user: thread_stacks.user.iter().map(Frame::from).collect(),
+                    unwound: thread_stacks.unwound.iter().map(Frame::from).collect(),
+                },
+                stack_dump: thread.stack_dump.as_ref().map(StackDump::from),
+            })
+            .collect(),
+        jvm: jvm.as_ref().map(Jvm::from),
+        python: python.as_ref().map(Python::from),
+        go: go.as_ref().map(Go::from),
+        rust: rust.as_ref().map(Rust::from),
+    };
+
+    serde_json::to_writer_pretty(&mut *w, &report)?;
+    writeln!(w)?;
+    Ok(())
+}
+
+impl From<&Registers> for RegisterValues {
+    fn from(r: &Registers) -> Self {
+        Self {
+            rip: Hex(r.rip),
+            rsp: Hex(r.rsp),
+            rbp: Hex(r.rbp),
+            rax: Hex(r.rax),
+            rbx: Hex(r.rbx),
+            rcx: Hex(r.rcx),
+            rdx: Hex(r.rdx),
+            rsi: Hex(r.rsi),
+            rdi: Hex(r.rdi),
+            r8: Hex(r.r8),
+            r9: Hex(r.r9),
+            r10: Hex(r.r10),
+            r11: Hex(r.r11),
+            r12: Hex(r.r12),
+            r13: Hex(r.r13),
+            r14: Hex(r.r14),
+            r15: Hex(r.r15),
+            rflags: Hex(r.rflags),
+        }
+    }
+}
+
+impl<'a> From<&'a JvmCrashInfo> for Jvm<'a> {
+    fn from(jvm: &'a JvmCrashInfo) -> Self {
+        Self {
+            error: jvm.error.as_deref(),
+            jre_version: jvm.jre_version.as_deref(),
+            vm: jvm.vm.as_deref(),
+            problematic_frame: jvm.problematic_frame.as_deref(),
+            current_thread: jvm.current_thread.as_deref(),
+            java_command: jvm.java_command.as_deref(),
+            jvm_args: jvm.jvm_args.as_deref(),
+            java_frames: &jvm.java_frames,
+            native_frames: &jvm.native_frames,
+            threads: &jvm.threads,
+            heap: &jvm.heap,
+            flags: jvm
+                .flags
+                .iter()
+                .map(|f| JvmFlag {
+                    name: &f.name,
+                    value: &f.value,
+                    origin: &f.origin,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a PythonStack> for Python<'a> {
+    fn from(python: &'a PythonStack) -> Self {
+        Self {
+            error: python.error.as_deref(),
+            threads: python
+                .threads
+                .iter()
+                .map(|t| PythonThread {
+                    id: &t.id,
+                    current: t.current,
+                    frames: t
+                        .frames
+                        .iter()
+                        .map(|f| PythonFrame {
+                            file: &f.file,
+                            line: f.line,
+                            function: &f.function,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a GoPanic> for Go<'a> {
+    fn from(panic: &'a GoPanic) -> Self {
+        Self {
+            message: panic.message.as_deref(),
+            signal: panic.signal.as_deref(),
+            goroutines: panic
+                .goroutines
+                .iter()
+                .map(|g| Goroutine {
+                    id: g.id,
+                    state: &g.state,
+                    frames: g
+                        .frames
+                        .iter()
+                        .map(|f| GoFrame {
+                            function: &f.function,
+                            file: f.file.as_deref(),
+                            line: f.line,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a RustPanic> for Rust<'a> {
+    fn from(panic: &'a RustPanic) -> Self {
+        Self {
+            thread: panic.thread.as_deref(),
+            message: panic.message.as_deref(),
+            location: panic.location.as_ref().map(|l| PanicSite {
+                file: &l.file,
+                line: l.line,
+                column: l.column,
+            }),
+            source: panic.source,
+        }
+    }
+}
+
+impl From<&(u64, Vec<u8>)> for StackDump {
+    fn from((rsp, dump): &(u64, Vec<u8>)) -> Self {
+        Self {
+            rsp: Hex(*rsp),
+            length: dump.len(),
+            data_base64: BASE64.encode(dump),
+        }
+    }
+}
+
+impl<'a> From<&'a SymbolizedFrame> for Frame<'a> {
+    fn from(frame: &'a SymbolizedFrame) -> Self {
+        let physical = frame.locations.iter().find(|loc| !loc.inlined);
+        Self {
+            ip: Hex(frame.ip),
+            module: frame.module.as_deref(),
+            module_offset: frame.module_offset.map(Hex),
+            function: physical.and_then(|loc| loc.function.as_deref()),
+            function_offset: physical.and_then(|loc| loc.function_offset).map(Hex),
+            locations: frame.locations.iter().map(Location::from).collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a SourceLocation> for Location<'a> {
+    fn from(loc: &'a SourceLocation) -> Self {
+        Self {
+            function: loc.function.as_deref(),
+            file: loc.file.as_deref(),
+            line: loc.line,
+            inlined: loc.inlined,
+        }
+    }
+}
+
+impl<'a> From<&'a KernelFrame> for KernelStackFrame<'a> {
+    fn from(frame: &'a KernelFrame) -> Self {
+        Self {
+            ip: Hex(frame.ip),
+            symbol: frame.symbol.as_deref(),
+            offset: Hex(frame.offset),
+            module: frame.module.as_deref(),
+        }
+    }
+}
+
+impl<'a> From<&'a MapEntry> for Map<'a> {
+    fn from(entry: &'a MapEntry) -> Self {
+        Self {
+            start: Hex(entry.start),
+            end: Hex(entry.end),
+            perms: &entry.perms,
+            offset: Hex(entry.offset),
+            dev: &entry.dev,
+            inode: entry.inode,
+            path: entry.path.as_deref(),
+        }
+    }
+}
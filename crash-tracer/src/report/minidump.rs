@@ -0,0 +1,377 @@
+//! Minidump export, readable by Breakpad/Crashpad tooling such as
+//! `minidump-stackwalk`.
+//!
+//! Only what a capture actually contains is written: the context of the
+//! crashing thread and any other captured threads, their raw stack dumps as
+//! stack memory, one module per mapped file (with a Breakpad `BpEL` CodeView
+//! record carrying the ELF build-id), the exception, and the raw
+//! `/proc/<pid>/maps` text.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use chrono::NaiveDateTime;
+
+use crate::db::{CrashReportData, Registers};
+use crate::symbolize::maps::{MapEntry, parse_maps};
+
+const SIGNATURE: u32 = 0x504d_444d; // "MDMP"
+const VERSION: u32 = 0xa793;
+
+const HEADER_SIZE: u32 = 32;
+const DIRECTORY_ENTRY_SIZE: u32 = 12;
+const CONTEXT_AMD64_SIZE: usize = 1232;
+const MODULE_SIZE: u32 = 108;
+
+// Stream types.
+const THREAD_LIST_STREAM: u32 = 3;
+const MODULE_LIST_STREAM: u32 = 4;
+const MEMORY_LIST_STREAM: u32 = 5;
+const EXCEPTION_STREAM: u32 = 6;
+const SYSTEM_INFO_STREAM: u32 = 7;
+const MISC_INFO_STREAM: u32 = 15;
+const LINUX_MAPS_STREAM: u32 = 0x4767_0009;
+
+const PROCESSOR_ARCHITECTURE_AMD64: u16 = 9;
+const PLATFORM_ID_LINUX: u32 = 0x8201;
+const CONTEXT_AMD64: u32 = 0x0010_0000;
+const CONTEXT_CONTROL: u32 = CONTEXT_AMD64 | 0x1;
+const CONTEXT_INTEGER: u32 = CONTEXT_AMD64 | 0x2;
+const CV_SIGNATURE_ELF: u32 = 0x4270_454c; // "BpEL"
+const MISC1_PROCESS_ID: u32 = 0x1;
+
+/// RVA and size of a blob inside the dump.
+#[derive(Clone, Copy, Default)]
+struct Location {
+    size: u32,
+    rva: u32,
+}
+
+/// Where one thread's context and stack memory were written.
+struct ThreadRecord {
+    tid: u32,
+    context: Location,
+    stack: Option<(u64, Location)>,
+}
+
+/// A module as minidump consumers see it: one entry per mapped file, spanning
+/// all of that file's mappings.
+struct Module<'a> {
+    path: &'a str,
+    base: u64,
+    end: u64,
+    executable: bool,
+    build_id: Option<Vec<u8>>,
+}
+
+/// Append-only buffer; RVAs are offsets into it.
+struct Buffer(Vec<u8>);
+
+impl Buffer {
+    fn rva(&self) -> u32 {
+        self.0.len() as u32
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn zeros(&mut self, n: usize) {
+        self.0.resize(self.0.len() + n, 0);
+    }
+
+    fn location(&mut self, loc: Location) {
+        self.u32(loc.size);
+        self.u32(loc.rva);
+    }
+
+    fn align(&mut self, to: usize) {
+        let padding = self.0.len().next_multiple_of(to) - self.0.len();
+        self.zeros(padding);
+    }
+
+    fn blob(&mut self, data: &[u8]) -> Location {
+        self.align(8);
+        let rva = self.rva();
+        self.0.extend_from_slice(data);
+        Location {
+            size: data.len() as u32,
+            rva,
+        }
+    }
+
+    /// `MINIDUMP_STRING`: byte length, then UTF-16LE with a terminating NUL.
+    fn string(&mut self, s: &str) -> u32 {
+        self.align(4);
+        let rva = self.rva();
+        let units: Vec<u16> = s.encode_utf16().collect();
+        self.u32((units.len() * 2) as u32);
+        for unit in units {
+            self.u16(unit);
+        }
+        self.u16(0);
+        rva
+    }
+
+    fn stream(
+        &mut self,
+        directory: &mut Vec<(u32, Location)>,
+        kind: u32,
+        f: impl FnOnce(&mut Self),
+    ) {
+        self.align(8);
+        let rva = self.rva();
+        f(self);
+        directory.push((
+            kind,
+            Location {
+                size: self.rva() - rva,
+                rva,
+            },
+        ));
+    }
+}
+
+pub fn write_minidump(w: &mut impl Write, data: &CrashReportData) -> anyhow::Result<()> {
+    let maps = parse_maps(&data.memory_maps);
+    let modules = collect_modules(&maps, data);
+
+    let mut buf = Buffer(Vec::new());
+    buf.zeros(HEADER_SIZE as usize);
+    // Directory size is fixed by the set of streams written below.
+    const STREAM_COUNT: u32 = 7;
+    buf.zeros((STREAM_COUNT * DIRECTORY_ENTRY_SIZE) as usize);
+
+    // The faulting thread first, then any other threads captured with it.
+    let threads: Vec<ThreadRecord> = std::iter::once((data.tid, &data.registers, &data.stack_dump))
+        .chain(
+            data.threads
+                .iter()
+                .map(|t| (t.tid, &t.registers, &t.stack_dump)),
+        )
+        .map(|(tid, registers, stack_dump)| ThreadRecord {
+            tid,
+            context: buf.blob(&thread_context(registers)),
+            stack: stack_dump
+                .as_ref()
+                .map(|(rsp, dump)| (*rsp, buf.blob(dump))),
+        })
+        .collect();
+    let context = threads[0].context;
+
+    let module_records: Vec<(u32, Location)> = modules
+        .iter()
+        .map(|module| {
+            let name_rva = buf.string(module.path);
+            let cv_record = match &module.build_id {
+                Some(build_id) => {
+                    let mut record = CV_SIGNATURE_ELF.to_le_bytes().to_vec();
+                    record.extend_from_slice(build_id);
+                    buf.blob(&record)
+                }
+                None => Location::default(),
+            };
+            (name_rva, cv_record)
+        })
+        .collect();
+    let csd_version_rva = buf.string("");
+
+    let mut directory = Vec::new();
+
+    buf.stream(&mut directory, THREAD_LIST_STREAM, |b| {
+        b.u32(threads.len() as u32);
+        for thread in &threads {
+            b.u32(thread.tid);
+            b.u32(0); // suspend_count
+            b.u32(0); // priority_class
+            b.u32(0); // priority
+            b.u64(0); // teb
+            memory_descriptor(b, thread.stack);
+            b.location(thread.context);
+        }
+    });
+
+    buf.stream(&mut directory, MODULE_LIST_STREAM, |b| {
+        b.u32(modules.len() as u32);
+        for (module, (name_rva, cv_record)) in modules.iter().zip(&module_records) {
+            let start = b.rva();
+            b.u64(module.base);
+            b.u32((module.end - module.base) as u32);
+            b.u32(0); // checksum
+            b.u32(0); // time_date_stamp
+            b.u32(*name_rva);
+            b.zeros(52); // VS_FIXEDFILEINFO
+            b.location(*cv_record);
+            b.location(Location::default()); // misc_record
+            b.zeros(16); // reserved
+            debug_assert_eq!(b.rva() - start, MODULE_SIZE);
+        }
+    });
+
+    buf.stream(&mut directory, MEMORY_LIST_STREAM, |b| {
+        let stacks: Vec<_> = threads.iter().filter(|t| t.stack.is_some()).collect();
+        b.u32(stacks.len() as u32);
+        for thread in stacks {
+            memory_descriptor(b, thread.stack);
+        }
+    });
+
+    buf.stream(&mut directory, EXCEPTION_STREAM, |b| {
+        b.u32(data.tid);
+        b.u32(0); // alignment
+        // Breakpad convention on Linux: code is the signal, flags the si_code.
+        b.u32(data.signal as u32);
+        b.u32(data.si_code as u32);
+        b.u64(0); // exception_record
+        b.u64(data.fault_addr);
+        b.u32(0); // number_parameters
+        b.u32(0); // alignment
+        b.zeros(15 * 8); // exception_information
+        b.location(context);
+    });
+
+    buf.stream(&mut directory, SYSTEM_INFO_STREAM, |b| {
+        b.u16(PROCESSOR_ARCHITECTURE_AMD64);
+        b.u16(0); // processor_level
+        b.u16(0); // processor_revision
+        b.u8(0); // number_of_processors
+        b.u8(0); // product_type
+        b.u32(0); // major_version
+        b.u32(0); // minor_version
+        b.u32(0); // build_number
+        b.u32(PLATFORM_ID_LINUX);
+        b.u32(csd_version_rva);
+        b.u16(0); // suite_mask
+        b.u16(0); // reserved
+        b.zeros(24); // CPU_INFORMATION
+    });
+
+    buf.stream(&mut directory, MISC_INFO_STREAM, |b| {
+        b.u32(24); // size_of_info
+        b.u32(MISC1_PROCESS_ID);
+        b.u32(data.pid);
+        b.u32(0); // process_create_time
+        b.u32(0); // process_user_time
+        b.u32(0); // process_kernel_time
+    });
+
+    buf.stream(&mut directory, LINUX_MAPS_STREAM, |b| {
+        for line in &data.memory_maps {
+            b.0.extend_from_slice(line.as_bytes());
+            b.u8(b'\n');
+        }
+    });
+
+    debug_assert_eq!(directory.len() as u32, STREAM_COUNT);
+
+    let mut header = Buffer(Vec::with_capacity(
+        (HEADER_SIZE + STREAM_COUNT * DIRECTORY_ENTRY_SIZE) as usize,
+    ));
+    header.u32(SIGNATURE);
+    header.u32(VERSION);
+    header.u32(directory.len() as u32);
+    header.u32(HEADER_SIZE); // stream_directory_rva
+    header.u32(0); // checksum
+    header.u32(crash_time(data));
+    header.u64(0); // flags: MiniDumpNormal
+    for (kind, location) in &directory {
+        header.u32(*kind);
+        header.location(*location);
+    }
+    buf.0[..header.0.len()].copy_from_slice(&header.0);
+
+    w.write_all(&buf.0)?;
+    Ok(())
+}
+
+/// `MINIDUMP_MEMORY_DESCRIPTOR` for the stack copy, or an empty one.
+fn memory_descriptor(b: &mut Buffer, stack: Option<(u64, Location)>) {
+    let (start, location) = stack.unwrap_or_default();
+    b.u64(start);
+    b.location(location);
+}
+
+/// `CONTEXT_AMD64` with the control and integer registers filled in.
+fn thread_context(r: &Registers) -> Vec<u8> {
+    let mut ctx = vec![0u8; CONTEXT_AMD64_SIZE];
+    let mut put = |offset: usize, bytes: &[u8]| {
+        ctx[offset..offset + bytes.len()].copy_from_slice(bytes);
+    };
+
+    put(0x30, &(CONTEXT_CONTROL | CONTEXT_INTEGER).to_le_bytes());
+    put(0x44, &(r.rflags as u32).to_le_bytes());
+    // rax, rcx, rdx, rbx, rsp, rbp, rsi, rdi, r8-r15, rip are contiguous from 0x78.
+    let gprs = [
+        r.rax, r.rcx, r.rdx, r.rbx, r.rsp, r.rbp, r.rsi, r.rdi, r.r8, r.r9, r.r10, r.r11, r.r12,
+        r.r13, r.r14, r.r15, r.rip,
+    ];
+    for (i, value) in gprs.iter().enumerate() {
+        put(0x78 + i * 8, &value.to_le_bytes());
+    }
+    ctx
+}
+
+/// One module per mapped file with code in it, based at its lowest mapping.
+fn collect_modules<'a>(maps: &'a [MapEntry], data: &'a CrashReportData) -> Vec<Module<'a>> {
+    let build_ids: HashMap<&str, &str> = data
+        .modules
+        .iter()
+        .filter_map(|m| Some((m.path.as_str(), m.build_id.as_deref()?)))
+        .collect();
+
+    let mut modules: Vec<Module> = Vec::new();
+    for entry in maps {
+        let Some(path) = entry.path.as_deref().filter(|p| p.starts_with('/')) else {
+            continue;
+        };
+        if entry.inode == 0 {
+            continue;
+        }
+        let path = path.trim_end_matches(" (deleted)");
+        match modules.iter_mut().find(|m| m.path == path) {
+            Some(module) => {
+                module.base = module.base.min(entry.start);
+                module.end = module.end.max(entry.end);
+                module.executable |= entry.is_executable();
+            }
+            None => modules.push(Module {
+                path,
+                base: entry.start,
+                end: entry.end,
+                executable: entry.is_executable(),
+                build_id: build_ids.get(path).and_then(|id| decode_hex(id)),
+            }),
+        }
+    }
+    // Mapped data files (locale archives, caches) are not code modules.
+    modules.retain(|m| m.executable);
+    modules
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn crash_time(data: &CrashReportData) -> u32 {
+    NaiveDateTime::parse_from_str(&data.created_at, "%Y-%m-%d %H:%M:%S")
+        .map(|t| t.and_utc().timestamp() as u32)
+        .unwrap_or(0)
+}
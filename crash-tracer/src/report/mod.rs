@@ -0,0 +1,801 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use aya::maps::stack_trace::StackTrace;
+use crash_tracer_common::{
+    MAX_SIGNAL, SIGBUS, SIGFPE, SIGILL, SIGKILL, SIGSEGV, SIGSYS, SIGTRAP, SIGXCPU, SIGXFSZ,
+    SignalDeliverEvent, StackDump,
+};
+
+use crate::db;
+use crate::exit::exit_status;
+use crate::runtime::go::GoPanic;
+use crate::runtime::jvm::JvmCrashInfo;
+use crate::runtime::python::PythonStack;
+use crate::runtime::rust::RustPanic;
+use crate::state::container::ContainerInfo;
+use crate::state::map::{ProcessInfo, describe_runtimes};
+use crate::symbolize::{SymbolizedFrame, SymbolizedStacks, kernel::KernelFrame};
+
+pub mod json;
+pub mod minidump;
+
+/// Which report files to write for each crash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Human-readable `crash_*.txt`.
+    Text,
+    /// `crash_*.json`, in the versioned schema described in [`json`].
+    Json,
+    /// Both of the above, sharing a file stem.
+    Both,
+}
+
+/// Core formatting — writes a crash report to any `Write` target.
+fn write_report(
+    w: &mut impl Write,
+    event: &SignalDeliverEvent,
+    stack_trace: Option<&StackTrace>,
+    kernel_stack: &[KernelFrame],
+    stack_dump: Option<&StackDump>,
+    map: Option<&ProcessInfo>,
+) -> anyhow::Result<()> {
+    let cmd = std::str::from_utf8(&event.cmd)
+        .unwrap_or("<unknown>")
+        .trim_end_matches('\0');
+
+    writeln!(w, "Crash Report")?;
+    writeln!(w, "============")?;
+    writeln!(w, "Generated: {}", chrono::Utc::now().to_rfc3339())?;
+    writeln!(w)?;
+    writeln!(
+        w,
+        "Process: {} (PID: {}, TID: {}{})",
+        cmd,
+        event.pid,
+        event.tid,
+        namespace_ids(event.pid, event.tid, Some(event.ns_pid), Some(event.ns_tid))
+    )?;
+    if let Some(process_info) = map {
+        write_container(w, &process_info.container)?;
+    }
+    writeln!(
+        w,
+        "Signal:  {} ({})",
+        signal_name(event.signal),
+        event.signal
+    )?;
+    writeln!(
+        w,
+        "Code:    {} ({})",
+        si_code_name(event.signal, event.si_code),
+        event.si_code
+    )?;
+
+    if event.fault_addr != 0 {
+        writeln!(w, "Fault:   0x{:016x}", event.fault_addr)?;
+    }
+
+    if let Some(note) = signal_note(event.signal, Some(event.syscall_nr)) {
+        writeln!(w, "Note:    {note}")?;
+    }
+
+    if let Some(process_info) = map {
+        writeln!(w)?;
+        writeln!(
+            w,
+            "Detected Runtime: {}",
+            describe_runtimes(&process_info.runtimes)
+        )?;
+    }
+
+    writeln!(w)?;
+    writeln!(w, "Registers")?;
+    writeln!(w, "---------")?;
+    writeln!(
+        w,
+        "  RIP: 0x{:016x}  RFLAGS: 0x{:016x}",
+        event.rip, event.rflags
+    )?;
+    writeln!(
+        w,
+        "  RSP: 0x{:016x}  RBP:    0x{:016x}",
+        event.rsp, event.rbp
+    )?;
+    writeln!(
+        w,
+        "  RAX: 0x{:016x}  RBX:    0x{:016x}",
+        event.rax, event.rbx
+    )?;
+    writeln!(
+        w,
+        "  RCX: 0x{:016x}  RDX:    0x{:016x}",
+        event.rcx, event.rdx
+    )?;
+    writeln!(
+        w,
+        "  RSI: 0x{:016x}  RDI:    0x{:016x}",
+        event.rsi, event.rdi
+    )?;
+    writeln!(w, "  R8:  0x{:016x}  R9:     0x{:016x}", event.r8, event.r9)?;
+    writeln!(
+        w,
+        "  R10: 0x{:016x}  R11:    0x{:016x}",
+        event.r10, event.r11
+    )?;
+    writeln!(
+        w,
+        "  R12: 0x{:016x}  R13:    0x{:016x}",
+        event.r12, event.r13
+    )?;
+    writeln!(
+        w,
+        "  R14: 0x{:016x}  R15:    0x{:016x}",
+        event.r14, event.r15
+    )?;
+
+    if !kernel_stack.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "Kernel Stack:")?;
+        writeln!(w, "---------")?;
+        write_kernel_frames(w, kernel_stack)?;
+    }
+
+    if let Some(trace) = stack_trace {
+        writeln!(w)?;
+        writeln!(w, "User Stack:")?;
+        writeln!(w, "---------")?;
+        for (i, frame) in trace.frames().iter().enumerate() {
+            if frame.ip == 0 {
+                break;
+            }
+            writeln!(w, "  #{:2}: 0x{:016x}", i, frame.ip)?;
+        }
+    }
+
+    if let Some(dump) = stack_dump {
+        let len = dump.len as usize;
+        writeln!(w)?;
+        writeln!(w, "Raw Stack ({} bytes from 0x{:016x})", len, dump.rsp)?;
+        writeln!(w, "---------")?;
+        for offset in (0..len).step_by(16) {
+            let addr = dump.rsp + offset as u64;
+            let end = (offset + 16).min(len);
+            let chunk = &dump.data[offset..end];
+
+            write!(w, "  0x{:016x}:", addr)?;
+            for (i, byte) in chunk.iter().enumerate() {
+                if i % 2 == 0 {
+                    write!(w, " ")?;
+                }
+                write!(w, "{:02x}", byte)?;
+            }
+            // Pad remaining space if chunk < 16 bytes
+            let missing = 16 - chunk.len();
+            for i in 0..missing {
+                if (chunk.len() + i) % 2 == 0 {
+                    write!(w, " ")?;
+                }
+                write!(w, "  ")?;
+            }
+
+            write!(w, "  |")?;
+            for byte in chunk {
+                let ch = if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                };
+                write!(w, "{}", ch)?;
+            }
+            writeln!(w, "|")?;
+        }
+    }
+
+    if let Some(process_info) = map {
+        writeln!(w)?;
+        writeln!(w, "Memory Maps")?;
+        writeln!(w, "-----------")?;
+        for line in &process_info.maps {
+            writeln!(w, "{}", line)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Print a crash summary to stdout.
+pub fn print_to_console(
+    event: &SignalDeliverEvent,
+    stack_trace: Option<&StackTrace>,
+    kernel_stack: &[KernelFrame],
+    process_info: Option<&ProcessInfo>,
+) {
+    let mut stdout = std::io::stdout().lock();
+    // Console output omits memory maps and raw stack (they can be very long)
+    if let Err(e) = write_report(
+        &mut stdout,
+        event,
+        stack_trace,
+        kernel_stack,
+        None,
+        process_info,
+    ) {
+        log::error!("Failed to write to stdout: {}", e);
+    }
+}
+
+pub fn signal_name(sig: i32) -> &'static str {
+    match sig {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        10 => "SIGUSR1",
+        11 => "SIGSEGV",
+        12 => "SIGUSR2",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        16 => "SIGSTKFLT",
+        17 => "SIGCHLD",
+        18 => "SIGCONT",
+        19 => "SIGSTOP",
+        20 => "SIGTSTP",
+        21 => "SIGTTIN",
+        22 => "SIGTTOU",
+        23 => "SIGURG",
+        24 => "SIGXCPU",
+        25 => "SIGXFSZ",
+        26 => "SIGVTALRM",
+        27 => "SIGPROF",
+        28 => "SIGWINCH",
+        29 => "SIGIO",
+        30 => "SIGPWR",
+        31 => "SIGSYS",
+        _ => "UNKNOWN",
+    }
+}
+
+const MAX_SIGNAL_NUMBER: i32 = MAX_SIGNAL as i32;
+
+/// Accept `11`, `SIGSEGV` or `segv`.
+pub fn parse_signal(s: &str) -> Result<i32, String> {
+    if let Ok(number) = s.parse::<i32>() {
+        return match number {
+            1..=MAX_SIGNAL_NUMBER => Ok(number),
+            _ => Err(format!("signal {number} out of range 1-{MAX_SIGNAL}")),
+        };
+    }
+    let name = s.to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    (1..=MAX_SIGNAL_NUMBER)
+        .find(|sig| signal_name(*sig).strip_prefix("SIG") == Some(name))
+        .ok_or_else(|| format!("unknown signal `{s}`"))
+}
+
+/// Name of `si_code` for `sig`, from `<asm-generic/siginfo.h>`. Codes that
+/// aren't signal specific (sent by `kill`, `tgkill`, the kernel) are checked
+/// last.
+pub fn si_code_name(sig: i32, code: i32) -> &'static str {
+    match (sig, code) {
+        (SIGSEGV, 1) => "SEGV_MAPERR",
+        (SIGSEGV, 2) => "SEGV_ACCERR",
+        (SIGSEGV, 3) => "SEGV_BNDERR",
+        (SIGSEGV, 4) => "SEGV_PKUERR",
+        (SIGBUS, 1) => "BUS_ADRALN",
+        (SIGBUS, 2) => "BUS_ADRERR",
+        (SIGBUS, 3) => "BUS_OBJERR",
+        (SIGBUS, 4) => "BUS_MCEERR_AR",
+        (SIGBUS, 5) => "BUS_MCEERR_AO",
+        (SIGFPE, 1) => "FPE_INTDIV",
+        (SIGFPE, 2) => "FPE_INTOVF",
+        (SIGFPE, 3) => "FPE_FLTDIV",
+        (SIGFPE, 4) => "FPE_FLTOVF",
+        (SIGFPE, 5) => "FPE_FLTUND",
+        (SIGFPE, 6) => "FPE_FLTRES",
+        (SIGFPE, 7) => "FPE_FLTINV",
+        (SIGFPE, 8) => "FPE_FLTSUB",
+        (SIGILL, 1) => "ILL_ILLOPC",
+        (SIGILL, 2) => "ILL_ILLOPN",
+        (SIGILL, 3) => "ILL_ILLADR",
+        (SIGILL, 4) => "ILL_ILLTRP",
+        (SIGILL, 5) => "ILL_PRVOPC",
+        (SIGILL, 6) => "ILL_PRVREG",
+        (SIGILL, 7) => "ILL_COPROC",
+        (SIGILL, 8) => "ILL_BADSTK",
+        (SIGTRAP, 1) => "TRAP_BRKPT",
+        (SIGTRAP, 2) => "TRAP_TRACE",
+        (SIGTRAP, 3) => "TRAP_BRANCH",
+        (SIGTRAP, 4) => "TRAP_HWBKPT",
+        (SIGSYS, 1) => "SYS_SECCOMP",
+        (SIGSYS, 2) => "SYS_USER_DISPATCH",
+        (_, 0) => "SI_USER",
+        (_, 0x80) => "SI_KERNEL",
+        (_, -1) => "SI_QUEUE",
+        (_, -6) => "SI_TKILL",
+        _ => "UNKNOWN",
+    }
+}
+
+/// One-line explanation for signals whose meaning isn't obvious from the name.
+/// `syscall_nr` is the syscall a seccomp filter rejected, negative otherwise.
+pub fn signal_note(signal: i32, syscall_nr: Option<i64>) -> Option<String> {
+    match signal {
+        SIGSYS => syscall_nr
+            .filter(|nr| *nr >= 0)
+            .map(|nr| format!("Syscall: {nr}")),
+        SIGKILL => Some("Killed by the OOM killer".to_string()),
+        SIGXCPU => Some("CPU time limit (RLIMIT_CPU) exceeded".to_string()),
+        SIGXFSZ => Some("File size limit (RLIMIT_FSIZE) exceeded".to_string()),
+        _ => None,
+    }
+}
+
+pub fn save_from_db(
+    output_dir: &Path,
+    data: &db::CrashReportData,
+    stacks: &SymbolizedStacks,
+    format: ReportFormat,
+    minidump: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let stem = format!("crash_{}_{}_{}", data.cmd, data.pid, timestamp);
+    save_as(output_dir, &stem, data, stacks, format, minidump)
+}
+
+/// Write the report files for `data` as `<stem>.txt` and/or `<stem>.json`,
+/// plus `<stem>.dmp` if `minidump` is set and the crash has registers to
+/// put in one.
+pub fn save_as(
+    output_dir: &Path,
+    stem: &str,
+    data: &db::CrashReportData,
+    stacks: &SymbolizedStacks,
+    format: ReportFormat,
+    minidump: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+
+    if matches!(format, ReportFormat::Text | ReportFormat::Both) {
+        let filepath = output_dir.join(format!("{stem}.txt"));
+        let mut file = std::fs::File::create(&filepath)?;
+        write_report_from_db(&mut file, data, stacks)?;
+        paths.push(filepath);
+    }
+
+    if matches!(format, ReportFormat::Json | ReportFormat::Both) {
+        let filepath = output_dir.join(format!("{stem}.json"));
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&filepath)?);
+        json::write_report(&mut file, data, stacks)?;
+        file.flush()?;
+        paths.push(filepath);
+    }
+
+    if minidump && data.kind == db::CrashKind::Signal {
+        let filepath = output_dir.join(format!("{stem}.dmp"));
+        let mut file = std::fs::File::create(&filepath)?;
+        minidump::write_minidump(&mut file, data)?;
+        paths.push(filepath);
+    }
+
+    Ok(paths)
+}
+
+pub fn write_report_from_db(
+    w: &mut impl Write,
+    data: &db::CrashReportData,
+    stacks: &SymbolizedStacks,
+) -> anyhow::Result<()> {
+    writeln!(w, "Crash Report")?;
+    writeln!(w, "============")?;
+    writeln!(w, "Generated: {}", chrono::Utc::now().to_rfc3339())?;
+    writeln!(w)?;
+    writeln!(
+        w,
+        "Process: {} (PID: {}, TID: {}{})",
+        data.cmd,
+        data.pid,
+        data.tid,
+        namespace_ids(data.pid, data.tid, data.ns_pid, data.ns_tid)
+    )?;
+    write_container(w, &data.container)?;
+    if data.kind == db::CrashKind::Exit {
+        let status = data.exit_code.and_then(exit_status).unwrap_or_default();
+        writeln!(w, "Exit:    status {status} (no signal)")?;
+    } else {
+        writeln!(w, "Signal:  {} ({})", signal_name(data.signal), data.signal)?;
+        writeln!(w, "Code:    {} ({})", si_code_name(data.signal, data.si_code), data.si_code)?;
+
+        if data.fault_addr != 0 {
+            writeln!(w, "Fault:   0x{:016x}", data.fault_addr)?;
+        }
+
+        if let Some(note) = signal_note(data.signal, data.syscall_nr) {
+            writeln!(w, "Note:    {note}")?;
+        }
+
+        if let Some(exit_code) = data.exit_code {
+            writeln!(w, "Exit:    {}", exit_code)?;
+        }
+    }
+
+    if let Some(group) = &data.group {
+        write!(
+            w,
+            "Group:   {} (seen {} times, first {}, last {}",
+            group.signature, group.count, group.first_seen, group.last_seen
+        )?;
+        match &group.source {
+            Some(source) => writeln!(w, "; {source} frames)")?,
+            None => writeln!(w, ")")?,
+        }
+    }
+
+    writeln!(w)?;
+    // Older databases only have the names.
+    let runtime = if data.runtimes.is_empty() {
+        data.runtime.clone()
+    } else {
+        describe_runtimes(&data.runtimes)
+    };
+    writeln!(w, "Detected Runtime: {runtime}")?;
+
+    if let Some(jvm) = JvmCrashInfo::from_artifacts(&data.artifacts) {
+        write_jvm_section(w, &jvm)?;
+    }
+    if let Some(python) = PythonStack::from_artifacts(&data.artifacts) {
+        write_python_section(w, &python)?;
+    }
+    if let Some(go) = GoPanic::from_artifacts(&data.artifacts) {
+        write_go_section(w, &go)?;
+    }
+    if let Some(panic) = RustPanic::recover(data, stacks) {
+        write_rust_section(w, &panic)?;
+    }
+
+    // Exits leave no registers (or stacks) behind.
+    if data.kind == db::CrashKind::Signal {
+        let r = &data.registers;
+        writeln!(w)?;
+        writeln!(w, "Registers")?;
+        writeln!(w, "---------")?;
+        writeln!(w, "  RIP: 0x{:016x}  RFLAGS: 0x{:016x}", r.rip, r.rflags)?;
+        writeln!(w, "  RSP: 0x{:016x}  RBP:    0x{:016x}", r.rsp, r.rbp)?;
+        writeln!(w, "  RAX: 0x{:016x}  RBX:    0x{:016x}", r.rax, r.rbx)?;
+        writeln!(w, "  RCX: 0x{:016x}  RDX:    0x{:016x}", r.rcx, r.rdx)?;
+        writeln!(w, "  RSI: 0x{:016x}  RDI:    0x{:016x}", r.rsi, r.rdi)?;
+        writeln!(w, "  R8:  0x{:016x}  R9:     0x{:016x}", r.r8, r.r9)?;
+        writeln!(w, "  R10: 0x{:016x}  R11:    0x{:016x}", r.r10, r.r11)?;
+        writeln!(w, "  R12: 0x{:016x}  R13:    0x{:016x}", r.r12, r.r13)?;
+        writeln!(w, "  R14: 0x{:016x}  R15:    0x{:016x}", r.r14, r.r15)?;
+    }
+
+    if !data.kernel_stack.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "Kernel Stack:")?;
+        writeln!(w, "---------")?;
+        write_kernel_frames(w, &data.kernel_stack)?;
+    }
+
+    if !stacks.user.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "User Stack:")?;
+        writeln!(w, "---------")?;
+        write_symbolized_frames(w, &stacks.user)?;
+    }
+
+    if !stacks.unwound.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "User Stack (CFI unwound from raw stack):")?;
+        writeln!(w, "---------")?;
+        write_symbolized_frames(w, &stacks.unwound)?;
+    }
+
+    for (thread, thread_stacks) in data.threads.iter().zip(&stacks.threads) {
+        let r = &thread.registers;
+        writeln!(w)?;
+        writeln!(w, "Thread {} ({}):", thread.tid, thread.cmd)?;
+        writeln!(w, "---------")?;
+        writeln!(
+            w,
+            "  RIP: 0x{:016x}  RSP: 0x{:016x}  RBP: 0x{:016x}",
+            r.rip, r.rsp, r.rbp
+        )?;
+        // Same choice as for crash signatures: CFI when it recovered anything.
+        if thread_stacks.unwound.is_empty() {
+            write_symbolized_frames(w, &thread_stacks.user)?;
+        } else {
+            write_symbolized_frames(w, &thread_stacks.unwound)?;
+        }
+    }
+
+    if let Some((rsp, ref dump)) = data.stack_dump {
+        let len = dump.len();
+        writeln!(w)?;
+        writeln!(w, "Raw Stack ({} bytes from 0x{:016x})", len, rsp)?;
+        writeln!(w, "---------")?;
+        for offset in (0..len).step_by(16) {
+            let addr = rsp + offset as u64;
+            let end = (offset + 16).min(len);
+            let chunk = &dump[offset..end];
+
+            write!(w, "  0x{:016x}:", addr)?;
+            for (i, byte) in chunk.iter().enumerate() {
+                if i % 2 == 0 {
+                    write!(w, " ")?;
+                }
+                write!(w, "{:02x}", byte)?;
+            }
+            let missing = 16 - chunk.len();
+            for i in 0..missing {
+                if (chunk.len() + i) % 2 == 0 {
+                    write!(w, " ")?;
+                }
+                write!(w, "  ")?;
+            }
+
+            write!(w, "  |")?;
+            for byte in chunk {
+                let ch = if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                };
+                write!(w, "{}", ch)?;
+            }
+            writeln!(w, "|")?;
+        }
+    }
+
+    if !data.memory_maps.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "Memory Maps")?;
+        writeln!(w, "-----------")?;
+        for line in &data.memory_maps {
+            writeln!(w, "{}", line)?;
+        }
+    }
+
+    if !data.artifacts.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "Runtime Artifacts")?;
+        writeln!(w, "-----------------")?;
+        for artifact in &data.artifacts {
+            writeln!(w, "  File: {} ({})", artifact.filename, artifact.full_path)?;
+            match &artifact.content {
+                Some(content) => {
+                    if let Ok(text) = std::str::from_utf8(content) {
+                        writeln!(w)?;
+                        let truncated = text.len() > 4096;
+                        let preview = &text[..text.floor_char_boundary(4096)];
+                        for line in preview.lines() {
+                            writeln!(w, "    {}", line)?;
+                        }
+                        if truncated {
+                            writeln!(w, "    ... ({} bytes total, truncated)", text.len())?;
+                        }
+                    } else {
+                        writeln!(w, "  (binary content, {} bytes)", content.len())?;
+                    }
+                }
+                None => {
+                    writeln!(w, "  (content not available)")?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// `; in namespace PID: 1, TID: 1` for processes that see other ids than
+/// the host, nothing otherwise. Ids are 0 or `None` when unknown.
+fn namespace_ids(pid: u32, tid: u32, ns_pid: Option<u32>, ns_tid: Option<u32>) -> String {
+    let ns_pid = ns_pid.filter(|id| *id != 0 && *id != pid);
+    let ns_tid = ns_tid.filter(|id| *id != 0 && *id != tid);
+    match (ns_pid, ns_tid) {
+        (Some(pid), Some(tid)) => format!("; in namespace PID: {pid}, TID: {tid}"),
+        (Some(pid), None) => format!("; in namespace PID: {pid}"),
+        _ => String::new(),
+    }
+}
+
+/// Where the process ran, below the `Process:` line. Nothing for processes
+/// outside containers beyond their cgroup.
+fn write_container(w: &mut impl Write, container: &ContainerInfo) -> anyhow::Result<()> {
+    if let Some(path) = &container.cgroup_path {
+        writeln!(w, "Cgroup:  {path}")?;
+    }
+    if let Some(id) = container.short_id() {
+        match &container.container_runtime {
+            Some(runtime) => writeln!(w, "Container: {id} ({runtime})")?,
+            None => writeln!(w, "Container: {id}")?,
+        }
+    }
+    if let Some(name) = &container.pod_name {
+        let namespace = container.pod_namespace.as_deref().unwrap_or_default();
+        let uid = container.pod_uid.as_deref().unwrap_or_default();
+        writeln!(w, "Pod:     {namespace}/{name} ({uid})")?;
+    } else if let Some(uid) = &container.pod_uid {
+        writeln!(w, "Pod:     {uid}")?;
+    }
+    if container.pid_ns != 0 || container.mnt_ns != 0 {
+        writeln!(w, "Namespaces: pid:[{}] mnt:[{}]", container.pid_ns, container.mnt_ns)?;
+    }
+    Ok(())
+}
+
+/// Java-level context parsed from the hs_err log.
+fn write_jvm_section(w: &mut impl Write, jvm: &JvmCrashInfo) -> anyhow::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "JVM")?;
+    writeln!(w, "---")?;
+    let fields = [
+        ("Error", &jvm.error),
+        ("JRE", &jvm.jre_version),
+        ("VM", &jvm.vm),
+        ("Frame", &jvm.problematic_frame),
+        ("Thread", &jvm.current_thread),
+        ("Command", &jvm.java_command),
+        ("Args", &jvm.jvm_args),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            writeln!(w, "  {:<8} {}", format!("{name}:"), value)?;
+        }
+    }
+
+    let blocks = [
+        ("Java frames", &jvm.java_frames),
+        ("Native frames", &jvm.native_frames),
+        ("Java threads", &jvm.threads),
+        ("Heap", &jvm.heap),
+    ];
+    for (name, lines) in blocks {
+        if lines.is_empty() {
+            continue;
+        }
+        writeln!(w)?;
+        writeln!(w, "  {name}:")?;
+        for line in lines {
+            writeln!(w, "    {line}")?;
+        }
+    }
+
+    if !jvm.flags.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "  Flags:")?;
+        for flag in &jvm.flags {
+            writeln!(w, "    {} = {} {}", flag.name, flag.value, flag.origin)?;
+        }
+    }
+    Ok(())
+}
+
+/// Python-level traceback printed by faulthandler, e.g.
+///
+/// ```text
+///   Current thread 0x00007f1c2a5e7740:
+///     # 0: inner_transform at /app/worker.py:27
+/// ```
+fn write_python_section(w: &mut impl Write, python: &PythonStack) -> anyhow::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "Python Stack")?;
+    writeln!(w, "------------")?;
+    if let Some(error) = &python.error {
+        writeln!(w, "  Error: {error}")?;
+    }
+    for thread in &python.threads {
+        writeln!(w)?;
+        let label = if thread.current {
+            "Current thread"
+        } else {
+            "Thread"
+        };
+        writeln!(w, "  {label} {}:", thread.id)?;
+        if thread.frames.is_empty() {
+            writeln!(w, "    <no Python frame>")?;
+        }
+        for (i, frame) in thread.frames.iter().enumerate() {
+            match frame.line {
+                Some(line) => writeln!(
+                    w,
+                    "    #{i:>2}: {} at {}:{line}",
+                    frame.function, frame.file
+                )?,
+                None => writeln!(w, "    #{i:>2}: {} at {}", frame.function, frame.file)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_go_section(w: &mut impl Write, panic: &GoPanic) -> anyhow::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "Go Panic")?;
+    writeln!(w, "--------")?;
+    if let Some(message) = &panic.message {
+        writeln!(w, "  {message}")?;
+    }
+    if let Some(signal) = &panic.signal {
+        writeln!(w, "  {signal}")?;
+    }
+    for goroutine in &panic.goroutines {
+        writeln!(w)?;
+        writeln!(w, "  Goroutine {} [{}]:", goroutine.id, goroutine.state)?;
+        for (i, frame) in goroutine.frames.iter().enumerate() {
+            match (&frame.file, frame.line) {
+                (Some(file), Some(line)) => {
+                    writeln!(w, "    #{i:>2}: {} at {file}:{line}", frame.function)?
+                }
+                (Some(file), None) => writeln!(w, "    #{i:>2}: {} at {file}", frame.function)?,
+                _ => writeln!(w, "    #{i:>2}: {}", frame.function)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_rust_section(w: &mut impl Write, panic: &RustPanic) -> anyhow::Result<()> {
+    writeln!(w)?;
+    writeln!(w, "Rust Panic")?;
+    writeln!(w, "----------")?;
+    if let Some(thread) = &panic.thread {
+        writeln!(w, "  Thread:   {thread}")?;
+    }
+    if let Some(location) = &panic.location {
+        writeln!(w, "  Location: {location}")?;
+    }
+    match &panic.message {
+        Some(message) => {
+            for line in message.lines() {
+                writeln!(w, "  {line}")?;
+            }
+        }
+        // The hook prints before aborting, ahead of the signal.
+        None => writeln!(
+            w,
+            "  (message not captured; recovered from the {}, stderr is kept for --exit-commands)",
+            panic.source
+        )?,
+    }
+    Ok(())
+}
+
+/// Writes one line per frame followed by its source locations, e.g.
+///
+/// ```text
+///   # 1: 0x00007f3a1c02a1ca libc.so.6!__libc_start_call_main+0x7a
+///          [inlined] parse_header at src/parse.c:41
+///          main at src/main.c:12
+/// ```
+fn write_symbolized_frames(w: &mut impl Write, frames: &[SymbolizedFrame]) -> anyhow::Result<()> {
+    for (i, frame) in frames.iter().enumerate() {
+        writeln!(w, "  #{:2}: 0x{:016x} {}", i, frame.ip, frame)?;
+        for loc in &frame.locations {
+            let Some(file) = &loc.file else {
+                continue;
+            };
+            let function = loc.function.as_deref().unwrap_or("??");
+            let inlined = if loc.inlined { "[inlined] " } else { "" };
+            match loc.line {
+                Some(line) => writeln!(w, "         {inlined}{function} at {file}:{line}")?,
+                None => writeln!(w, "         {inlined}{function} at {file}")?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_kernel_frames(w: &mut impl Write, frames: &[KernelFrame]) -> anyhow::Result<()> {
+    for (i, frame) in frames.iter().enumerate() {
+        writeln!(w, "  #{:2}: 0x{:016x} {}", i, frame.ip, frame)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,169 @@
+//! .NET (CoreCLR) diagnostics, configured through `DOTNET_*` (or legacy
+//! `COMPlus_*`) environment variables:
+//!
+//! - `PerfMapEnabled=1|3` writes `perf-<pid>.map`, naming JIT-compiled
+//!   methods, and `perfinfo-<pid>.map`, listing loaded images, to
+//!   `PerfMapJitDumpPath` (default `/tmp`).
+//! - `DbgEnableMiniDump=1` runs `createdump` on a crash, writing
+//!   `DbgMiniDumpName` (default `/tmp/coredump.<pid>`). With
+//!   `CrashReportEnabled=1` it also writes `<dump>.crashreport.json`, which
+//!   names the managed frames of every thread.
+//!
+//! Mono has neither; its `--jitmap` perf map is picked up like any other.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::db::ArtifactData;
+use crate::state::map::ProcessInfo;
+use crate::symbolize::{SourceLocation, SymbolizedFrame};
+
+const DEFAULT_PERF_MAP_DIR: &str = "/tmp";
+const DEFAULT_DUMP_NAME: &str = "/tmp/coredump.%p";
+const CRASH_REPORT_SUFFIX: &str = ".crashreport.json";
+
+/// Files the runtime of `process` wrote as configured by its environment.
+pub fn locate_artifacts(process: &ProcessInfo) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    if matches!(config_flag(process, "PerfMapEnabled"), Some(1 | 3)) {
+        let dir = config(process, "PerfMapJitDumpPath").unwrap_or(DEFAULT_PERF_MAP_DIR);
+        for name in ["perf", "perfinfo"] {
+            paths.push(Path::new(dir).join(format!("{name}-{}.map", process.ns_pid)));
+        }
+    }
+
+    if config_flag(process, "DbgEnableMiniDump") == Some(1) {
+        let template = config(process, "DbgMiniDumpName").unwrap_or(DEFAULT_DUMP_NAME);
+        if let Some(dump) = expand_dump_name(template, process) {
+            if config_flag(process, "CrashReportEnabled") == Some(1) {
+                paths.push(PathBuf::from(format!("{dump}{CRASH_REPORT_SUFFIX}")));
+            }
+            paths.push(PathBuf::from(dump));
+        }
+    }
+
+    let cwd = process.cwd.as_deref().map(Path::new);
+    paths
+        .into_iter()
+        .map(|path| match cwd {
+            Some(cwd) if path.is_relative() => cwd.join(path),
+            _ => path,
+        })
+        .filter(|path| process.open_file(path).is_ok())
+        .collect()
+}
+
+/// `DOTNET_<name>`, falling back to `COMPlus_<name>` as the runtime does.
+fn config<'a>(process: &'a ProcessInfo, name: &str) -> Option<&'a str> {
+    process
+        .env(&format!("DOTNET_{name}"))
+        .or_else(|| process.env(&format!("COMPlus_{name}")))
+}
+
+/// Numeric settings are parsed as hex by the runtime.
+fn config_flag(process: &ProcessInfo, name: &str) -> Option<u32> {
+    config(process, name).and_then(|v| u32::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+}
+
+/// Expand `%p`/`%d` (pid), `%e` (executable name), `%h` (hostname) and `%%`
+/// as createdump does. `%t` is the time of the dump, which cannot be
+/// predicted, so such names are not located.
+fn expand_dump_name(template: &str, process: &ProcessInfo) -> Option<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('p' | 'd') => out.push_str(&process.ns_pid.to_string()),
+            Some('e') => {
+                let exe = process.cmdline.as_deref()?.split_whitespace().next()?;
+                out.push_str(exe.rsplit('/').next().unwrap_or(exe));
+            }
+            Some('h') => {
+                let host = std::fs::read_to_string("/proc/sys/kernel/hostname").ok()?;
+                out.push_str(host.trim());
+            }
+            Some('%') => out.push('%'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Managed frames listed in a createdump crash report, by instruction pointer.
+pub struct CrashReport {
+    frames: Vec<ManagedFrame>,
+}
+
+struct ManagedFrame {
+    ip: u64,
+    offset: Option<u64>,
+    method: String,
+    /// Assembly file, e.g. `System.Private.CoreLib.dll`.
+    assembly: Option<String>,
+}
+
+impl CrashReport {
+    /// The crash report among the crash's artifacts, if any.
+    pub fn from_artifacts(artifacts: &[ArtifactData]) -> Option<Self> {
+        artifacts
+            .iter()
+            .filter(|a| a.filename.ends_with(CRASH_REPORT_SUFFIX))
+            .find_map(|a| a.content.as_deref())
+            .and_then(|content| serde_json::from_slice::<Value>(content).ok())
+            .map(|json| Self::parse(&json))
+    }
+
+    /// Every field of the report is a string, numbers in `0x` hex.
+    pub fn parse(json: &Value) -> Self {
+        let threads = json["payload"]["threads"].as_array().map(Vec::as_slice);
+        let frames = threads
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|thread| thread["stack_frames"].as_array())
+            .flatten()
+            .filter(|frame| frame["is_managed"].as_str() == Some("true"))
+            .filter_map(|frame| {
+                Some(ManagedFrame {
+                    ip: hex_field(frame, "native_address")?,
+                    offset: hex_field(frame, "native_offset"),
+                    method: frame["method_name"].as_str()?.to_string(),
+                    assembly: frame["filename"].as_str().map(str::to_string),
+                })
+            })
+            .collect();
+        Self { frames }
+    }
+
+    /// Name managed frames no ELF module or perf map could account for.
+    pub fn resolve(&self, frames: &mut [SymbolizedFrame]) {
+        for frame in frames {
+            if frame.locations.iter().any(|loc| loc.function.is_some()) {
+                continue;
+            }
+            let Some(managed) = self.frames.iter().find(|m| m.ip == frame.ip) else {
+                continue;
+            };
+            if frame.module.is_none() {
+                frame.module = managed.assembly.clone();
+            }
+            frame.locations = vec![SourceLocation {
+                function: Some(managed.method.clone()),
+                function_offset: managed.offset,
+                file: None,
+                line: None,
+                inlined: false,
+            }];
+        }
+    }
+}
+
+fn hex_field(frame: &Value, name: &str) -> Option<u64> {
+    let value = frame[name].as_str()?;
+    u64::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
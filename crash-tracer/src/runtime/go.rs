@@ -0,0 +1,233 @@
+//! Go. The runtime is linked into every binary, which records the toolchain
+//! and module it was built from in `.go.buildinfo`. Fatal panics, including
+//! those the runtime raises for SIGSEGV and friends, print the crashing
+//! goroutine (every one with `GOTRACEBACK=all`) to stderr and exit with
+//! status 2.
+
+use std::path::Path;
+
+use object::{Object, ObjectSection};
+
+use crate::db::ArtifactData;
+use crate::report::signal_name;
+use crate::symbolize::gopclntab::read_uint;
+
+/// Exit status of a Go program that died of a panic or fatal error.
+pub const PANIC_EXIT_STATUS: u8 = 2;
+
+const BUILD_INFO_MAGIC: &[u8] = b"\xff Go buildinf:";
+const BUILD_INFO_HEADER_LEN: usize = 32;
+/// Strings are stored inline rather than behind pointers (Go 1.18+).
+const BUILD_INFO_FLAG_INLINE: u8 = 0x2;
+/// Length of the markers `runtime/debug` wraps the module info in.
+const MOD_INFO_SENTINEL_LEN: usize = 16;
+
+/// What `go version -m` prints for a binary.
+pub struct BuildInfo {
+    /// `go1.22.1`
+    pub go_version: String,
+    /// Main package, e.g. `example.com/app/cmd/server`.
+    pub path: Option<String>,
+    /// Main module as `path@version`; the version is `(devel)` for builds
+    /// from a checkout.
+    pub module: Option<String>,
+}
+
+impl BuildInfo {
+    /// `1.22.1` from `go1.22.1`.
+    pub fn version(&self) -> Option<String> {
+        let version = self.go_version.strip_prefix("go")?;
+        let end = version
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(version.len());
+        Some(version[..end].to_string()).filter(|v| !v.is_empty())
+    }
+}
+
+/// Build info of the Go binary at `path`; `None` for anything else. Only the
+/// headers and the section are read.
+pub fn read_build_info(path: &Path) -> Option<BuildInfo> {
+    let file = std::fs::File::open(path).ok()?;
+    let cache = object::ReadCache::new(file);
+    let elf = object::File::parse(&cache).ok()?;
+    let section = elf.section_by_name(".go.buildinfo")?;
+    let data = section.data().ok()?;
+    if !data.starts_with(BUILD_INFO_MAGIC) || data.len() < BUILD_INFO_HEADER_LEN {
+        return None;
+    }
+
+    let ptr_size = data[14] as usize;
+    let flags = data[15];
+    let (version, mod_info) = if flags & BUILD_INFO_FLAG_INLINE != 0 {
+        let (version, rest) = read_varint_string(&data[BUILD_INFO_HEADER_LEN..])?;
+        let (mod_info, _) = read_varint_string(rest)?;
+        (version.to_vec(), mod_info.to_vec())
+    } else {
+        // Before Go 1.18: pointers to Go string headers (data, len).
+        let read_string = |at: usize| {
+            let header = read_uint(data, at, ptr_size)?;
+            let header = read_at(&elf, header, 2 * ptr_size as u64)?;
+            let ptr = read_uint(header, 0, ptr_size)?;
+            let len = read_uint(header, ptr_size, ptr_size)?;
+            read_at(&elf, ptr, len).map(<[u8]>::to_vec)
+        };
+        (
+            read_string(16)?,
+            read_string(16 + ptr_size).unwrap_or_default(),
+        )
+    };
+
+    let mut info = BuildInfo {
+        go_version: String::from_utf8_lossy(&version).into_owned(),
+        path: None,
+        module: None,
+    };
+    let mut mod_info = mod_info.as_slice();
+    if mod_info.len() > 2 * MOD_INFO_SENTINEL_LEN
+        && mod_info[mod_info.len() - MOD_INFO_SENTINEL_LEN - 1] == b'\n'
+    {
+        mod_info = &mod_info[MOD_INFO_SENTINEL_LEN..mod_info.len() - MOD_INFO_SENTINEL_LEN];
+    }
+    for line in String::from_utf8_lossy(mod_info).lines() {
+        let mut fields = line.split('\t');
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some("path"), Some(path), _) => info.path = Some(path.to_string()),
+            (Some("mod"), Some(module), Some(version)) => {
+                info.module = Some(format!("{module}@{version}"));
+            }
+            _ => {}
+        }
+    }
+    Some(info)
+}
+
+fn read_varint_string(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut len = 0usize;
+    for (i, byte) in data.iter().enumerate().take(10) {
+        len |= ((byte & 0x7f) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            let rest = &data[i + 1..];
+            return (len <= rest.len()).then(|| rest.split_at(len));
+        }
+    }
+    None
+}
+
+/// `len` bytes at virtual address `address` of `elf`.
+fn read_at<'a, R: object::ReadRef<'a>>(
+    elf: &object::File<'a, R>,
+    address: u64,
+    len: u64,
+) -> Option<&'a [u8]> {
+    elf.sections()
+        .find_map(|section| section.data_range(address, len).ok().flatten())
+}
+
+/// A fatal Go panic or runtime error as printed to stderr.
+pub struct GoPanic {
+    /// `panic: runtime error: index out of range [5] with length 3`, or
+    /// `fatal error: ...`.
+    pub message: Option<String>,
+    /// `[signal SIGSEGV: segmentation violation code=0x1 addr=0x0 pc=0x48f0a5]`
+    /// when a signal caused it.
+    pub signal: Option<String>,
+    /// The panicking goroutine first, as the runtime prints them.
+    pub goroutines: Vec<Goroutine>,
+}
+
+pub struct Goroutine {
+    pub id: u64,
+    /// `running`, `chan receive`, `sleep, 2 minutes`, ...
+    pub state: String,
+    pub frames: Vec<GoFrame>,
+}
+
+pub struct GoFrame {
+    /// `main.(*Server).handle(0xc000010000, {0x4b6a20, 0x5})`, arguments as
+    /// printed; `created by main.main in goroutine 1` for the spawn site.
+    pub function: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+impl GoPanic {
+    /// The panic among the crash's artifacts, normally its stderr.
+    pub fn from_artifacts(artifacts: &[ArtifactData]) -> Option<Self> {
+        artifacts
+            .iter()
+            .filter_map(|a| a.content.as_deref())
+            .map(String::from_utf8_lossy)
+            .find_map(|text| Self::parse(&text))
+    }
+
+    /// Whether the panic was raised for `signal`, rather than for an earlier
+    /// one the program recovered from.
+    pub fn names_signal(&self, signal: i32) -> bool {
+        self.signal
+            .as_deref()
+            .and_then(|line| line.strip_prefix("[signal "))
+            .and_then(|rest| rest.split([':', ' ']).next())
+            .is_some_and(|name| name == signal_name(signal))
+    }
+
+    /// Parse the output of the Go runtime's crash traceback; `None` unless it
+    /// has at least one goroutine.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut panic = GoPanic {
+            message: None,
+            signal: None,
+            goroutines: Vec::new(),
+        };
+
+        let mut lines = text.lines().peekable();
+        while let Some(line) = lines.next() {
+            if panic.message.is_none()
+                && (line.starts_with("panic: ") || line.starts_with("fatal error: "))
+            {
+                panic.message = Some(line.trim_end().to_string());
+            } else if line.starts_with("[signal ") {
+                panic.signal = Some(line.trim_end().to_string());
+            } else if let Some((id, state)) = parse_goroutine_header(line) {
+                let mut frames = Vec::new();
+                while let Some(function) = lines.next_if(|l| !l.trim().is_empty()) {
+                    let location = lines
+                        .next_if(|l| l.starts_with('\t'))
+                        .and_then(parse_location);
+                    // Every frame has a location but the elision marker;
+                    // anything else was written after the traceback.
+                    if location.is_none() && !function.starts_with("...") {
+                        break;
+                    }
+                    let (file, line) = location.unzip();
+                    frames.push(GoFrame {
+                        function: function.to_string(),
+                        file,
+                        line: line.flatten(),
+                    });
+                }
+                panic.goroutines.push(Goroutine { id, state, frames });
+            }
+        }
+
+        (!panic.goroutines.is_empty()).then_some(panic)
+    }
+}
+
+/// `goroutine 1 [running]:`, also with the `gp=0x... m=0 mp=0x...` that
+/// `GOTRACEBACK=system` adds.
+fn parse_goroutine_header(line: &str) -> Option<(u64, String)> {
+    let rest = line.strip_prefix("goroutine ")?.strip_suffix("]:")?;
+    let (id, rest) = rest.split_once(' ')?;
+    let (_, state) = rest.split_once('[')?;
+    Some((id.parse().ok()?, state.to_string()))
+}
+
+/// `\t/src/app/main.go:12 +0x1d` into file and line.
+fn parse_location(line: &str) -> Option<(String, Option<u32>)> {
+    let location = line.trim();
+    let location = location.split_once(" +0x").map_or(location, |(l, _)| l);
+    Some(match location.rsplit_once(':') {
+        Some((file, line)) => (file.to_string(), line.parse().ok()),
+        None => (location.to_string(), None),
+    })
+}
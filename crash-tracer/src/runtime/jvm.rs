@@ -0,0 +1,194 @@
+//! HotSpot fatal error logs (`hs_err_pid<pid>.log`).
+//!
+//! The log is free-form text meant for humans, but its layout has been stable
+//! across JDK 8-21: `#`-prefixed header, then `T H R E A D`, `P R O C E S S`
+//! and `S Y S T E M` sections. Anything not found is left empty rather than
+//! failing the report.
+
+use std::path::{Path, PathBuf};
+
+use crate::db::ArtifactData;
+use crate::state::map::ProcessInfo;
+
+/// Header line of every hs_err log, used to recognize one whatever its name.
+const HEADER: &str = "# A fatal error has been detected by the Java Runtime Environment";
+
+/// Where the JVM writes its error log when none is configured and the
+/// working directory is not writable.
+const FALLBACK_DIR: &str = "/tmp";
+
+/// Structured view of an hs_err log.
+#[derive(Default)]
+pub struct JvmCrashInfo {
+    /// `SIGSEGV (0xb) at pc=0x..., pid=..., tid=...` or `Internal Error (...)`.
+    pub error: Option<String>,
+    pub jre_version: Option<String>,
+    pub vm: Option<String>,
+    /// `C  [libfoo.so+0x1234]  foo+0x10`.
+    pub problematic_frame: Option<String>,
+    /// `JavaThread "main" [_thread_in_native, id=..., stack(...)]`.
+    pub current_thread: Option<String>,
+    /// Mixed-mode stack of the crashing thread, innermost first.
+    pub native_frames: Vec<String>,
+    /// Java-only stack of the crashing thread, innermost first.
+    pub java_frames: Vec<String>,
+    /// The `Java Threads` list; the crashing one is marked with `=>`.
+    pub threads: Vec<String>,
+    pub jvm_args: Option<String>,
+    pub java_command: Option<String>,
+    /// `[Global flags]` that were not left at their default.
+    pub flags: Vec<JvmFlag>,
+    /// The `Heap:` block, e.g. `garbage-first heap total 258048K, used 2048K`.
+    pub heap: Vec<String>,
+}
+
+pub struct JvmFlag {
+    pub name: String,
+    pub value: String,
+    /// `{product} {ergonomic}`, `{product} {command line}`, ...
+    pub origin: String,
+}
+
+impl JvmCrashInfo {
+    /// Parse the first hs_err log among `artifacts`.
+    pub fn from_artifacts(artifacts: &[ArtifactData]) -> Option<Self> {
+        artifacts
+            .iter()
+            .filter_map(|a| a.content.as_deref())
+            .filter_map(|content| std::str::from_utf8(content).ok())
+            .find(|text| text.contains(HEADER))
+            .map(Self::parse)
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut info = Self::default();
+        let mut lines = text.lines().peekable();
+
+        while let Some(line) = lines.next() {
+            if let Some(header) = line.strip_prefix('#') {
+                let header = header.trim();
+                if let Some(version) = header.strip_prefix("JRE version:") {
+                    info.jre_version = Some(version.trim().to_string());
+                } else if let Some(vm) = header.strip_prefix("Java VM:") {
+                    info.vm = Some(vm.trim().to_string());
+                } else if header == "Problematic frame:" {
+                    info.problematic_frame = lines
+                        .next_if(|next| next.starts_with('#'))
+                        .map(|next| next.trim_start_matches('#').trim().to_string());
+                } else if info.error.is_none()
+                    && !header.is_empty()
+                    && !header.starts_with("A fatal error")
+                {
+                    info.error = Some(header.to_string());
+                }
+                continue;
+            }
+
+            if let Some(thread) = line.strip_prefix("Current thread") {
+                // `Current thread (0x00007f...):  JavaThread "main" [...]`
+                info.current_thread = thread
+                    .split_once("):")
+                    .map(|(_, thread)| thread.trim().to_string());
+            } else if line.starts_with("Native frames:") {
+                info.native_frames = take_block(&mut lines);
+            } else if line.starts_with("Java frames:") {
+                info.java_frames = take_block(&mut lines);
+            } else if line.starts_with("Java Threads:") {
+                info.threads = take_block(&mut lines)
+                    .into_iter()
+                    .filter(|t| t.starts_with("=>") || t.starts_with("0x"))
+                    .collect();
+            } else if let Some(args) = line.strip_prefix("jvm_args:") {
+                info.jvm_args = Some(args.trim().to_string());
+            } else if let Some(command) = line.strip_prefix("java_command:") {
+                info.java_command = Some(command.trim().to_string());
+            } else if line == "[Global flags]" {
+                info.flags = take_block(&mut lines)
+                    .iter()
+                    .filter_map(|l| parse_flag(l))
+                    .collect();
+            } else if line == "Heap:" {
+                info.heap = take_block(&mut lines);
+            }
+        }
+        info
+    }
+}
+
+/// Following lines up to the next blank one, trimmed.
+fn take_block<'a>(lines: &mut impl Iterator<Item = &'a str>) -> Vec<String> {
+    lines
+        .take_while(|l| !l.trim().is_empty())
+        .map(|l| l.trim().to_string())
+        .collect()
+}
+
+/// `intx CICompilerCount = 4 {product} {ergonomic}`
+fn parse_flag(line: &str) -> Option<JvmFlag> {
+    let (decl, rest) = line.split_once('=')?;
+    let name = decl.split_whitespace().last()?;
+    let (value, origin) = match rest.find('{') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, ""),
+    };
+    Some(JvmFlag {
+        name: name.to_string(),
+        value: value.trim().to_string(),
+        origin: origin.trim().to_string(),
+    })
+}
+
+/// Where the JVM of `process` would have written its error log, if it exists.
+///
+/// Honors `-XX:ErrorFile=` (with `%p` for the pid), otherwise the default
+/// `hs_err_pid<pid>.log` in the working directory or `/tmp`.
+pub fn locate_error_file(process: &ProcessInfo) -> Option<PathBuf> {
+    let cwd = process.cwd.as_deref().map(Path::new);
+    let configured = process
+        .cmdline
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        // The last occurrence wins, as for any -XX option.
+        .rev()
+        .find_map(|arg| arg.strip_prefix("-XX:ErrorFile="))
+        .map(|pattern| expand_pid(pattern, process.ns_pid));
+
+    let default = format!("hs_err_pid{}.log", process.ns_pid);
+    let mut candidates = Vec::new();
+    if let Some(path) = configured {
+        candidates.push(PathBuf::from(path));
+    }
+    candidates.push(PathBuf::from(&default));
+    candidates.push(Path::new(FALLBACK_DIR).join(&default));
+
+    candidates
+        .into_iter()
+        .map(|path| match cwd {
+            Some(cwd) if path.is_relative() => cwd.join(path),
+            _ => path,
+        })
+        .find(|path| process.host_path(path).is_file())
+}
+
+/// `%p` is the pid and `%%` a literal `%`, as in HotSpot's `Arguments::copy_expand_pid`.
+fn expand_pid(pattern: &str, pid: u32) -> String {
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('p') => out.push_str(&pid.to_string()),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
@@ -0,0 +1,69 @@
+//! Runtime-specific context for crashes of managed runtimes, recovered from
+//! the artifacts they leave behind.
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::state::map::{ProcessInfo, RuntimeKind};
+
+pub mod dotnet;
+pub mod go;
+pub mod jvm;
+pub mod node;
+pub mod python;
+pub mod rust;
+
+/// Files the runtimes of `process` left behind, found from its command line
+/// and working directory. Complements the eBPF artifact tracking, which only
+/// follows well-known names. Paths are as the process names them; see
+/// [`ProcessInfo::host_path`].
+pub fn locate_artifacts(process: &ProcessInfo) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for runtime in &process.runtimes {
+        match runtime.kind {
+            RuntimeKind::Jvm => paths.extend(jvm::locate_error_file(process)),
+            RuntimeKind::V8 => paths.extend(node::locate_report(process)),
+            RuntimeKind::CoreClr => paths.extend(dotnet::locate_artifacts(process)),
+            _ => {}
+        }
+    }
+
+    // Written by any JIT asked to (V8 `--perf-basic-prof`, Mono `--jitmap`,
+    // LuaJIT, ...).
+    let perf_map = PathBuf::from(format!("/tmp/perf-{}.map", process.ns_pid));
+    if process.open_file(&perf_map).is_ok() && !paths.contains(&perf_map) {
+        paths.push(perf_map);
+    }
+
+    // Files named after the pid may be left over from an earlier process.
+    paths.retain(|path| {
+        process
+            .open_file(path)
+            .is_ok_and(|file| written_since(&file, process.boottime))
+    });
+    paths
+}
+
+/// Whether `file` was modified after the process that started `boottime` ns
+/// after boot came up.
+fn written_since(file: &File, boottime: u64) -> bool {
+    let Some(uptime) = std::fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|s| s.split_whitespace().next()?.parse::<f64>().ok())
+    else {
+        return true;
+    };
+    let Some(started) = SystemTime::now()
+        .checked_sub(Duration::from_secs_f64(uptime))
+        .and_then(|boot| boot.checked_add(Duration::from_nanos(boottime)))
+    else {
+        return true;
+    };
+    // mtime has coarser resolution than the clocks above.
+    let started = started - Duration::from_secs(1);
+
+    file.metadata()
+        .and_then(|meta| meta.modified())
+        .is_ok_and(|modified| modified >= started)
+}
@@ -0,0 +1,70 @@
+//! Node.js diagnostic reports, written on `--report-on-fatalerror`,
+//! `--report-uncaught-exception` or `--report-on-signal`.
+
+use std::path::{Path, PathBuf};
+
+use crate::state::map::ProcessInfo;
+
+/// The report `process` wrote, if any.
+///
+/// Honors `--report-directory` and `--report-filename`; otherwise looks for
+/// the default `report.<date>.<time>.<pid>.<seq>.json` in the working
+/// directory, taking the newest.
+pub fn locate_report(process: &ProcessInfo) -> Option<PathBuf> {
+    let args: Vec<&str> = process
+        .cmdline
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect();
+    let cwd = process.cwd.as_deref().map(Path::new);
+
+    let dir = option(&args, &["--report-directory", "--report-dir"])
+        .map(PathBuf::from)
+        .or_else(|| cwd.map(Path::to_path_buf))?;
+    let dir = match cwd {
+        Some(cwd) if dir.is_relative() => cwd.join(dir),
+        _ => dir,
+    };
+
+    match option(&args, &["--report-filename"]) {
+        // Written to a stream rather than a file; nothing to pick up.
+        Some("stdout" | "stderr") => None,
+        Some(name) => Some(dir.join(name)).filter(|path| process.host_path(path).is_file()),
+        None => {
+            let marker = format!(".{}.", process.ns_pid);
+            std::fs::read_dir(process.host_path(&dir))
+                .ok()?
+                .filter_map(Result::ok)
+                .filter(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    name.starts_with("report.") && name.ends_with(".json") && name.contains(&marker)
+                })
+                .filter_map(|entry| {
+                    let modified = entry.metadata().ok()?.modified().ok()?;
+                    Some((modified, dir.join(entry.file_name())))
+                })
+                .max_by_key(|(modified, _)| *modified)
+                .map(|(_, path)| path)
+        }
+    }
+}
+
+/// Value of the first of `names` given as `--name=value` or `--name value`.
+fn option<'a>(args: &[&'a str], names: &[&str]) -> Option<&'a str> {
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        for name in names {
+            if let Some(value) = arg.strip_prefix(name) {
+                if let Some(value) = value.strip_prefix('=') {
+                    return Some(value);
+                }
+                if value.is_empty() {
+                    return iter.next().copied();
+                }
+            }
+        }
+    }
+    None
+}
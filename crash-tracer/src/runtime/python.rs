@@ -0,0 +1,108 @@
+//! CPython tracebacks as printed by `faulthandler` on a fatal signal.
+//!
+//! faulthandler must be enabled in the crashing process (`-X faulthandler`,
+//! `PYTHONFAULTHANDLER=1` or `faulthandler.enable()`). It writes to stderr,
+//! which we keep the tail of after the crash signal. CPython has no
+//! convention for a file passed to `faulthandler.enable(file)`; such a file
+//! is only picked up when its name matches `faulthandler*.log`, a naming
+//! requirement of this project.
+//!
+//! ```text
+//! Fatal Python error: Segmentation fault
+//!
+//! Current thread 0x00007f1c2a5e7740 (most recent call first):
+//!   File "/app/worker.py", line 27 in inner_transform
+//!   File "/app/worker.py", line 30 in <module>
+//! ```
+
+use crate::db::ArtifactData;
+
+const FATAL_ERROR: &str = "Fatal Python error: ";
+const STACK_HEADER_SUFFIX: &str = "(most recent call first):";
+
+pub struct PythonStack {
+    /// `Segmentation fault`, `Aborted`, ...
+    pub error: Option<String>,
+    /// The crashing thread first when faulthandler marked it.
+    pub threads: Vec<PythonThread>,
+}
+
+pub struct PythonThread {
+    /// `0x00007f1c2a5e7740`, as printed.
+    pub id: String,
+    pub current: bool,
+    /// Innermost first.
+    pub frames: Vec<PythonFrame>,
+}
+
+pub struct PythonFrame {
+    pub file: String,
+    pub line: Option<u32>,
+    pub function: String,
+}
+
+impl PythonStack {
+    /// Parse the first faulthandler traceback among `artifacts`.
+    pub fn from_artifacts(artifacts: &[ArtifactData]) -> Option<Self> {
+        artifacts
+            .iter()
+            .filter_map(|a| a.content.as_deref())
+            .map(String::from_utf8_lossy)
+            .find_map(|text| Self::parse(&text))
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut error = None;
+        let mut threads: Vec<PythonThread> = Vec::new();
+
+        for line in text.lines() {
+            if let Some(message) = line.strip_prefix(FATAL_ERROR) {
+                error.get_or_insert_with(|| message.trim().to_string());
+            } else if let Some(header) = line.strip_suffix(STACK_HEADER_SUFFIX) {
+                // `Current thread 0x... ` or `Thread 0x... `; older versions
+                // print `Stack` when threads are not dumped.
+                let mut words = header.split_whitespace();
+                let current = header.starts_with("Current thread");
+                let id = words.find(|w| w.starts_with("0x")).unwrap_or_default();
+                threads.push(PythonThread {
+                    id: id.to_string(),
+                    current,
+                    frames: Vec::new(),
+                });
+            } else if let Some(frame) = parse_frame(line)
+                && let Some(thread) = threads.last_mut()
+            {
+                thread.frames.push(frame);
+            }
+        }
+
+        if threads.is_empty() {
+            return None;
+        }
+        threads.sort_by_key(|t| !t.current);
+        Some(Self { error, threads })
+    }
+}
+
+/// `  File "/app/worker.py", line 27 in inner_transform`
+fn parse_frame(line: &str) -> Option<PythonFrame> {
+    let rest = line.trim_start().strip_prefix("File \"")?;
+    let (file, rest) = rest.split_once('"')?;
+    let rest = rest.trim_start_matches(',').trim_start();
+    let (line_no, function) = match rest.strip_prefix("line ") {
+        Some(rest) => {
+            let (number, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+            (number.parse().ok(), rest)
+        }
+        None => (None, rest),
+    };
+    Some(PythonFrame {
+        file: file.to_string(),
+        line: line_no,
+        function: function
+            .trim()
+            .strip_prefix("in ")
+            .unwrap_or("???")
+            .to_string(),
+    })
+}
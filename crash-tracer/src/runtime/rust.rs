@@ -0,0 +1,317 @@
+//! Rust. There is no runtime to find in the maps; rustc marks the binaries it
+//! links in `.comment`. Services built with `panic = "abort"` die of SIGABRT
+//! from `std::process::abort` after the panic hook printed
+//!
+//! ```text
+//! thread 'main' (4242) panicked at src/main.rs:21:5:
+//! index out of bounds: the len is 3 but the index is 7
+//! note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+//! ```
+//!
+//! to stderr, which is only captured for `--exit-commands`. Otherwise the
+//! location is recovered from the stack dump: the panic machinery passes a
+//! pointer to the `core::panic::Location` static of the panic site down to
+//! the abort.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crash_tracer_common::SIGABRT;
+use object::{
+    Object, ObjectSection, ObjectSegment, ReadRef, RelocationFlags, RelocationTarget, elf,
+};
+
+use crate::db::{ArtifactData, CrashReportData};
+use crate::state::{map::RuntimeKind, namespace::join_root};
+use crate::symbolize::{SymbolizedStacks, elf::read_build_id, maps::parse_maps};
+
+const COMMENT_PREFIX: &str = "rustc version ";
+/// Stack words scanned for a `Location`, from the stack pointer up; the
+/// panic site is a handful of frames above the abort.
+const MAX_SCANNED_WORDS: usize = 512;
+/// Bounds that keep arbitrary stack words from passing as a `Location`.
+const MAX_FILE_LEN: u64 = 512;
+const MAX_LINE: u32 = 10_000_000;
+const MAX_COLUMN: u32 = 10_000;
+
+/// A binary rustc linked.
+pub struct RustBinary {
+    /// `1.85.0`, from `.comment`.
+    pub version: Option<String>,
+}
+
+/// Whether the ELF file at `path` was built by rustc. Only the headers and
+/// the sections looked at are read.
+pub fn read_binary_info(path: &Path) -> Option<RustBinary> {
+    let file = std::fs::File::open(path).ok()?;
+    let cache = object::ReadCache::new(file);
+    let elf = object::File::parse(&cache).ok()?;
+
+    // `rustc version 1.85.0 (4d91de4e4 2025-02-17)`, among the linker's.
+    let version = elf
+        .section_by_name(".comment")
+        .and_then(|section| section.data().ok())
+        .and_then(|data| {
+            data.split(|b| *b == 0)
+                .filter_map(|entry| std::str::from_utf8(entry).ok())
+                .find_map(|entry| entry.strip_prefix(COMMENT_PREFIX))
+                .map(|rest| rest.split(' ').next().unwrap_or(rest).to_string())
+        });
+    // Rust dylibs carry their crate metadata in `.rustc`.
+    if version.is_none() && elf.section_by_name(".rustc").is_none() {
+        return None;
+    }
+    Some(RustBinary { version })
+}
+
+pub struct RustPanic {
+    /// `main`, `tokio-runtime-worker`, or `<unnamed>`.
+    pub thread: Option<String>,
+    /// Only known when stderr was captured.
+    pub message: Option<String>,
+    pub location: Option<PanicLocation>,
+    /// Where the details were recovered from: `stderr` or `stack`.
+    pub source: &'static str,
+}
+
+#[derive(Clone)]
+pub struct PanicLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl std::fmt::Display for PanicLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+impl RustPanic {
+    /// The panic behind the crash: from the panic hook's output when it was
+    /// captured, otherwise the location the symbolizer recovered from the
+    /// stack dump of a Rust binary that aborted.
+    pub fn recover(data: &CrashReportData, stacks: &SymbolizedStacks) -> Option<Self> {
+        if let Some(panic) = Self::from_artifacts(&data.artifacts) {
+            return Some(panic);
+        }
+        let location = stacks.panic_location.clone()?;
+        Some(Self {
+            thread: None,
+            message: None,
+            location: Some(location),
+            source: "stack",
+        })
+    }
+
+    /// The panic among the crash's artifacts, normally its stderr.
+    pub fn from_artifacts(artifacts: &[ArtifactData]) -> Option<Self> {
+        artifacts
+            .iter()
+            .filter_map(|a| a.content.as_deref())
+            .map(String::from_utf8_lossy)
+            .find_map(|text| Self::parse(&text))
+    }
+
+    /// Parse the default panic hook's output. The first panic is kept: a
+    /// panic during unwinding or in a `Drop` follows from it.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines().peekable();
+        let (thread, rest) = lines.by_ref().find_map(parse_panic_header)?;
+        // Rust 1.73 and later: `panicked at src/main.rs:2:5:`, the message
+        // on the following lines.
+        let (message, location) = match rest.strip_suffix(':') {
+            Some(location) => {
+                let mut message = Vec::new();
+                while let Some(line) = lines.next_if(|l| !is_hook_trailer(l)) {
+                    message.push(line);
+                }
+                (Some(message.join("\n")), parse_location(location))
+            }
+            // Before: `panicked at 'message', src/main.rs:2:5`.
+            None => match rest.rsplit_once(", ") {
+                Some((message, location)) => (
+                    message
+                        .strip_prefix('\'')
+                        .and_then(|m| m.strip_suffix('\''))
+                        .map(str::to_string),
+                    parse_location(location),
+                ),
+                None => (None, parse_location(rest)),
+            },
+        };
+        Some(Self {
+            thread: Some(thread),
+            message: message.filter(|m| !m.is_empty()),
+            location,
+            source: "stderr",
+        })
+    }
+}
+
+/// `thread 'main' (4242) panicked at ...` into the thread name and what
+/// follows `at `. The thread id is printed since Rust 1.89.
+fn parse_panic_header(line: &str) -> Option<(String, &str)> {
+    let rest = line.strip_prefix("thread '")?;
+    let (thread, rest) = rest.split_once('\'')?;
+    let (_, rest) = rest.split_once("panicked at ")?;
+    Some((thread.to_string(), rest.trim_end()))
+}
+
+fn is_hook_trailer(line: &str) -> bool {
+    line.starts_with("note: ")
+        || line.starts_with("stack backtrace:")
+        || line.starts_with("thread '")
+        || line.starts_with("panic in a function that cannot unwind")
+}
+
+/// `src/main.rs:21:5`
+fn parse_location(location: &str) -> Option<PanicLocation> {
+    let (rest, column) = location.rsplit_once(':')?;
+    let (file, line) = rest.rsplit_once(':')?;
+    Some(PanicLocation {
+        file: file.to_string(),
+        line: line.parse().ok()?,
+        column: column.parse().ok()?,
+    })
+}
+
+/// Where a Rust binary that aborted panicked: the first stack word, from the
+/// stack pointer up, that points at a `Location { file: &str, line: u32,
+/// col: u32 }` in the executable. `root` is where the process' files are
+/// found; only the headers, relocations and the words followed are read.
+pub fn location_from_stack(data: &CrashReportData, root: Option<&Path>) -> Option<PanicLocation> {
+    let is_rust = data.runtimes.iter().any(|r| r.kind == RuntimeKind::Rust);
+    if !is_rust || data.signal != SIGABRT {
+        return None;
+    }
+    let (_, dump) = data.stack_dump.as_ref()?;
+    let maps = parse_maps(&data.memory_maps);
+    let exe = maps.iter().find_map(|entry| entry.file_path())?;
+    let mappings: Vec<_> = maps
+        .iter()
+        .filter(|entry| entry.file_path() == Some(exe))
+        .collect();
+    let path = join_root(root, exe);
+
+    // After a redeploy the file on disk describes another binary.
+    let recorded = data
+        .modules
+        .iter()
+        .find(|m| m.path == exe)
+        .and_then(|m| m.build_id.as_deref());
+    if let Some(recorded) = recorded
+        && read_build_id(&path).is_none_or(|id| !id.eq_ignore_ascii_case(recorded))
+    {
+        return None;
+    }
+
+    let cache = object::ReadCache::new(std::fs::File::open(&path).ok()?);
+    let image = Image::parse(&cache)?;
+
+    dump.chunks_exact(8)
+        .take(MAX_SCANNED_WORDS)
+        .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+        .find_map(|word| {
+            let entry = mappings.iter().find(|m| m.contains(word))?;
+            let address = image.file_offset_to_address(word - entry.start + entry.offset)?;
+            image.location_at(address)
+        })
+}
+
+/// The parts of the executable needed to follow pointers in its read-only
+/// data the way the loader would.
+struct Image<R> {
+    data: R,
+    /// (file offset, file size, address)
+    segments: Vec<(u64, u64, u64)>,
+    /// Link-time value of pointer slots the loader relocates (PIE).
+    relative: HashMap<u64, u64>,
+}
+
+impl<'a, R: ReadRef<'a>> Image<R> {
+    fn parse(data: R) -> Option<Self> {
+        let file = object::File::parse(data).ok()?;
+        let segments = file
+            .segments()
+            .map(|segment| {
+                let (offset, size) = segment.file_range();
+                (offset, size, segment.address())
+            })
+            .collect();
+        let relative = file
+            .dynamic_relocations()
+            .into_iter()
+            .flatten()
+            .filter(|(_, reloc)| {
+                matches!(
+                    reloc.flags(),
+                    RelocationFlags::Elf {
+                        r_type: elf::R_X86_64_RELATIVE | elf::R_AARCH64_RELATIVE
+                    }
+                ) && reloc.target() == RelocationTarget::Absolute
+            })
+            .map(|(offset, reloc)| (offset, reloc.addend() as u64))
+            .collect();
+        Some(Self {
+            data,
+            segments,
+            relative,
+        })
+    }
+
+    fn file_offset_to_address(&self, offset: u64) -> Option<u64> {
+        self.segments
+            .iter()
+            .find(|(start, size, _)| offset >= *start && offset < start + size)
+            .map(|(start, _, address)| address + (offset - start))
+    }
+
+    fn bytes(&self, address: u64, len: u64) -> Option<&'a [u8]> {
+        let end = address.checked_add(len)?;
+        let (start, _, segment) = self
+            .segments
+            .iter()
+            .find(|(_, size, segment)| address >= *segment && end <= segment + size)?;
+        self.data
+            .read_bytes_at(start + (address - segment), len)
+            .ok()
+    }
+
+    fn u64(&self, address: u64) -> Option<u64> {
+        Some(u64::from_le_bytes(self.bytes(address, 8)?.try_into().ok()?))
+    }
+
+    fn u32(&self, address: u64) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes(address, 4)?.try_into().ok()?))
+    }
+
+    fn pointer(&self, address: u64) -> Option<u64> {
+        match self.relative.get(&address) {
+            Some(value) => Some(*value),
+            None => self.u64(address).filter(|value| *value != 0),
+        }
+    }
+
+    fn location_at(&self, address: u64) -> Option<PanicLocation> {
+        let file = self.pointer(address)?;
+        let len = self
+            .u64(address + 8)
+            .filter(|len| (1..=MAX_FILE_LEN).contains(len))?;
+        let line = self
+            .u32(address + 16)
+            .filter(|line| (1..=MAX_LINE).contains(line))?;
+        let column = self
+            .u32(address + 20)
+            .filter(|col| (1..=MAX_COLUMN).contains(col))?;
+        let file = std::str::from_utf8(self.bytes(file, len)?).ok()?;
+        if !file.ends_with(".rs") || file.contains(|c: char| c.is_control()) {
+            return None;
+        }
+        Some(PanicLocation {
+            file: file.to_string(),
+            line,
+            column,
+        })
+    }
+}
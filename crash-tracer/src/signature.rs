@@ -0,0 +1,121 @@
+use crate::{
+    db::{FRAME_SOURCE_BPF, FRAME_SOURCE_CFI},
+    symbolize::maps::{MapEntry, parse_maps},
+};
+
+/// Number of innermost frames that identify a crash.
+const SIGNATURE_FRAMES: usize = 5;
+
+/// A stable identifier for "the same crash", independent of ASLR, pid and time.
+///
+/// Built from the signal, `si_code` and the innermost frames expressed as
+/// `module+file_offset`, so the same bug in the same build always lands in the
+/// same crash group.
+pub struct CrashSignature {
+    /// 16 hex digits of a 64-bit FNV-1a hash over the fields below.
+    pub hash: String,
+    /// Normalized frames that went into the hash, innermost first.
+    pub frames: Vec<String>,
+    /// Stack the frames came from, as `stack_frames.source` names it; `None`
+    /// for exits.
+    pub source: Option<&'static str>,
+}
+
+impl CrashSignature {
+    /// The frames to sign: the CFI unwind when it recovered any, otherwise
+    /// the BPF frame-pointer walk, with the source of the one used. The order
+    /// is fixed so the signature does not depend on which walk got further.
+    pub fn pick_frames<'a>(unwound: &'a [u64], bpf: &'a [u64]) -> (&'static str, &'a [u64]) {
+        if unwound.is_empty() {
+            (FRAME_SOURCE_BPF, bpf)
+        } else {
+            (FRAME_SOURCE_CFI, unwound)
+        }
+    }
+
+    pub fn compute(
+        signal: i32,
+        si_code: i32,
+        frames: &[u64],
+        source: &'static str,
+        maps: &[String],
+    ) -> Self {
+        let maps = parse_maps(maps);
+        let frames: Vec<String> = frames
+            .iter()
+            .take_while(|ip| **ip != 0)
+            .take(SIGNATURE_FRAMES)
+            .map(|ip| normalize_frame(*ip, &maps))
+            .collect();
+
+        let mut hash = Fnv1a::new();
+        hash.write(&signal.to_le_bytes());
+        hash.write(&si_code.to_le_bytes());
+        for frame in &frames {
+            hash.write(frame.as_bytes());
+            // Separator so ["ab", "c"] and ["a", "bc"] hash differently.
+            hash.write(&[0]);
+        }
+
+        Self {
+            hash: format!("{:016x}", hash.finish()),
+            frames,
+            source: Some(source),
+        }
+    }
+
+    /// Exits have no frames, so they are grouped by command and status.
+    pub fn exit(cmd: &str, status: u8) -> Self {
+        let mut hash = Fnv1a::new();
+        hash.write(b"exit\0");
+        hash.write(cmd.as_bytes());
+        hash.write(&[0, status]);
+
+        Self {
+            hash: format!("{:016x}", hash.finish()),
+            frames: Vec::new(),
+            source: None,
+        }
+    }
+}
+
+/// `libc.so.6+0x2a1ca` for file-backed code. Anonymous memory (JIT code,
+/// trampolines) has no stable offset, so only its mapping name is kept.
+fn normalize_frame(ip: u64, maps: &[MapEntry]) -> String {
+    let Some(entry) = maps.iter().find(|m| m.contains(ip)) else {
+        return "?".to_string();
+    };
+    match entry.module_name() {
+        Some(name) if entry.inode != 0 => format!(
+            "{}+0x{:x}",
+            name.trim_end_matches(" (deleted)"),
+            ip - entry.start + entry.offset
+        ),
+        Some(name) => name.to_string(),
+        None => "[anon]".to_string(),
+    }
+}
+
+/// 64-bit FNV-1a. Used instead of `std::hash` because signatures are persisted
+/// and must not change between Rust releases.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,304 @@
+//! Which cgroup, container and Kubernetes pod a process belongs to.
+//!
+//! The eBPF side records the cgroup v2 id, which is the inode number of the
+//! cgroup's directory, and the pid and mount namespace inodes. The container
+//! and pod are recognised from the cgroup path the runtimes create:
+//!
+//! ```text
+//! /system.slice/docker-<id>.scope                              docker
+//! /docker/<id>                                                 docker (cgroupfs)
+//! /kubepods.slice/kubepods-burstable.slice/
+//!     kubepods-burstable-pod<uid>.slice/cri-containerd-<id>.scope containerd
+//! /kubepods/burstable/pod<uid>/<id>                            kubelet cgroupfs driver
+//! /kubepods.slice/.../crio-<id>.scope                          CRI-O
+//! /machine.slice/libpod-<id>.scope                             podman
+//! ```
+
+use std::{
+    collections::HashMap,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+/// Where kubelet keeps container logs, one directory per pod named
+/// `<namespace>_<name>_<uid>`. The only local record of a pod's name.
+const POD_LOG_DIR: &str = "/var/log/pods";
+const DEFAULT_CGROUP2_ROOT: &str = "/sys/fs/cgroup";
+/// Directory levels searched below the cgroup root for an unknown id.
+const MAX_CGROUP_DEPTH: usize = 10;
+const MAX_CACHED_CGROUPS: usize = 4096;
+const CONTAINER_ID_LEN: usize = 64;
+
+/// Where a process runs. Everything but the ids is empty outside containers.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ContainerInfo {
+    /// cgroup v2 id; 0 when the kernel runs cgroup v1 only.
+    pub cgroup_id: u64,
+    /// `/kubepods.slice/.../cri-containerd-<id>.scope`, relative to the
+    /// cgroup root.
+    pub cgroup_path: Option<String>,
+    /// Full 64-character container id.
+    pub container_id: Option<String>,
+    /// `docker`, `containerd`, `cri-o` or `podman`, when the path tells.
+    pub container_runtime: Option<String>,
+    pub pod_uid: Option<String>,
+    pub pod_name: Option<String>,
+    pub pod_namespace: Option<String>,
+    /// Inode of the pid namespace (`/proc/<pid>/ns/pid`).
+    pub pid_ns: u32,
+    /// Inode of the mount namespace (`/proc/<pid>/ns/mnt`).
+    pub mnt_ns: u32,
+}
+
+impl ContainerInfo {
+    /// `abcdef012345`, the short id `docker ps` and `crictl ps` show.
+    pub fn short_id(&self) -> Option<&str> {
+        let id = self.container_id.as_deref()?;
+        Some(&id[..id.len().min(12)])
+    }
+}
+
+/// Resolves cgroup ids to paths and pod uids to names. Paths found by
+/// walking the hierarchy and pod names are cached; a node runs a few hundred
+/// cgroups at most.
+pub struct ContainerResolver {
+    cgroup_root: PathBuf,
+    /// `None` for ids the walk did not find, so it is not repeated for every
+    /// event of a process in a cgroup removed since.
+    cgroups: HashMap<u64, Option<String>>,
+    pods: HashMap<String, (String, String)>,
+}
+
+impl ContainerResolver {
+    pub fn new() -> Self {
+        Self {
+            cgroup_root: cgroup2_root(),
+            cgroups: HashMap::new(),
+            pods: HashMap::new(),
+        }
+    }
+
+    /// Describe process `pid`, which the kernel placed in `cgroup_id` and the
+    /// given namespaces.
+    pub fn resolve(&mut self, pid: u32, cgroup_id: u64, pid_ns: u32, mnt_ns: u32) -> ContainerInfo {
+        let mut info = ContainerInfo {
+            cgroup_id,
+            pid_ns,
+            mnt_ns,
+            ..Default::default()
+        };
+        info.cgroup_path = self.cgroup_path(pid, cgroup_id);
+        let Some(path) = &info.cgroup_path else {
+            return info;
+        };
+
+        if let Some((id, runtime)) = parse_container_id(path) {
+            info.container_id = Some(id);
+            info.container_runtime = runtime.map(str::to_string);
+        }
+        info.pod_uid = parse_pod_uid(path);
+        if let Some(uid) = &info.pod_uid
+            && let Some((namespace, name)) = self.pod(uid)
+        {
+            info.pod_namespace = Some(namespace);
+            info.pod_name = Some(name);
+        }
+        info
+    }
+
+    /// The cgroup v2 id of running process `pid`, as eBPF reports it: the
+    /// inode of its cgroup directory. 0 when unknown, or on cgroup v1.
+    pub fn cgroup_id(&self, pid: u32) -> u64 {
+        match read_proc_cgroup(pid) {
+            Some(ProcCgroup::V2(path)) => self.inode_of(&path).unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// The cgroup path of `cgroup_id`, read from `/proc/<pid>/cgroup` when
+    /// the process is still there and in it, otherwise found by inode below
+    /// the cgroup2 root.
+    fn cgroup_path(&mut self, pid: u32, cgroup_id: u64) -> Option<String> {
+        match read_proc_cgroup(pid) {
+            Some(ProcCgroup::V1(path)) => return Some(path),
+            Some(ProcCgroup::V2(path))
+                if cgroup_id == 0 || self.inode_of(&path) == Some(cgroup_id) =>
+            {
+                return Some(path);
+            }
+            // Exited, or moved to another cgroup since.
+            _ if cgroup_id == 0 => return None,
+            _ => {}
+        }
+
+        if let Some(path) = self.cgroups.get(&cgroup_id) {
+            return path.clone();
+        }
+        let path = self.find_cgroup(cgroup_id);
+        if self.cgroups.len() >= MAX_CACHED_CGROUPS {
+            self.cgroups.clear();
+        }
+        self.cgroups.insert(cgroup_id, path.clone());
+        path
+    }
+
+    fn inode_of(&self, path: &str) -> Option<u64> {
+        let dir = self.cgroup_root.join(path.trim_start_matches('/'));
+        std::fs::metadata(dir).ok().map(|meta| meta.ino())
+    }
+
+    /// Walk the cgroup2 hierarchy for the directory with inode `cgroup_id`.
+    fn find_cgroup(&self, cgroup_id: u64) -> Option<String> {
+        let mut pending = vec![(self.cgroup_root.clone(), 0)];
+        while let Some((dir, depth)) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                if !meta.is_dir() {
+                    continue;
+                }
+                let path = entry.path();
+                if meta.ino() == cgroup_id {
+                    let relative = path.strip_prefix(&self.cgroup_root).ok()?;
+                    return Some(format!("/{}", relative.display()));
+                }
+                if depth < MAX_CGROUP_DEPTH {
+                    pending.push((path, depth + 1));
+                }
+            }
+        }
+        None
+    }
+
+    /// (namespace, name) of the pod with `uid`, from kubelet's log directory.
+    fn pod(&mut self, uid: &str) -> Option<(String, String)> {
+        if let Some(pod) = self.pods.get(uid) {
+            return Some(pod.clone());
+        }
+        let suffix = format!("_{uid}");
+        let pod = std::fs::read_dir(POD_LOG_DIR)
+            .ok()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .find_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let (namespace, name) = name.strip_suffix(&suffix)?.split_once('_')?;
+                Some((namespace.to_string(), name.to_string()))
+            });
+        // Only hits are remembered: the directory may not exist yet for a
+        // pod that is starting up.
+        if let Some(pod) = &pod {
+            if self.pods.len() >= MAX_CACHED_CGROUPS {
+                self.pods.clear();
+            }
+            self.pods.insert(uid.to_string(), pod.clone());
+        }
+        pod
+    }
+}
+
+enum ProcCgroup {
+    /// The `0::` line.
+    V2(String),
+    /// The first v1 hierarchy with a path other than `/`, on v1-only and
+    /// hybrid hosts, where containers are placed in v1 only.
+    V1(String),
+}
+
+fn read_proc_cgroup(pid: u32) -> Option<ProcCgroup> {
+    let content = std::fs::read_to_string(format!("/proc/{pid}/cgroup")).ok()?;
+    let (mut v1, mut v2) = (None, None);
+    for line in content.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(hierarchy), Some(_), Some(path)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if hierarchy == "0" {
+            v2 = Some(path.to_string());
+        } else if v1.is_none() && path != "/" {
+            v1 = Some(path.to_string());
+        }
+    }
+    match (v2, v1) {
+        (Some(v2), _) if v2 != "/" => Some(ProcCgroup::V2(v2)),
+        (_, Some(v1)) => Some(ProcCgroup::V1(v1)),
+        (v2, None) => v2.map(ProcCgroup::V2),
+    }
+}
+
+/// Mount point of the cgroup2 hierarchy: `/sys/fs/cgroup`, or
+/// `/sys/fs/cgroup/unified` on hybrid systems.
+fn cgroup2_root() -> PathBuf {
+    std::fs::read_to_string("/proc/self/mounts")
+        .ok()
+        .and_then(|mounts| {
+            mounts.lines().find_map(|line| {
+                let mut fields = line.split_whitespace();
+                let (_, target, fstype) = (fields.next()?, fields.next()?, fields.next()?);
+                (fstype == "cgroup2").then(|| PathBuf::from(target))
+            })
+        })
+        .unwrap_or_else(|| Path::new(DEFAULT_CGROUP2_ROOT).to_path_buf())
+}
+
+/// Container id and, when the path names it, the runtime; the innermost
+/// match wins, as containers may nest.
+fn parse_container_id(path: &str) -> Option<(String, Option<&'static str>)> {
+    const PREFIXES: &[(&str, &str)] = &[
+        ("docker-", "docker"),
+        ("cri-containerd-", "containerd"),
+        ("crio-", "cri-o"),
+        ("libpod-", "podman"),
+    ];
+
+    path.rsplit('/').find_map(|component| {
+        let name = component.strip_suffix(".scope").unwrap_or(component);
+        // containerd with the systemd driver but cgroupfs paths:
+        // `kubepods-burstable-pod<uid>.slice:cri-containerd:<id>`.
+        let mut parts = name.rsplit(':');
+        let last = parts.next()?;
+        if let Some(runtime) = parts.next()
+            && is_container_id(last)
+        {
+            let runtime = PREFIXES
+                .iter()
+                .find(|(prefix, _)| prefix.trim_end_matches('-') == runtime)
+                .map(|(_, runtime)| *runtime);
+            return Some((last.to_string(), runtime));
+        }
+        if let Some((id, runtime)) = PREFIXES
+            .iter()
+            .find_map(|(prefix, runtime)| Some((name.strip_prefix(prefix)?, *runtime)))
+            && is_container_id(id)
+        {
+            return Some((id.to_string(), Some(runtime)));
+        }
+        // cgroupfs drivers: the bare id below `/docker` or the pod.
+        is_container_id(name).then(|| {
+            let runtime = path.contains("/docker/").then_some("docker");
+            (name.to_string(), runtime)
+        })
+    })
+}
+
+fn is_container_id(s: &str) -> bool {
+    s.len() == CONTAINER_ID_LEN && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// `pod<uid>` (cgroupfs) or `kubepods-<qos>-pod<uid with _ for ->.slice`
+/// (systemd).
+fn parse_pod_uid(path: &str) -> Option<String> {
+    path.split(['/', ':']).find_map(|component| {
+        let name = component.strip_suffix(".slice").unwrap_or(component);
+        let (_, uid) = name.rsplit_once("pod")?;
+        let uid = uid.replace('_', "-");
+        let is_uid = uid.len() == 36 && uid.bytes().all(|b| b.is_ascii_hexdigit() || b == b'-');
+        is_uid.then_some(uid)
+    })
+}
@@ -1,12 +1,26 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{self, Display},
-    fs::OpenOptions,
+    fs::{File, OpenOptions},
     hash::Hash,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 use anyhow::Result;
+use crash_tracer_common::{
+    EXEC_ARGS_MAX, EXEC_FILENAME_MAX, ModuleLoadEvent, NS_PER_TICK, SchedExecEvent,
+};
+
+use crate::runtime::{go, rust};
+use crate::state::container::{ContainerInfo, ContainerResolver};
+use crate::state::namespace::{MountRoots, join_root, open_owned};
+use crate::symbolize::{
+    elf::read_build_id,
+    maps::{MapEntry, parse_maps},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RuntimeKind {
@@ -17,82 +31,450 @@ pub enum RuntimeKind {
     Mono,
     CoreClr,
     Python,
+    Go,
+    Rust,
+}
+
+/// A managed runtime found loaded into a process.
+pub struct DetectedRuntime {
+    pub kind: RuntimeKind,
+    /// `3.11` for `libpython3.11.so.1.0`, when the path tells.
+    pub version: Option<String>,
+    /// Library or binary it was detected from.
+    pub path: String,
+    /// Main module of the program the runtime is linked into, as
+    /// `path@version`; Go only.
+    pub module: Option<String>,
+}
+
+/// A file-backed module mapped into a process, identified by its ELF build-id
+/// so it can still be symbolized after the file on disk is replaced.
+pub struct ModuleInfo {
+    pub path: String,
+    pub build_id: Option<String>,
 }
 
 pub struct ProcessInfo {
     pub pid: u32,
+    /// `pid` inside the process' pid namespace, which is what it writes into
+    /// file names; equal to `pid` on the host.
+    pub ns_pid: u32,
     pub boottime: u64,
+    /// Effective uid, which the files it writes belong to; `None` when it
+    /// was gone before it could be read.
+    pub uid: Option<u32>,
     pub maps: Vec<String>,
-    pub runtime: RuntimeKind,
+    pub modules: Vec<ModuleInfo>,
+    /// Every runtime loaded, in maps order; empty for native code.
+    pub runtimes: Vec<DetectedRuntime>,
+    /// Path the program was exec'd from, made absolute.
+    pub exe: Option<String>,
     pub cwd: Option<String>,
     pub cmdline: Option<String>,
+    /// Environment variables matching [`RUNTIME_ENV_PREFIXES`].
+    pub environ: Vec<(String, String)>,
+    pub container: ContainerInfo,
+    /// Where the process' filesystem is found when it runs in another mount
+    /// namespace; see [`MountRoots`].
+    pub root: Option<PathBuf>,
+}
+
+impl ProcessInfo {
+    /// `path`, as the process names it, from the daemon's filesystem.
+    pub fn host_path(&self, path: impl AsRef<Path>) -> PathBuf {
+        join_root(self.root.as_deref(), path)
+    }
+
+    /// `path`, as the process names it, opened to read a file it wrote; see
+    /// [`open_owned`].
+    pub fn open_file(&self, path: impl AsRef<Path>) -> io::Result<File> {
+        let uid = self
+            .uid
+            .ok_or_else(|| io::Error::new(io::ErrorKind::PermissionDenied, "owner unknown"))?;
+        open_owned(self.root.as_deref(), path, uid)
+    }
+
+    /// Value of the runtime configuration variable `name`, if set.
+    pub fn env(&self, name: &str) -> Option<&str> {
+        self.environ
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
 }
 
+/// Environment variables runtimes read their crash and diagnostics settings
+/// from. Only these are kept; the rest of the environment may hold secrets.
+const RUNTIME_ENV_PREFIXES: &[&str] = &["DOTNET_", "COMPlus_"];
+
 #[repr(C)]
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 struct MapKey {
     pid: u32,
     boottime: u64,
 }
 const MAX_TRACKED_PROCESSES: usize = 4096;
 
+const PROT_READ: u32 = 0x1;
+const PROT_WRITE: u32 = 0x2;
+const PROT_EXEC: u32 = 0x4;
+const MAP_SHARED: u32 = 0x1;
+
 pub struct MemoryMap {
     memory_map: HashMap<MapKey, ProcessInfo>,
+    containers: ContainerResolver,
+    roots: MountRoots,
+    /// Processes whose maps changed since they were written to the database.
+    /// Most processes never crash and are dropped from it at exit, so they
+    /// are only written again for a crash; see [`take_unsaved`](Self::take_unsaved).
+    unsaved: HashSet<MapKey>,
+    /// Processes found running at startup, by pid, under their start time
+    /// rounded down to a clock tick until an event tells the exact one; see
+    /// [`settle`](Self::settle).
+    seeded: HashMap<u32, u64>,
+    build_ids: BuildIds,
+}
+
+const MAX_CACHED_BUILD_IDS: usize = 4096;
+
+/// Build-ids by the device and inode maps show for a file, and its
+/// modification time, which changes when it is rewritten in place. Most
+/// processes map the same few libraries, which are not read again for each
+/// exec.
+#[derive(Default)]
+struct BuildIds(HashMap<(String, u64, Option<SystemTime>), Option<String>>);
+
+impl BuildIds {
+    /// Build-id of the file `entry` maps, found at `file`.
+    fn get(&mut self, entry: &MapEntry, file: &Path) -> Option<String> {
+        let modified = std::fs::metadata(file).and_then(|m| m.modified()).ok();
+        let key = (entry.dev.clone(), entry.inode, modified);
+        if let Some(build_id) = self.0.get(&key) {
+            return build_id.clone();
+        }
+        if self.0.len() >= MAX_CACHED_BUILD_IDS {
+            self.0.clear();
+        }
+        let build_id = read_build_id(file);
+        self.0.insert(key, build_id.clone());
+        build_id
+    }
+}
+
+/// Identity of a process and where the kernel runs it, from its exec event
+/// or, for one already running, from /proc.
+struct Origin {
+    pid: u32,
+    ns_pid: u32,
+    boottime: u64,
+    cgroup_id: u64,
+    pid_ns: u32,
+    mnt_ns: u32,
 }
 
 impl MemoryMap {
     pub fn new() -> Self {
         Self {
             memory_map: HashMap::new(),
+            containers: ContainerResolver::new(),
+            roots: MountRoots::new(),
+            unsaved: HashSet::new(),
+            seeded: HashMap::new(),
+            build_ids: BuildIds::default(),
+        }
+    }
+
+    pub fn insert(&mut self, exec: &SchedExecEvent) {
+        let pid = exec.pid;
+        // From the exec event; /proc only fills in what did not fit.
+        let cwd = exec_cwd(exec).or_else(|| read_cwd(pid));
+
+        let args_truncated = exec.args_len as usize >= EXEC_ARGS_MAX;
+        let cmdline = args_truncated
+            .then(|| read_cmdline(pid))
+            .flatten()
+            .or_else(|| Some(join_args(&exec.args[..exec.args_len as usize])))
+            .filter(|cmdline| !cmdline.is_empty());
+
+        let exe = exec_filename(exec, cwd.as_deref()).or_else(|| read_exe(pid));
+
+        let origin = Origin {
+            pid,
+            ns_pid: if exec.ns_pid != 0 { exec.ns_pid } else { pid },
+            boottime: exec.boottime,
+            cgroup_id: exec.cgroup_id,
+            pid_ns: exec.pid_ns,
+            mnt_ns: exec.mnt_ns,
+        };
+        self.track(origin, exe, cwd, cmdline);
+    }
+
+    /// Track `pid`, which was running before the tracer started and whose
+    /// exec was never seen, from /proc. Kernel threads and processes gone
+    /// meanwhile are skipped, and so is everything once as many processes as
+    /// are kept are tracked. Returns the process as tracked.
+    pub fn seed(&mut self, pid: u32) -> Option<&ProcessInfo> {
+        if self.is_full() {
+            return None;
+        }
+        // Kernel threads have no executable.
+        let exe = read_exe(pid)?;
+        let ticks = read_start_ticks(pid)?;
+        let boottime = ticks * NS_PER_TICK;
+        let origin = Origin {
+            pid,
+            ns_pid: read_ns_pid(pid).unwrap_or(pid),
+            boottime,
+            cgroup_id: self.containers.cgroup_id(pid),
+            pid_ns: ns_inode(pid, "pid_for_children").unwrap_or(0),
+            mnt_ns: ns_inode(pid, "mnt").unwrap_or(0),
+        };
+        self.track(origin, Some(exe), read_cwd(pid), read_cmdline(pid));
+        self.seeded.insert(pid, boottime);
+        self.get(pid, boottime)
+    }
+
+    /// Whether as many processes as are kept are tracked, so that
+    /// [`seed`](Self::seed) takes no more.
+    pub fn is_full(&self) -> bool {
+        self.memory_map.len() >= MAX_TRACKED_PROCESSES
+    }
+
+    /// Move a process found running at startup to the exact start time an
+    /// event for `pid` carries, if it is the same process. Returns the start
+    /// time it was tracked under, which the database still has.
+    pub fn settle(&mut self, pid: u32, boottime: u64) -> Option<u64> {
+        let seeded = self.seeded.remove(&pid)?;
+        if seeded == boottime {
+            return None;
+        }
+        let info = self.memory_map.remove(&MapKey {
+            pid,
+            boottime: seeded,
+        })?;
+        let unsaved = self.unsaved.remove(&MapKey {
+            pid,
+            boottime: seeded,
+        });
+        // The pid was reused since.
+        if boottime / NS_PER_TICK * NS_PER_TICK != seeded {
+            self.release_root(&info);
+            return None;
+        }
+        let key = MapKey { pid, boottime };
+        if unsaved {
+            self.unsaved.insert(key);
         }
+        self.memory_map
+            .insert(key, ProcessInfo { boottime, ..info });
+        Some(seeded)
     }
 
-    pub fn insert(&mut self, pid: u32, boottime: u64) {
+    fn track(
+        &mut self,
+        origin: Origin,
+        exe: Option<String>,
+        cwd: Option<String>,
+        cmdline: Option<String>,
+    ) {
+        let (pid, boottime) = (origin.pid, origin.boottime);
         if self.memory_map.len() >= MAX_TRACKED_PROCESSES {
             log::warn!(
                 "Memory map exceeded {} entries, pruning stale entries",
                 MAX_TRACKED_PROCESSES
             );
-            self.memory_map
-                .retain(|key, _| std::fs::metadata(format!("/proc/{}", key.pid)).is_ok());
+            let (roots, unsaved) = (&mut self.roots, &mut self.unsaved);
+            self.memory_map.retain(|key, info| {
+                let alive = std::fs::metadata(format!("/proc/{}", key.pid)).is_ok();
+                if !alive {
+                    unsaved.remove(key);
+                    if info.root.is_some() {
+                        roots.release(info.container.mnt_ns);
+                    }
+                }
+                alive
+            });
         }
 
-        let maps = match self.read_map(pid) {
-            Ok(maps) => maps,
-            Err(e) => {
-                log::warn!("Failed to read /proc/{}/maps: {e}", pid);
-                return;
-            }
-        };
-
-        let runtime = self.detect_runtime(&maps);
+        // Re-read when the process crashes; a process that exited already
+        // is still worth knowing for its exit status.
+        let maps = self.read_map(pid).unwrap_or_else(|e| {
+            log::debug!("Failed to read /proc/{}/maps: {e}", pid);
+            Vec::new()
+        });
 
-        let cwd = std::fs::read_link(format!("/proc/{}/cwd", pid))
-            .ok()
-            .map(|p| p.to_string_lossy().into_owned());
+        let root = self.roots.acquire(pid, origin.mnt_ns);
+        let runtimes = self.detect_runtimes(&maps, root.as_deref());
+        let modules = read_modules(&mut self.build_ids, &maps, root.as_deref(), &[]);
 
-        let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid))
-            .ok()
+        let environ = std::fs::read(format!("/proc/{}/environ", pid))
             .map(|bytes| {
                 bytes
                     .split(|&b| b == 0)
-                    .filter(|s| !s.is_empty())
-                    .map(|s| String::from_utf8_lossy(s).into_owned())
-                    .collect::<Vec<_>>()
-                    .join(" ")
-            });
+                    .filter_map(|var| {
+                        let var = String::from_utf8_lossy(var);
+                        let (key, value) = var.split_once('=')?;
+                        RUNTIME_ENV_PREFIXES
+                            .iter()
+                            .any(|prefix| key.starts_with(prefix))
+                            .then(|| (key.to_string(), value.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
 
-        self.memory_map.insert(
+        let container =
+            self.containers
+                .resolve(pid, origin.cgroup_id, origin.pid_ns, origin.mnt_ns);
+
+        let previous = self.memory_map.insert(
             MapKey { pid, boottime },
             ProcessInfo {
                 pid,
+                ns_pid: origin.ns_pid,
                 boottime,
+                uid: read_uid(pid),
                 maps,
-                runtime,
+                modules,
+                runtimes,
+                exe,
                 cwd,
                 cmdline,
+                environ,
+                container,
+                root,
             },
         );
+        // The same process exec'ing again; the caller writes it out.
+        if let Some(previous) = previous {
+            self.release_root(&previous);
+            self.unsaved.remove(&MapKey { pid, boottime });
+        }
+    }
+
+    /// Re-resolve the container of a known process that is now in another
+    /// cgroup or namespace than at exec, e.g. one systemd or a runtime moved
+    /// after starting it. Returns the process when anything changed.
+    pub fn update_container(
+        &mut self,
+        pid: u32,
+        boottime: u64,
+        cgroup_id: u64,
+        pid_ns: u32,
+        mnt_ns: u32,
+    ) -> Option<&ProcessInfo> {
+        let info = self.memory_map.get_mut(&MapKey { pid, boottime })?;
+        let container = &info.container;
+        if (container.cgroup_id, container.pid_ns, container.mnt_ns) == (cgroup_id, pid_ns, mnt_ns)
+        {
+            return None;
+        }
+        if mnt_ns != container.mnt_ns {
+            if info.root.is_some() {
+                self.roots.release(container.mnt_ns);
+            }
+            info.root = self.roots.acquire(pid, mnt_ns);
+        }
+        info.container = self.containers.resolve(pid, cgroup_id, pid_ns, mnt_ns);
+        Some(info)
+    }
+
+    /// Re-read the maps of a process, which may have mapped files the
+    /// module events missed, such as before the tracer started or with the
+    /// ring buffer full, and unmapped others. Called as it takes a crash
+    /// signal, while it is still alive.
+    pub fn refresh_maps(&mut self, pid: u32, boottime: u64) {
+        let Some(maps) = self
+            .read_map(pid)
+            .map_err(|e| log::debug!("Failed to re-read /proc/{}/maps: {e}", pid))
+            .ok()
+            .filter(|maps| !maps.is_empty())
+        else {
+            return;
+        };
+        let key = MapKey { pid, boottime };
+        let Some(info) = self.memory_map.get(&key) else {
+            return;
+        };
+        if info.maps == maps {
+            return;
+        }
+
+        let root = info.root.as_deref();
+        let runtimes = self.detect_runtimes(&maps, root);
+        let modules = read_modules(&mut self.build_ids, &maps, root, &info.modules);
+        if let Some(info) = self.memory_map.get_mut(&key) {
+            info.maps = maps;
+            info.modules = modules;
+            info.runtimes = runtimes;
+            self.unsaved.insert(key);
+        }
+    }
+
+    /// Add a file a process mapped executable since exec, typically a
+    /// library it `dlopen`ed, replacing whatever was mapped there before.
+    ///
+    /// Unmaps are not followed: `munmap` is too frequent to trace, and a
+    /// library `dlclose`d stays listed until [`refresh_maps`](Self::refresh_maps).
+    /// Code is only ever run from an executable mapping, which would have
+    /// replaced it here, so a stale entry never names a frame.
+    pub fn load_module(&mut self, module: &ModuleLoadEvent) {
+        let key = MapKey {
+            pid: module.pid,
+            boottime: module.boottime,
+        };
+        // Without the whole path the file cannot be read.
+        let Some(path) = module_path(module) else {
+            return;
+        };
+        let Some(info) = self.memory_map.get_mut(&key) else {
+            return;
+        };
+
+        let entry = MapEntry {
+            start: module.start,
+            end: module.end,
+            perms: perms(module.prot, module.flags),
+            offset: module.offset,
+            dev: format!("{:02x}:{:02x}", module.dev >> 20, module.dev & 0xfffff),
+            inode: module.inode,
+            path: Some(path.clone()),
+        };
+        unmap(&mut info.maps, module.start, module.end);
+        let at = info
+            .maps
+            .iter()
+            .position(|line| MapEntry::parse(line).is_some_and(|e| e.start > module.start))
+            .unwrap_or(info.maps.len());
+        info.maps.insert(at, entry.to_string());
+
+        if !info.modules.iter().any(|m| m.path == path) {
+            info.modules.push(ModuleInfo {
+                build_id: self.build_ids.get(&entry, &info.host_path(&path)),
+                path: path.clone(),
+            });
+        }
+        if let Some(kind) = runtime_of(&path)
+            && !info.runtimes.iter().any(|r| r.kind == kind)
+        {
+            info.runtimes.push(DetectedRuntime {
+                kind,
+                version: runtime_version(kind, &path),
+                path,
+                module: None,
+            });
+        }
+        self.unsaved.insert(key);
+    }
+
+    /// The process, if its maps changed since it was last written to the
+    /// database, which the caller is about to do.
+    pub fn take_unsaved(&mut self, pid: u32, boottime: u64) -> Option<&ProcessInfo> {
+        let key = MapKey { pid, boottime };
+        if !self.unsaved.remove(&key) {
+            return None;
+        }
+        self.memory_map.get(&key)
     }
 
     pub fn get(&self, pid: u32, boottime: u64) -> Option<&ProcessInfo> {
@@ -100,7 +482,16 @@ impl MemoryMap {
     }
 
     pub fn remove(&mut self, pid: u32, boottime: u64) {
-        self.memory_map.remove(&MapKey { pid, boottime });
+        self.unsaved.remove(&MapKey { pid, boottime });
+        if let Some(info) = self.memory_map.remove(&MapKey { pid, boottime }) {
+            self.release_root(&info);
+        }
+    }
+
+    fn release_root(&mut self, info: &ProcessInfo) {
+        if info.root.is_some() {
+            self.roots.release(info.container.mnt_ns);
+        }
     }
 
     fn read_map(&mut self, pid: u32) -> Result<Vec<String>, anyhow::Error> {
@@ -118,34 +509,318 @@ impl MemoryMap {
             .collect()
     }
 
-    // detect a possible runtime in the process. this logic does assume that there is
-    // a sole primary runtime. There are cases where multiple runtimes are used, but it is rare.
-    // we do first match rather than handle those for now. an example is Jython
-    fn detect_runtime(&self, maps: &[String]) -> RuntimeKind {
-        for line in maps {
-            if let Some(path) = line.split_whitespace().last() {
-                if path.contains("libjvm.so") {
-                    return RuntimeKind::Jvm;
-                }
-                if path.contains("libil2cpp.so") {
-                    return RuntimeKind::Il2Cpp;
-                }
-                if path.contains("libnode.so") || path.contains("libv8.so") {
-                    return RuntimeKind::V8;
-                }
-                if path.contains("libcoreclr.so") {
-                    return RuntimeKind::CoreClr;
-                }
-                if path.contains("libmonosgen") {
-                    return RuntimeKind::Mono;
+    /// Every runtime loaded into the process, in maps order. Several can be
+    /// present at once, e.g. a JVM embedded into Python through JPype.
+    fn detect_runtimes(&self, maps: &[String], root: Option<&Path>) -> Vec<DetectedRuntime> {
+        let mut runtimes: Vec<DetectedRuntime> = Vec::new();
+        let entries = parse_maps(maps);
+
+        // Go and Rust are linked into the executable, mapped first, which
+        // only tells by its build info or compiler notes.
+        if let Some(exe) = entries.iter().find_map(|entry| entry.file_path()) {
+            let file = join_root(root, exe);
+            if let Some(info) = go::read_build_info(&file) {
+                runtimes.push(DetectedRuntime {
+                    kind: RuntimeKind::Go,
+                    version: info.version(),
+                    path: exe.to_string(),
+                    module: info.module,
+                });
+            } else if let Some(info) = rust::read_binary_info(&file) {
+                runtimes.push(DetectedRuntime {
+                    kind: RuntimeKind::Rust,
+                    version: info.version,
+                    path: exe.to_string(),
+                    module: None,
+                });
+            }
+        }
+
+        for entry in &entries {
+            let Some(path) = entry.file_path() else {
+                continue;
+            };
+            let Some(kind) = runtime_of(path) else {
+                continue;
+            };
+            if runtimes.iter().any(|r| r.kind == kind) {
+                continue;
+            }
+            runtimes.push(DetectedRuntime {
+                kind,
+                version: runtime_version(kind, path),
+                path: path.to_string(),
+                module: None,
+            });
+        }
+        runtimes
+    }
+}
+
+fn read_cwd(pid: u32) -> Option<String> {
+    std::fs::read_link(format!("/proc/{}/cwd", pid))
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+}
+
+fn read_exe(pid: u32) -> Option<String> {
+    std::fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+}
+
+fn read_cmdline(pid: u32) -> Option<String> {
+    std::fs::read(format!("/proc/{}/cmdline", pid))
+        .ok()
+        .map(|bytes| join_args(&bytes))
+}
+
+/// `starttime` from `/proc/<pid>/stat`, in clock ticks since boot.
+fn read_start_ticks(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // `comm` is parenthesized and may contain spaces or parentheses itself;
+    // `starttime` is the 20th field after it.
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+/// The innermost pid from the `NSpid` line of `/proc/<pid>/status`.
+fn read_ns_pid(pid: u32) -> Option<u32> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let line = status
+        .lines()
+        .find_map(|line| line.strip_prefix("NSpid:"))?;
+    line.split_whitespace().last()?.parse().ok()
+}
+
+/// Effective uid of `pid`.
+fn read_uid(pid: u32) -> Option<u32> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let line = status.lines().find_map(|line| line.strip_prefix("Uid:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Inode of namespace `kind` of `pid`, as `/proc/<pid>/ns/<kind>` shows it.
+fn ns_inode(pid: u32, kind: &str) -> Option<u32> {
+    let meta = std::fs::metadata(format!("/proc/{}/ns/{}", pid, kind)).ok()?;
+    Some(meta.ino() as u32)
+}
+
+/// One entry per distinct file-backed mapping, in maps order. `root` is
+/// where the process' files are found, if not under `/`. Build-ids of
+/// the `known` modules are kept rather than read again.
+fn read_modules(
+    build_ids: &mut BuildIds,
+    maps: &[String],
+    root: Option<&Path>,
+    known: &[ModuleInfo],
+) -> Vec<ModuleInfo> {
+    let mut modules: Vec<ModuleInfo> = Vec::new();
+    for entry in parse_maps(maps) {
+        let Some(path) = entry.file_path() else {
+            continue;
+        };
+        if modules.iter().any(|m| m.path == path) {
+            continue;
+        }
+        modules.push(ModuleInfo {
+            path: path.to_string(),
+            build_id: match known.iter().find(|m| m.path == path) {
+                Some(module) => module.build_id.clone(),
+                None => build_ids.get(&entry, &join_root(root, path)),
+            },
+        });
+    }
+    modules
+}
+
+/// The working directory the exec event carries, when it was complete.
+fn exec_cwd(exec: &SchedExecEvent) -> Option<String> {
+    if exec.cwd_complete == 0 {
+        return None;
+    }
+    let len = (exec.cwd_len as usize).min(exec.cwd.len());
+    let cwd = join_components(&exec.cwd[..len]);
+    Some(if cwd.is_empty() { "/".to_string() } else { cwd })
+}
+
+/// The path passed to exec, resolved against `cwd` when relative.
+fn exec_filename(exec: &SchedExecEvent, cwd: Option<&str>) -> Option<String> {
+    let len = (exec.filename_len as usize).min(exec.filename.len());
+    if len == 0 || len >= EXEC_FILENAME_MAX - 1 {
+        return None;
+    }
+    let filename = String::from_utf8_lossy(&exec.filename[..len]);
+    match cwd {
+        Some(cwd) if !filename.starts_with('/') => Some(
+            Path::new(cwd)
+                .join(filename.trim_start_matches("./"))
+                .to_string_lossy()
+                .into_owned(),
+        ),
+        _ => Some(filename.into_owned()),
+    }
+}
+
+/// Path of a mapped file, from its components innermost first, when complete.
+fn module_path(module: &ModuleLoadEvent) -> Option<String> {
+    if module.path_complete == 0 {
+        return None;
+    }
+    let len = (module.path_len as usize).min(module.path.len());
+    Some(join_components(&module.path[..len])).filter(|path| !path.is_empty())
+}
+
+/// An absolute path from the NUL-terminated components the eBPF side walks
+/// up, innermost first; empty for the root.
+fn join_components(bytes: &[u8]) -> String {
+    let components: Vec<_> = bytes
+        .split(|&b| b == 0)
+        .filter(|c| !c.is_empty())
+        .map(String::from_utf8_lossy)
+        .collect();
+    let mut path = String::new();
+    for component in components.iter().rev() {
+        path.push('/');
+        path.push_str(component);
+    }
+    path
+}
+
+/// `r-xp`, from `mmap` protection and flags.
+fn perms(prot: u32, flags: u32) -> String {
+    [
+        if prot & PROT_READ != 0 { 'r' } else { '-' },
+        if prot & PROT_WRITE != 0 { 'w' } else { '-' },
+        if prot & PROT_EXEC != 0 { 'x' } else { '-' },
+        if flags & MAP_SHARED != 0 { 's' } else { 'p' },
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// Remove `start..end` from maps lines, splitting the mappings it cuts
+/// through.
+fn unmap(maps: &mut Vec<String>, start: u64, end: u64) {
+    let mut kept = Vec::with_capacity(maps.len());
+    for line in maps.drain(..) {
+        let Some(entry) = MapEntry::parse(&line).filter(|e| e.start < end && e.end > start) else {
+            kept.push(line);
+            continue;
+        };
+        if entry.start < start {
+            kept.push(
+                MapEntry {
+                    end: start,
+                    perms: entry.perms.clone(),
+                    dev: entry.dev.clone(),
+                    path: entry.path.clone(),
+                    ..entry
                 }
-                if path.contains("libpython3") {
-                    return RuntimeKind::Python;
+                .to_string(),
+            );
+        }
+        if entry.end > end {
+            let cut = end - entry.start;
+            kept.push(
+                MapEntry {
+                    start: end,
+                    offset: entry.offset + cut,
+                    ..entry
                 }
-            }
+                .to_string(),
+            );
+        }
+    }
+    *maps = kept;
+}
+
+/// NUL-separated arguments, as in `/proc/<pid>/cmdline`, joined by spaces.
+fn join_args(bytes: &[u8]) -> String {
+    bytes
+        .split(|&b| b == 0)
+        .filter(|s| !s.is_empty())
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The runtime a mapped file belongs to, if any.
+fn runtime_of(path: &str) -> Option<RuntimeKind> {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    if path.contains("libjvm.so") {
+        return Some(RuntimeKind::Jvm);
+    }
+    if path.contains("libil2cpp.so") {
+        return Some(RuntimeKind::Il2Cpp);
+    }
+    // The stock `node` binary links V8 statically.
+    if path.contains("libnode.so")
+        || path.contains("libv8.so")
+        || file == "node"
+        || file == "nodejs"
+    {
+        return Some(RuntimeKind::V8);
+    }
+    if path.contains("libcoreclr.so") {
+        return Some(RuntimeKind::CoreClr);
+    }
+    if path.contains("libmonosgen") {
+        return Some(RuntimeKind::Mono);
+    }
+    // Distribution `python3` binaries often link libpython statically.
+    if path.contains("libpython3") || (file.starts_with("python3") && !file.contains(".so")) {
+        return Some(RuntimeKind::Python);
+    }
+    None
+}
+
+/// Version of the runtime `path` was detected from, from a versioned library
+/// name (`libpython3.11.so.1.0`) or install directory
+/// (`/usr/lib/jvm/java-17-openjdk-amd64/lib/server/libjvm.so`,
+/// `/usr/share/dotnet/shared/Microsoft.NETCore.App/8.0.1/libcoreclr.so`).
+fn runtime_version(kind: RuntimeKind, path: &str) -> Option<String> {
+    let (name_prefixes, dir_prefixes): (&[&str], &[&str]) = match kind {
+        RuntimeKind::Python => (&["libpython", "python"], &[]),
+        RuntimeKind::Mono => (&["libmonosgen-"], &[]),
+        RuntimeKind::Jvm => (&[], &["java-", "jdk-", "jdk", "jre-", "jre", "openjdk-"]),
+        RuntimeKind::CoreClr => (&[], &[""]),
+        // nvm and the official tarballs: `.../node/v20.11.0/bin/node`,
+        // `node-v20.11.0-linux-x64/bin/node`.
+        RuntimeKind::V8 => (&[], &["v", "node-v"]),
+        // Read from the build info instead.
+        RuntimeKind::Il2Cpp | RuntimeKind::Go | RuntimeKind::Rust | RuntimeKind::Native => {
+            (&[], &[])
         }
-        RuntimeKind::Native
+    };
+
+    let mut components = path.rsplit('/');
+    let file = components.next()?;
+    if let Some(version) = name_prefixes
+        .iter()
+        .find_map(|prefix| leading_version(file.strip_prefix(prefix)?))
+    {
+        return Some(version);
     }
+    components.find_map(|dir| {
+        dir_prefixes
+            .iter()
+            .filter_map(|prefix| leading_version(dir.strip_prefix(prefix)?))
+            // A bare number is too likely to be something else, except in
+            // JDK directory names (`java-17-openjdk`).
+            .find(|version| version.contains('.') || kind == RuntimeKind::Jvm)
+    })
+}
+
+/// `3.11` from `3.11.so.1.0`.
+fn leading_version(s: &str) -> Option<String> {
+    let end = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let version = s[..end].trim_end_matches('.');
+    version
+        .starts_with(|c: char| c.is_ascii_digit())
+        .then(|| version.to_string())
 }
 
 impl RuntimeKind {
@@ -158,16 +833,78 @@ impl RuntimeKind {
             RuntimeKind::Mono => 4,
             RuntimeKind::CoreClr => 5,
             RuntimeKind::Python => 6,
+            RuntimeKind::Go => 7,
+            RuntimeKind::Rust => 8,
+        }
+    }
+}
+
+impl RuntimeKind {
+    /// Inverse of the `Display` impl, as stored in the database.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            RuntimeKind::Native,
+            RuntimeKind::Jvm,
+            RuntimeKind::V8,
+            RuntimeKind::Il2Cpp,
+            RuntimeKind::Mono,
+            RuntimeKind::CoreClr,
+            RuntimeKind::Python,
+            RuntimeKind::Go,
+            RuntimeKind::Rust,
+        ]
+        .into_iter()
+        .find(|kind| kind.to_string().eq_ignore_ascii_case(name))
+    }
+}
+
+impl Display for DetectedRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{} {version}", self.kind)?,
+            None => write!(f, "{}", self.kind)?,
+        }
+        match &self.module {
+            Some(module) => write!(f, " ({module})"),
+            None => Ok(()),
         }
     }
 }
 
+/// `Python+Jvm`, or `Native` when no runtime was detected; the
+/// `processes.runtime` column.
+pub fn runtime_names(runtimes: &[DetectedRuntime]) -> String {
+    if runtimes.is_empty() {
+        return RuntimeKind::Native.to_string();
+    }
+    runtimes
+        .iter()
+        .map(|r| r.kind.to_string())
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// `Python 3.11, Jvm 17`, or `Native`; for reports.
+pub fn describe_runtimes(runtimes: &[DetectedRuntime]) -> String {
+    if runtimes.is_empty() {
+        return RuntimeKind::Native.to_string();
+    }
+    runtimes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 impl Display for RuntimeKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
         match self {
             RuntimeKind::CoreClr => {
                 write!(f, "CoreClr")
             }
+            RuntimeKind::Go => {
+                write!(f, "Go")
+            }
             RuntimeKind::Il2Cpp => {
                 write!(f, "Il2Cpp")
             }
@@ -183,6 +920,9 @@ impl Display for RuntimeKind {
             RuntimeKind::Python => {
                 write!(f, "Python")
             }
+            RuntimeKind::Rust => {
+                write!(f, "Rust")
+            }
             RuntimeKind::V8 => {
                 write!(f, "V8")
             }
@@ -1 +1,3 @@
+pub mod container;
 pub mod map;
+pub mod namespace;
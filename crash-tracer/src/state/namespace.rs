@@ -0,0 +1,137 @@
+//! Files of processes in other mount namespaces, such as containers. The
+//! paths in their maps, command lines and artifacts name files in their own
+//! filesystem, which is reachable from here through `/proc/<pid>/root` only
+//! while the process lives. Reports are written once it has exited, so the
+//! root directory is held open per namespace for as long as any of its
+//! processes is tracked. That keeps a container's root filesystem readable
+//! after its last process exited; volumes mounted below it are detached
+//! with the namespace.
+
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    ffi::CString,
+    fs::File,
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd},
+        unix::{ffi::OsStrExt, fs::MetadataExt},
+    },
+    path::{Path, PathBuf},
+};
+
+struct Root {
+    dir: File,
+    /// Tracked processes in the namespace.
+    users: usize,
+}
+
+pub struct MountRoots {
+    /// Inode of the daemon's own mount namespace.
+    own: Option<u32>,
+    roots: HashMap<u32, Root>,
+}
+
+impl MountRoots {
+    pub fn new() -> Self {
+        Self {
+            own: mnt_ns_of("self"),
+            roots: HashMap::new(),
+        }
+    }
+
+    /// Hold the root directory of `pid`, which runs in mount namespace
+    /// `mnt_ns`, for one more process. Returns the path the namespace's files
+    /// are found under, or `None` when the process shares the daemon's
+    /// namespace or is gone. Every returned root is given back with
+    /// [`release`](Self::release).
+    pub fn acquire(&mut self, pid: u32, mnt_ns: u32) -> Option<PathBuf> {
+        if mnt_ns == 0 || self.own.is_none_or(|own| own == mnt_ns) {
+            return None;
+        }
+        let root = match self.roots.entry(mnt_ns) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let dir = File::open(format!("/proc/{pid}/root"))
+                    .map_err(|e| log::debug!("cannot open root of pid {pid}: {e}"))
+                    .ok()?;
+                entry.insert(Root { dir, users: 0 })
+            }
+        };
+        root.users += 1;
+        Some(fd_path(&root.dir))
+    }
+
+    pub fn release(&mut self, mnt_ns: u32) {
+        if let Some(root) = self.roots.get_mut(&mnt_ns) {
+            root.users -= 1;
+            if root.users == 0 {
+                self.roots.remove(&mnt_ns);
+            }
+        }
+    }
+}
+
+/// `path`, as a process with root directory `root` names it, from here.
+/// Relative paths are left alone. Symlinks in the result resolve against the
+/// daemon's root, so files a process may have planted are opened with
+/// [`open_owned`] instead.
+pub fn join_root(root: Option<&Path>, path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    match (root, path.strip_prefix("/")) {
+        (Some(root), Ok(relative)) => root.join(relative),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Open `path`, as a process with root directory `root` names it, to read a
+/// file the process wrote. The daemon reads as root, and a predictable name
+/// such as `/tmp/perf-<pid>.map` may have been planted by anyone, so the
+/// file must not be a symlink and must be a regular file owned by `uid`.
+/// Symlinks on the way resolve below `root`, as they do for the process,
+/// never reaching the daemon's own files.
+pub fn open_owned(root: Option<&Path>, path: impl AsRef<Path>, uid: u32) -> io::Result<File> {
+    let path = path.as_ref();
+    let dir = File::open(root.unwrap_or(Path::new("/")))?;
+    let name = CString::new(
+        path.strip_prefix("/")
+            .unwrap_or(path)
+            .as_os_str()
+            .as_bytes(),
+    )?;
+    let mut how: libc::open_how = unsafe { std::mem::zeroed() };
+    how.flags = (libc::O_RDONLY | libc::O_NOFOLLOW | libc::O_CLOEXEC) as u64;
+    how.resolve = libc::RESOLVE_IN_ROOT | libc::RESOLVE_NO_MAGICLINKS;
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_openat2,
+            dir.as_raw_fd(),
+            name.as_ptr(),
+            &how,
+            size_of::<libc::open_how>(),
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let file = unsafe { File::from_raw_fd(fd as i32) };
+    let meta = file.metadata()?;
+    if !meta.is_file() || meta.uid() != uid {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("not a regular file of uid {uid}"),
+        ));
+    }
+    Ok(file)
+}
+
+/// Inode of the mount namespace of `pid`, or of `self`.
+fn mnt_ns_of(pid: &str) -> Option<u32> {
+    let meta = std::fs::metadata(format!("/proc/{pid}/ns/mnt")).ok()?;
+    Some(meta.ino() as u32)
+}
+
+/// `/proc/self/fd/<fd>`, which resolves to the directory `dir` was opened on
+/// in whatever namespace it belongs to.
+fn fd_path(dir: &File) -> PathBuf {
+    PathBuf::from(format!("/proc/self/fd/{}", dir.as_raw_fd()))
+}
@@ -0,0 +1,259 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use object::{Object, ObjectSection, ObjectSegment, ObjectSymbol, ObjectSymbolTable};
+
+use crate::{
+    state::namespace::join_root,
+    symbolize::{SourceLocation, gopclntab::GoPclnTab, unwind::CfiTables},
+};
+
+/// Root of the distro debug-info tree (`debuginfo` packages, `-dbg` packages).
+const DEBUG_ROOT: &str = "/usr/lib/debug";
+
+struct Segment {
+    file_offset: u64,
+    file_size: u64,
+    address: u64,
+}
+
+/// Everything needed to symbolize addresses inside one mapped ELF file.
+pub struct ElfModule {
+    segments: Vec<Segment>,
+    /// (address, name) sorted by address, from `.symtab` or `.dynsym`.
+    symbols: Vec<(u64, String)>,
+    dwarf: Option<addr2line::Loader>,
+    cfi: CfiTables,
+    /// Go binaries name their functions here even when stripped.
+    gopclntab: Option<GoPclnTab>,
+}
+
+impl ElfModule {
+    /// Load `path`, taking DWARF from `debug_file` when given and otherwise
+    /// searching the usual system debug locations. `root` is where the
+    /// process' filesystem is found, which `path` is already below.
+    pub fn load(
+        path: &Path,
+        debug_file: Option<&Path>,
+        root: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        let data = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        let file = object::File::parse(&*data)
+            .with_context(|| format!("parsing ELF {}", path.display()))?;
+
+        let segments = file
+            .segments()
+            .map(|segment| {
+                let (file_offset, file_size) = segment.file_range();
+                Segment {
+                    file_offset,
+                    file_size,
+                    address: segment.address(),
+                }
+            })
+            .collect();
+
+        let debug_file = debug_file
+            .map(Path::to_path_buf)
+            .or_else(|| find_debug_file(path, &file, root));
+        let debug_data = debug_file.as_ref().and_then(|p| std::fs::read(p).ok());
+        let debug_obj = debug_data
+            .as_deref()
+            .and_then(|d| object::File::parse(d).ok());
+
+        let mut symbols = read_symbols(&file);
+        if let Some(debug_obj) = &debug_obj {
+            // Stripped binaries only carry .dynsym; the debug file has the full .symtab.
+            let debug_symbols = read_symbols(debug_obj);
+            if debug_symbols.len() > symbols.len() {
+                symbols = debug_symbols;
+            }
+        }
+
+        let cfi = CfiTables::load(&file, debug_obj.as_ref());
+
+        let gopclntab = file
+            .section_by_name(".gopclntab")
+            .and_then(|section| section.data().ok())
+            .and_then(|data| {
+                let text = file.section_by_name(".text").map(|s| s.address());
+                GoPclnTab::parse(data.to_vec(), text)
+            });
+
+        let dwarf_path = debug_file.as_deref().unwrap_or(path);
+        let dwarf = addr2line::Loader::new(dwarf_path)
+            .map_err(|e| log::debug!("no DWARF for {}: {e}", dwarf_path.display()))
+            .ok();
+
+        Ok(Self {
+            segments,
+            symbols,
+            dwarf,
+            cfi,
+            gopclntab,
+        })
+    }
+
+    /// Translate a file offset (as derived from a maps line) into the
+    /// link-time virtual address used by the symbol table and DWARF.
+    pub fn file_offset_to_address(&self, file_offset: u64) -> Option<u64> {
+        self.segments
+            .iter()
+            .find(|s| file_offset >= s.file_offset && file_offset < s.file_offset + s.file_size)
+            .map(|s| s.address + (file_offset - s.file_offset))
+    }
+
+    pub fn cfi(&self) -> &CfiTables {
+        &self.cfi
+    }
+
+    /// Nearest preceding symbol and the offset of `address` into it, from
+    /// `.gopclntab` when there is no symbol table.
+    pub fn find_symbol(&self, address: u64) -> Option<(&str, u64)> {
+        if self.symbols.is_empty() {
+            let func = self.gopclntab.as_ref()?.find(address)?;
+            return Some((func.name, address - func.entry));
+        }
+        let idx = self.symbols.partition_point(|(addr, _)| *addr <= address);
+        let (addr, name) = self.symbols.get(idx.checked_sub(1)?)?;
+        Some((name.as_str(), address - addr))
+    }
+
+    /// Source locations for `address`, innermost inlined function first.
+    /// Without DWARF, Go binaries still give the physical function's line.
+    pub fn find_locations(&self, address: u64) -> Vec<SourceLocation> {
+        let mut locations = Vec::new();
+        let frames = self
+            .dwarf
+            .as_ref()
+            .and_then(|d| d.find_frames(address).ok());
+        let Some(mut frames) = frames else {
+            locations.extend(self.go_location(address));
+            return locations;
+        };
+        while let Ok(Some(frame)) = frames.next() {
+            let function = frame
+                .function
+                .as_ref()
+                .and_then(|f| f.demangle().ok())
+                .map(|name| name.into_owned());
+            let (file, line) = match &frame.location {
+                Some(loc) => (loc.file.map(str::to_string), loc.line),
+                None => (None, None),
+            };
+            locations.push(SourceLocation {
+                function,
+                function_offset: None,
+                file,
+                line,
+                inlined: true,
+            });
+        }
+        // The last frame is the physical function; everything before it was inlined into it.
+        if let Some(last) = locations.last_mut() {
+            last.inlined = false;
+        }
+        if locations.is_empty() {
+            locations.extend(self.go_location(address));
+        }
+        locations
+    }
+
+    fn go_location(&self, address: u64) -> Option<SourceLocation> {
+        let func = self.gopclntab.as_ref()?.find(address)?;
+        Some(SourceLocation {
+            function: Some(func.name.to_string()),
+            function_offset: None,
+            file: func.file.map(str::to_string),
+            line: func.line,
+            inlined: false,
+        })
+    }
+}
+
+/// Read the `.note.gnu.build-id` of an ELF file as lowercase hex.
+///
+/// Only the headers and note segments are read, so this is cheap enough to run
+/// for every module of every exec'd process.
+pub fn read_build_id(path: &Path) -> Option<String> {
+    let file = std::fs::File::open(path).ok()?;
+    let cache = object::ReadCache::new(file);
+    let elf = object::File::parse(&cache).ok()?;
+    let build_id = elf.build_id().ok()??;
+    Some(build_id_hex(build_id))
+}
+
+pub fn build_id_hex(build_id: &[u8]) -> String {
+    build_id.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn read_symbols(file: &object::File) -> Vec<(u64, String)> {
+    let table = file.symbol_table().or_else(|| file.dynamic_symbol_table());
+    let Some(table) = table else {
+        return Vec::new();
+    };
+    let mut symbols: Vec<(u64, String)> = table
+        .symbols()
+        .filter(|sym| sym.is_definition() && sym.address() != 0)
+        .filter_map(|sym| Some((sym.address(), demangle(sym.name().ok()?))))
+        .filter(|(_, name)| !name.is_empty())
+        .collect();
+    symbols.sort_by_key(|(addr, _)| *addr);
+    symbols.dedup_by_key(|(addr, _)| *addr);
+    symbols
+}
+
+/// Rust symbols (legacy `_ZN...17h<hash>E` and v0 `_R...`) without the
+/// hash; anything else, C++ included, as is.
+fn demangle(name: &str) -> String {
+    match rustc_demangle::try_demangle(name) {
+        Ok(demangled) => format!("{demangled:#}"),
+        Err(_) => name.to_string(),
+    }
+}
+
+/// Locate separate debug info for `path`, following the same conventions as gdb:
+/// `/usr/lib/debug/.build-id/xx/yyyy.debug` first, then `.gnu_debuglink`.
+/// The debug tree is the one below `root`; a build-id names the same file
+/// anywhere, so the daemon's own tree is tried after it.
+fn find_debug_file(path: &Path, file: &object::File, root: Option<&Path>) -> Option<PathBuf> {
+    let debug_root = join_root(root, DEBUG_ROOT);
+    if let Ok(Some(build_id)) = file.build_id() {
+        let candidate = [debug_root.as_path(), Path::new(DEBUG_ROOT)]
+            .into_iter()
+            .filter_map(|dir| build_id_debug_path(dir, build_id))
+            .find(|candidate| candidate.is_file());
+        if candidate.is_some() {
+            return candidate;
+        }
+    }
+
+    let (link, _crc) = file.gnu_debuglink().ok()??;
+    let link = std::str::from_utf8(link).ok()?;
+    let dir = path.parent()?;
+    // The directory as the process names it, for the mirror in the debug tree.
+    let own_dir = root
+        .and_then(|root| dir.strip_prefix(root).ok())
+        .unwrap_or(dir);
+    let candidates = [
+        dir.join(link),
+        dir.join(".debug").join(link),
+        debug_root
+            .join(own_dir.strip_prefix("/").unwrap_or(own_dir))
+            .join(link),
+    ];
+    candidates
+        .into_iter()
+        .find(|candidate| candidate != path && candidate.is_file())
+}
+
+/// `<root>/.build-id/ab/cdef0123....debug`
+pub fn build_id_debug_path(root: &Path, build_id: &[u8]) -> Option<PathBuf> {
+    let (first, rest) = build_id.split_first()?;
+    let rest = build_id_hex(rest);
+    Some(
+        root.join(".build-id")
+            .join(format!("{first:02x}"))
+            .join(format!("{rest}.debug")),
+    )
+}
@@ -0,0 +1,220 @@
+/// Go's own function table, `.gopclntab`. The runtime needs it for
+/// tracebacks, so it survives `go build -ldflags='-s -w'`, which strips the
+/// symbol table and DWARF. Read the way `runtime.findfunc` and
+/// `runtime.pcvalue` do; the layouts of Go 1.16 and later are supported.
+pub struct GoPclnTab {
+    data: Vec<u8>,
+    layout: Layout,
+    /// Instruction size unit of pc deltas; 1 on x86.
+    quantum: u64,
+    ptr_size: usize,
+    nfunc: usize,
+    /// Function entries are offsets from here since Go 1.18.
+    text_start: u64,
+    funcname_offset: usize,
+    cu_offset: usize,
+    filetab_offset: usize,
+    pctab_offset: usize,
+    functab_offset: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    /// Go 1.16 and 1.17: absolute, pointer-sized entry addresses.
+    Go116,
+    /// Go 1.18 onwards: 32-bit entry offsets from `text_start`.
+    Go118,
+}
+
+const MAGIC_GO116: u32 = 0xfffffffa;
+const MAGIC_GO118: u32 = 0xfffffff0;
+const MAGIC_GO120: u32 = 0xfffffff1;
+
+/// Where an address falls in the Go program.
+pub struct GoFunction<'a> {
+    /// `main.(*Server).handle`
+    pub name: &'a str,
+    pub entry: u64,
+    pub file: Option<&'a str>,
+    pub line: Option<u32>,
+}
+
+impl GoPclnTab {
+    /// Parse the contents of `.gopclntab`. `text_address` is that of `.text`,
+    /// standing in for the header's text start when it is left to a dynamic
+    /// relocation (PIE).
+    pub fn parse(data: Vec<u8>, text_address: Option<u64>) -> Option<Self> {
+        let layout = match u32::from_le_bytes(data.get(..4)?.try_into().ok()?) {
+            MAGIC_GO116 => Layout::Go116,
+            MAGIC_GO118 | MAGIC_GO120 => Layout::Go118,
+            magic => {
+                log::debug!("unsupported .gopclntab magic 0x{magic:x}");
+                return None;
+            }
+        };
+        let quantum = *data.get(6)? as u64;
+        let ptr_size = *data.get(7)? as usize;
+        if !matches!(ptr_size, 4 | 8) {
+            return None;
+        }
+
+        let word = |idx: usize| read_uint(&data, 8 + idx * ptr_size, ptr_size);
+        let (text_start, offsets) = match layout {
+            Layout::Go116 => (0, 2),
+            Layout::Go118 => (word(2)?, 3),
+        };
+        let word = |idx: usize| word(offsets + idx).map(|w| w as usize);
+
+        Some(Self {
+            layout,
+            quantum,
+            ptr_size,
+            nfunc: read_uint(&data, 8, ptr_size)? as usize,
+            text_start: match text_start {
+                0 => text_address.unwrap_or(0),
+                start => start,
+            },
+            funcname_offset: word(0)?,
+            cu_offset: word(1)?,
+            filetab_offset: word(2)?,
+            pctab_offset: word(3)?,
+            functab_offset: word(4)?,
+            data,
+        })
+    }
+
+    /// The function containing `address`, with the file and line of the
+    /// instruction there.
+    pub fn find(&self, address: u64) -> Option<GoFunction<'_>> {
+        // Entry `nfunc` is the end of the last function.
+        let (mut lo, mut hi) = (0, self.nfunc);
+        if address < self.entry(0)? || address >= self.entry(self.nfunc)? {
+            return None;
+        }
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if self.entry(mid)? <= address {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        let entry = self.entry(lo)?;
+        let func = self.functab_offset + self.func_offset(lo)?;
+        // `_func` fields after the entry are the same in every layout.
+        let fields = func
+            + match self.layout {
+                Layout::Go116 => self.ptr_size,
+                Layout::Go118 => 4,
+            };
+        let name_offset = self.u32(fields)? as usize;
+        let pcfile = self.u32(fields + 16)?;
+        let pcln = self.u32(fields + 20)?;
+        let cu_index = self.u32(fields + 28)? as usize;
+
+        let file = self
+            .pcvalue(pcfile, entry, address)
+            .and_then(|file_index| usize::try_from(file_index).ok())
+            .and_then(|file_index| self.u32(self.cu_offset + (cu_index + file_index) * 4))
+            .filter(|file_offset| *file_offset != u32::MAX)
+            .and_then(|file_offset| self.c_str(self.filetab_offset + file_offset as usize));
+        let line = self
+            .pcvalue(pcln, entry, address)
+            .and_then(|line| u32::try_from(line).ok());
+
+        Some(GoFunction {
+            name: self.c_str(self.funcname_offset + name_offset)?,
+            entry,
+            file,
+            line,
+        })
+    }
+
+    fn entry(&self, idx: usize) -> Option<u64> {
+        match self.layout {
+            Layout::Go116 => {
+                let at = self.functab_offset + idx * 2 * self.ptr_size;
+                read_uint(&self.data, at, self.ptr_size)
+            }
+            Layout::Go118 => {
+                let offset = self.u32(self.functab_offset + idx * 8)?;
+                Some(self.text_start + offset as u64)
+            }
+        }
+    }
+
+    fn func_offset(&self, idx: usize) -> Option<usize> {
+        let offset = match self.layout {
+            Layout::Go116 => {
+                let at = self.functab_offset + idx * 2 * self.ptr_size + self.ptr_size;
+                read_uint(&self.data, at, self.ptr_size)?
+            }
+            Layout::Go118 => self.u32(self.functab_offset + idx * 8 + 4)? as u64,
+        };
+        Some(offset as usize)
+    }
+
+    /// Value of the pc-value table at `table` (offset into the pctab) for
+    /// `target`, in the function starting at `entry`.
+    fn pcvalue(&self, table: u32, entry: u64, target: u64) -> Option<i32> {
+        if table == 0 {
+            return None;
+        }
+        let mut data = self.data.get(self.pctab_offset + table as usize..)?;
+        let mut pc = entry;
+        let mut value: i32 = -1;
+        let mut first = true;
+        loop {
+            let value_delta = read_uvarint(&mut data)?;
+            if value_delta == 0 && !first {
+                return None;
+            }
+            first = false;
+            // Zig-zag encoded.
+            let value_delta = if value_delta & 1 != 0 {
+                !(value_delta >> 1)
+            } else {
+                value_delta >> 1
+            };
+            value = value.wrapping_add(value_delta as i32);
+            pc += read_uvarint(&mut data)? as u64 * self.quantum;
+            if target < pc {
+                return Some(value);
+            }
+        }
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        read_uint(&self.data, at, 4).map(|v| v as u32)
+    }
+
+    fn c_str(&self, at: usize) -> Option<&str> {
+        let bytes = self.data.get(at..)?;
+        let end = bytes.iter().position(|b| *b == 0)?;
+        std::str::from_utf8(&bytes[..end]).ok()
+    }
+}
+
+/// Little-endian `size`-byte integer at `at`, for `size` 4 or 8.
+pub fn read_uint(data: &[u8], at: usize, size: usize) -> Option<u64> {
+    let bytes = data.get(at..at.checked_add(size)?)?;
+    Some(match size {
+        4 => u32::from_le_bytes(bytes.try_into().ok()?) as u64,
+        8 => u64::from_le_bytes(bytes.try_into().ok()?),
+        _ => return None,
+    })
+}
+
+fn read_uvarint(data: &mut &[u8]) -> Option<u32> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let (byte, rest) = data.split_first()?;
+        *data = rest;
+        value |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::symbolize::SourceLocation;
+
+/// C# context for code in `libil2cpp.so`, which IL2CPP generates from the
+/// game's assemblies. Loaded from the symbol store:
+///
+/// - `LineNumberMappings.json`, written by Unity next to the generated C++,
+///   maps lines of each `.cpp` file back to the C# source:
+///
+///   ```text
+///   { "<cpp file>": { "<cs file>": { "<cpp line>": <cs line>, ... } } }
+///   ```
+///
+/// - `script.json`, derived from `global-metadata.dat` (e.g. by Il2CppDumper),
+///   lists the address of every C# method, for stripped builds without DWARF:
+///
+///   ```text
+///   { "ScriptMethod": [{ "Address": 1234560, "Name": "Player$$Update" }] }
+///   ```
+pub struct Il2CppSymbols {
+    /// Sorted by address.
+    methods: Vec<(u64, String)>,
+    /// Keyed by `.cpp` file name; paths differ between build machine and DWARF.
+    lines: HashMap<String, CppLineMap>,
+}
+
+/// C# location of each mapped line of one `.cpp` file.
+struct CppLineMap {
+    cs_files: Vec<String>,
+    /// C++ line to index into `cs_files` and C# line.
+    lines: HashMap<u32, (usize, u32)>,
+}
+
+#[derive(Deserialize)]
+struct ScriptJson {
+    #[serde(rename = "ScriptMethod", default)]
+    methods: Vec<ScriptMethod>,
+}
+
+#[derive(Deserialize)]
+struct ScriptMethod {
+    #[serde(rename = "Address")]
+    address: u64,
+    #[serde(rename = "Name")]
+    name: String,
+}
+
+type LineNumberMappings = HashMap<String, HashMap<String, HashMap<String, u32>>>;
+
+impl Il2CppSymbols {
+    /// Load whichever of the two files are present; `None` if neither is.
+    pub fn load(line_mappings: Option<&Path>, script: Option<&Path>) -> Option<Self> {
+        if line_mappings.is_none() && script.is_none() {
+            return None;
+        }
+
+        let mut methods: Vec<(u64, String)> = script
+            .and_then(read_json::<ScriptJson>)
+            .map(|script| {
+                script
+                    .methods
+                    .into_iter()
+                    .map(|m| (m.address, m.name))
+                    .collect()
+            })
+            .unwrap_or_default();
+        methods.sort_by_key(|(address, _)| *address);
+
+        let lines = line_mappings
+            .and_then(read_json::<LineNumberMappings>)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(cpp_file, cs_files)| {
+                let mut map = CppLineMap {
+                    cs_files: Vec::new(),
+                    lines: HashMap::new(),
+                };
+                for (cs_file, lines) in cs_files {
+                    let idx = map.cs_files.len();
+                    map.cs_files.push(cs_file);
+                    for (cpp_line, cs_line) in lines {
+                        if let Ok(cpp_line) = cpp_line.parse() {
+                            map.lines.insert(cpp_line, (idx, cs_line));
+                        }
+                    }
+                }
+                (file_name(&cpp_file).to_string(), map)
+            })
+            .collect();
+
+        Some(Self { methods, lines })
+    }
+
+    /// The C# method containing `address`, unless `elf_symbol_start` (the
+    /// nearest ELF symbol) is closer, the offset into it, and whether
+    /// `address` is known to lie inside it. `script.json` has no sizes, so a
+    /// method ends where the next begins; the last one has no known end.
+    pub fn find_method(
+        &self,
+        address: u64,
+        elf_symbol_start: Option<u64>,
+    ) -> Option<(&str, u64, bool)> {
+        let idx = self.methods.partition_point(|(start, _)| *start <= address);
+        let (start, name) = self.methods.get(idx.checked_sub(1)?)?;
+        if elf_symbol_start.is_some_and(|elf| elf > *start) {
+            return None;
+        }
+        // `address` is below the start of `methods[idx]`, if there is one.
+        let bounded = idx < self.methods.len();
+        Some((name.as_str(), address - start, bounded))
+    }
+
+    /// Point locations in generated C++ at the C# line they came from.
+    pub fn map_lines(&self, locations: &mut [SourceLocation]) {
+        for loc in locations {
+            let (Some(file), Some(line)) = (&loc.file, loc.line) else {
+                continue;
+            };
+            let Some(map) = self.lines.get(file_name(file)) else {
+                continue;
+            };
+            // Only lines generated from a C# statement are mapped; the rest
+            // is IL2CPP boilerplate and keeps its C++ location.
+            if let Some((idx, cs_line)) = map.lines.get(&line) {
+                loc.file = Some(map.cs_files[*idx].clone());
+                loc.line = Some(*cs_line);
+            }
+        }
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| log::debug!("cannot open {}: {e}", path.display()))
+        .ok()?;
+    serde_json::from_reader(std::io::BufReader::new(file))
+        .map_err(|e| log::warn!("cannot parse {}: {e}", path.display()))
+        .ok()
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
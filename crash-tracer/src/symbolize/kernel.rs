@@ -0,0 +1,180 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+};
+
+use anyhow::Context;
+
+const KALLSYMS: &str = "/proc/kallsyms";
+const MODULES: &str = "/proc/modules";
+
+/// A kernel instruction pointer resolved against `/proc/kallsyms`.
+///
+/// Resolution has to happen at capture time: kernel addresses are randomized
+/// per boot (KASLR) and modules come and go, so a stored raw address cannot be
+/// symbolized later.
+pub struct KernelFrame {
+    pub ip: u64,
+    pub symbol: Option<String>,
+    pub offset: u64,
+    /// Loadable module the address belongs to; `None` for the core kernel.
+    pub module: Option<String>,
+}
+
+impl Display for KernelFrame {
+    /// `function+0xoff [module]`, `module+0xoff` or `??` as resolution degrades.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.symbol, &self.module) {
+            (Some(symbol), Some(module)) => write!(f, "{symbol}+0x{:x} [{module}]", self.offset),
+            (Some(symbol), None) => write!(f, "{symbol}+0x{:x}", self.offset),
+            (None, Some(module)) => write!(f, "{module}+0x{:x}", self.offset),
+            (None, None) => write!(f, "??"),
+        }
+    }
+}
+
+struct KernelModule {
+    name: String,
+    start: u64,
+    size: u64,
+}
+
+struct KernelSymbols {
+    /// (address, name, module) sorted by address.
+    symbols: Vec<(u64, String, Option<String>)>,
+    modules: Vec<KernelModule>,
+    /// Raw `/proc/modules` contents the tables were built from.
+    modules_snapshot: String,
+}
+
+/// Resolves kernel stack frames. The symbol table is loaded on first use and
+/// reloaded whenever the set of loaded modules changes.
+pub struct KernelSymbolizer {
+    symbols: Option<KernelSymbols>,
+}
+
+impl KernelSymbolizer {
+    pub fn new() -> Self {
+        Self { symbols: None }
+    }
+
+    pub fn symbolize(&mut self, ips: impl IntoIterator<Item = u64>) -> Vec<KernelFrame> {
+        let symbols = match self.refresh() {
+            Ok(symbols) => Some(symbols),
+            Err(e) => {
+                log::warn!("Kernel stack will not be symbolized: {e:#}");
+                None
+            }
+        };
+
+        ips.into_iter()
+            .take_while(|ip| *ip != 0)
+            .map(|ip| match symbols {
+                Some(symbols) => symbols.resolve(ip),
+                None => KernelFrame {
+                    ip,
+                    symbol: None,
+                    offset: 0,
+                    module: None,
+                },
+            })
+            .collect()
+    }
+
+    fn refresh(&mut self) -> anyhow::Result<&KernelSymbols> {
+        // Not every kernel has loadable module support; treat that as "no modules".
+        let modules_snapshot = fs::read_to_string(MODULES).unwrap_or_default();
+        let stale = self
+            .symbols
+            .as_ref()
+            .is_none_or(|s| s.modules_snapshot != modules_snapshot);
+        if stale {
+            self.symbols = Some(KernelSymbols::load(modules_snapshot)?);
+        }
+        self.symbols
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("kernel symbols not loaded"))
+    }
+}
+
+impl KernelSymbols {
+    fn load(modules_snapshot: String) -> anyhow::Result<Self> {
+        let kallsyms =
+            fs::read_to_string(KALLSYMS).with_context(|| format!("reading {KALLSYMS}"))?;
+
+        // `ffffffffc0a01000 t nvme_probe\t[nvme]`
+        let mut symbols: Vec<(u64, String, Option<String>)> = kallsyms
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let addr = u64::from_str_radix(fields.next()?, 16).ok()?;
+                let kind = fields.next()?;
+                let name = fields.next()?;
+                let module = fields
+                    .next()
+                    .map(|m| m.trim_start_matches('[').trim_end_matches(']').to_string());
+                // Only text symbols can appear in a stack trace.
+                matches!(kind, "t" | "T" | "w" | "W").then(|| (addr, name.to_string(), module))
+            })
+            .collect();
+
+        if symbols.iter().all(|(addr, _, _)| *addr == 0) {
+            anyhow::bail!("{KALLSYMS} addresses are hidden (kptr_restrict); run as root");
+        }
+
+        symbols.sort_by_key(|(addr, _, _)| *addr);
+
+        // `nvme 57344 4 - Live 0xffffffffc0a00000`
+        let modules = modules_snapshot
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let start = fields.get(5)?.trim_start_matches("0x");
+                Some(KernelModule {
+                    name: fields.first()?.to_string(),
+                    start: u64::from_str_radix(start, 16).ok()?,
+                    size: fields.get(1)?.parse().ok()?,
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            symbols,
+            modules,
+            modules_snapshot,
+        })
+    }
+
+    fn resolve(&self, ip: u64) -> KernelFrame {
+        let module = self
+            .modules
+            .iter()
+            .find(|m| ip >= m.start && ip < m.start + m.size);
+
+        let idx = self.symbols.partition_point(|(addr, _, _)| *addr <= ip);
+        let symbol = idx
+            .checked_sub(1)
+            .and_then(|i| self.symbols.get(i))
+            // A module address must resolve to a symbol of that same module,
+            // otherwise we'd blame whatever happens to precede it in memory.
+            .filter(|(_, _, sym_module)| match module {
+                Some(m) => sym_module.as_deref() == Some(m.name.as_str()),
+                None => true,
+            });
+
+        match symbol {
+            Some((addr, name, sym_module)) => KernelFrame {
+                ip,
+                symbol: Some(name.clone()),
+                offset: ip - addr,
+                module: sym_module.clone(),
+            },
+            None => KernelFrame {
+                ip,
+                symbol: None,
+                offset: module.map_or(0, |m| ip - m.start),
+                module: module.map(|m| m.name.clone()),
+            },
+        }
+    }
+}
@@ -0,0 +1,81 @@
+/// A single parsed line of `/proc/<pid>/maps`.
+///
+/// `7f1c2a000000-7f1c2a022000 r-xp 00028000 08:01 1234  /usr/lib/libc.so.6`
+pub struct MapEntry {
+    pub start: u64,
+    pub end: u64,
+    pub perms: String,
+    pub offset: u64,
+    pub dev: String,
+    pub inode: u64,
+    pub path: Option<String>,
+}
+
+impl MapEntry {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let (start, end) = fields.next()?.split_once('-')?;
+        let perms = fields.next()?.to_string();
+        let offset = fields.next()?;
+        let dev = fields.next()?.to_string();
+        let inode = fields.next()?.parse().ok()?;
+        // Paths may contain spaces, so take the remainder of the line as-is.
+        let path = fields.collect::<Vec<_>>().join(" ").trim().to_string();
+
+        Some(Self {
+            start: u64::from_str_radix(start, 16).ok()?,
+            end: u64::from_str_radix(end, 16).ok()?,
+            perms,
+            offset: u64::from_str_radix(offset, 16).ok()?,
+            dev,
+            inode,
+            path: (!path.is_empty()).then_some(path),
+        })
+    }
+
+    pub fn contains(&self, addr: u64) -> bool {
+        addr >= self.start && addr < self.end
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.perms.as_bytes().get(2) == Some(&b'x')
+    }
+
+    /// Returns the backing file path for mappings that come from a regular file
+    /// that still exists on disk (not `[stack]`, `[vdso]`, anonymous or deleted).
+    pub fn file_path(&self) -> Option<&str> {
+        let path = self.path.as_deref()?;
+        if self.inode == 0 || !path.starts_with('/') || path.ends_with(" (deleted)") {
+            return None;
+        }
+        Some(path)
+    }
+
+    /// Short name of the mapped module, e.g. `libc.so.6` or `[vdso]`.
+    pub fn module_name(&self) -> Option<&str> {
+        let path = self.path.as_deref()?;
+        Some(path.rsplit('/').next().unwrap_or(path))
+    }
+}
+
+/// The entry as a `/proc/<pid>/maps` line.
+impl std::fmt::Display for MapEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:08x}-{:08x} {} {:08x} {} {}",
+            self.start, self.end, self.perms, self.offset, self.dev, self.inode
+        )?;
+        match &self.path {
+            Some(path) => write!(f, " {path}"),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn parse_maps(lines: &[String]) -> Vec<MapEntry> {
+    lines
+        .iter()
+        .filter_map(|line| MapEntry::parse(line))
+        .collect()
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    db::CrashReportData,
+    runtime::{
+        dotnet::CrashReport,
+        rust::{PanicLocation, location_from_stack},
+    },
+    state::{map::ModuleInfo, namespace::join_root},
+    symbolize::{
+        elf::{ElfModule, read_build_id},
+        il2cpp::Il2CppSymbols,
+        maps::{MapEntry, parse_maps},
+        perf_map::PerfMap,
+        store::SymbolStore,
+        unwind::{CfiLookup, FrameRule, RegisterSet, StackMemory},
+    },
+};
+
+pub mod elf;
+pub mod gopclntab;
+pub mod il2cpp;
+pub mod kernel;
+pub mod maps;
+pub mod perf_map;
+pub mod store;
+pub mod unwind;
+
+const MAX_CACHED_MODULES: usize = 256;
+
+/// One source-level frame for an address. Several of these are produced for a
+/// single instruction pointer when functions were inlined into each other.
+pub struct SourceLocation {
+    pub function: Option<String>,
+    pub function_offset: Option<u64>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub inlined: bool,
+}
+
+/// An instruction pointer resolved against the process memory maps.
+pub struct SymbolizedFrame {
+    pub ip: u64,
+    /// Short module name, e.g. `libc.so.6`.
+    pub module: Option<String>,
+    /// Offset of `ip` into the module's file.
+    pub module_offset: Option<u64>,
+    /// Innermost inlined function first, physical function last.
+    pub locations: Vec<SourceLocation>,
+}
+
+impl SymbolizedFrame {
+    fn unresolved(ip: u64) -> Self {
+        Self {
+            ip,
+            module: None,
+            module_offset: None,
+            locations: Vec::new(),
+        }
+    }
+}
+
+impl Display for SymbolizedFrame {
+    /// `module!function+0xoff`, degrading to `module+0xoff` or the raw address.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(module) = &self.module else {
+            return write!(f, "0x{:x}", self.ip);
+        };
+        let physical = self.locations.iter().find(|loc| !loc.inlined);
+        match physical.and_then(|loc| Some((loc.function.as_ref()?, loc.function_offset))) {
+            Some((function, Some(offset))) => write!(f, "{module}!{function}+0x{offset:x}"),
+            Some((function, None)) => write!(f, "{module}!{function}"),
+            None => write!(f, "{module}+0x{:x}", self.module_offset.unwrap_or(0)),
+        }
+    }
+}
+
+/// The user stacks of one crash, ready to render.
+pub struct SymbolizedStacks {
+    /// From the BPF stack map (frame-pointer walk).
+    pub user: Vec<SymbolizedFrame>,
+    /// Recovered from the stack dump with DWARF CFI.
+    pub unwound: Vec<SymbolizedFrame>,
+    /// Other threads, in the order of `CrashReportData::threads`.
+    pub threads: Vec<SymbolizedThread>,
+    /// Where a Rust binary that aborted panicked, read from the executable
+    /// along with the symbols.
+    pub panic_location: Option<PanicLocation>,
+}
+
+/// The user stacks of one non-faulting thread.
+pub struct SymbolizedThread {
+    pub user: Vec<SymbolizedFrame>,
+    pub unwound: Vec<SymbolizedFrame>,
+}
+
+/// Resolves user-space instruction pointers to `module!function+offset` and,
+/// where DWARF is available, to `file:line` with inlined frames.
+///
+/// Parsed ELF files are cached by path and build-id, so the same libc is only
+/// loaded once across many crashes.
+pub struct Symbolizer {
+    store: Option<SymbolStore>,
+    modules: HashMap<(PathBuf, Option<String>), Option<ElfModule>>,
+    /// By build-id.
+    il2cpp: HashMap<String, Option<Il2CppSymbols>>,
+}
+
+impl Symbolizer {
+    pub fn new(store: Option<SymbolStore>) -> Self {
+        Self {
+            store,
+            modules: HashMap::new(),
+            il2cpp: HashMap::new(),
+        }
+    }
+
+    /// Symbolize a user stack captured from a process whose `/proc/<pid>/maps`
+    /// lines are `maps`. `modules` carries the build-ids recorded at exec time.
+    /// `root` is where the process' files are found when it runs in another
+    /// mount namespace.
+    pub fn symbolize(
+        &mut self,
+        frames: &[u64],
+        maps: &[String],
+        modules: &[ModuleInfo],
+        root: Option<&Path>,
+    ) -> Vec<SymbolizedFrame> {
+        let entries = parse_maps(maps);
+        let build_ids = build_id_index(modules);
+        frames
+            .iter()
+            .take_while(|ip| **ip != 0)
+            .enumerate()
+            .map(|(idx, ip)| self.symbolize_ip(*ip, idx > 0, &entries, &build_ids, root))
+            .collect()
+    }
+
+    /// Symbolize every stack of a crash. Frames in JIT code are named from the
+    /// process' perf map when one was collected.
+    pub fn symbolize_report(
+        &mut self,
+        data: &CrashReportData,
+        root: Option<&Path>,
+    ) -> SymbolizedStacks {
+        let (maps, modules) = (&data.memory_maps, &data.modules);
+        let mut stacks = SymbolizedStacks {
+            user: self.symbolize(&data.stack_frames, maps, modules, root),
+            unwound: self.symbolize(&data.unwound_frames, maps, modules, root),
+            threads: data
+                .threads
+                .iter()
+                .map(|thread| SymbolizedThread {
+                    user: self.symbolize(&thread.stack_frames, maps, modules, root),
+                    unwound: self.symbolize(&thread.unwound_frames, maps, modules, root),
+                })
+                .collect(),
+            panic_location: location_from_stack(data, root),
+        };
+
+        // Named after the pid the process knows itself by.
+        let pid = data.ns_pid.unwrap_or(data.pid);
+        if let Some(perf_map) = PerfMap::from_artifacts(&data.artifacts, pid) {
+            perf_map.resolve(&mut stacks.user);
+            perf_map.resolve(&mut stacks.unwound);
+            for thread in &mut stacks.threads {
+                perf_map.resolve(&mut thread.user);
+                perf_map.resolve(&mut thread.unwound);
+            }
+        }
+        if let Some(report) = CrashReport::from_artifacts(&data.artifacts) {
+            report.resolve(&mut stacks.user);
+            report.resolve(&mut stacks.unwound);
+            for thread in &mut stacks.threads {
+                report.resolve(&mut thread.user);
+                report.resolve(&mut thread.unwound);
+            }
+        }
+        stacks
+    }
+
+    /// Recover the call stack from the crash-time registers and stack copy
+    /// using `.eh_frame`/`.debug_frame`, for code built without frame pointers.
+    pub fn unwind(
+        &mut self,
+        regs: RegisterSet,
+        stack: &StackMemory,
+        maps: &[String],
+        modules: &[ModuleInfo],
+        root: Option<&Path>,
+    ) -> Vec<u64> {
+        let mut lookup = ProcessCfi {
+            symbolizer: self,
+            maps: parse_maps(maps),
+            build_ids: build_id_index(modules),
+            root,
+        };
+        unwind::unwind(&mut lookup, regs, stack)
+    }
+
+    fn symbolize_ip(
+        &mut self,
+        ip: u64,
+        is_return_address: bool,
+        maps: &[MapEntry],
+        build_ids: &HashMap<&str, &str>,
+        root: Option<&Path>,
+    ) -> SymbolizedFrame {
+        let Some(entry) = maps.iter().find(|m| m.contains(ip)) else {
+            return SymbolizedFrame::unresolved(ip);
+        };
+
+        let module_offset = ip - entry.start + entry.offset;
+        let mut frame = SymbolizedFrame {
+            ip,
+            module: entry.module_name().map(str::to_string),
+            module_offset: Some(module_offset),
+            locations: Vec::new(),
+        };
+
+        let Some(path) = entry.file_path() else {
+            return frame;
+        };
+        let build_id = build_ids.get(path).copied();
+        let Some(module) = self.module(path, build_id, root) else {
+            return frame;
+        };
+        let Some(address) = module.file_offset_to_address(module_offset) else {
+            return frame;
+        };
+
+        // Return addresses point at the instruction after the call; step back
+        // into the call so line info and inlining reflect the call site.
+        let probe = if is_return_address {
+            address.saturating_sub(1)
+        } else {
+            address
+        };
+
+        frame.locations = module.find_locations(probe);
+        let symbol = module.find_symbol(probe);
+        let symbol_start = symbol.map(|(_, offset)| probe - offset);
+        if let Some((name, offset)) = symbol {
+            set_physical_function(
+                &mut frame.locations,
+                name,
+                offset + (address - probe),
+                false,
+            );
+        }
+
+        // IL2CPP code is named after the C# it was generated from.
+        if let Some(il2cpp) = build_id.and_then(|id| self.il2cpp(id)) {
+            il2cpp.map_lines(&mut frame.locations);
+            if let Some((name, offset, bounded)) = il2cpp.find_method(probe, symbol_start) {
+                // Past the last method, the ELF name (if any) is as likely right.
+                let offset = offset + (address - probe);
+                set_physical_function(&mut frame.locations, name, offset, bounded);
+            }
+        }
+
+        frame
+    }
+
+    fn module(
+        &mut self,
+        path: &str,
+        build_id: Option<&str>,
+        root: Option<&Path>,
+    ) -> Option<&ElfModule> {
+        let path = join_root(root, path);
+        let key = (path.clone(), build_id.map(str::to_string));
+        if !self.modules.contains_key(&key) && self.modules.len() >= MAX_CACHED_MODULES {
+            self.modules.clear();
+        }
+        let store = self.store.as_ref();
+        self.modules
+            .entry(key)
+            .or_insert_with(|| {
+                let (binary, debug_file) = locate(store, &path, build_id)?;
+                ElfModule::load(&binary, debug_file.as_deref(), root)
+                    .map_err(|e| log::debug!("failed to load {}: {e:#}", binary.display()))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// IL2CPP symbol files registered in the store for `build_id`.
+    fn il2cpp(&mut self, build_id: &str) -> Option<&Il2CppSymbols> {
+        let store = self.store.as_ref()?;
+        if !self.il2cpp.contains_key(build_id) && self.il2cpp.len() >= MAX_CACHED_MODULES {
+            self.il2cpp.clear();
+        }
+        self.il2cpp
+            .entry(build_id.to_string())
+            .or_insert_with(|| {
+                Il2CppSymbols::load(
+                    store.il2cpp_line_mappings(build_id).as_deref(),
+                    store.il2cpp_script(build_id).as_deref(),
+                )
+            })
+            .as_ref()
+    }
+}
+
+/// Attach `name` and `offset` to the non-inlined location, adding one if DWARF
+/// had none. An existing name is kept unless `replace`.
+fn set_physical_function(
+    locations: &mut Vec<SourceLocation>,
+    name: &str,
+    offset: u64,
+    replace: bool,
+) {
+    match locations.iter_mut().find(|loc| !loc.inlined) {
+        Some(physical) => {
+            physical.function_offset = Some(offset);
+            if replace || physical.function.is_none() {
+                physical.function = Some(name.to_string());
+            }
+        }
+        None => locations.push(SourceLocation {
+            function: Some(name.to_string()),
+            function_offset: Some(offset),
+            file: None,
+            line: None,
+            inlined: false,
+        }),
+    }
+}
+
+fn build_id_index(modules: &[ModuleInfo]) -> HashMap<&str, &str> {
+    modules
+        .iter()
+        .filter_map(|m| Some((m.path.as_str(), m.build_id.as_deref()?)))
+        .collect()
+}
+
+/// CFI lookup across all modules mapped into one process.
+struct ProcessCfi<'a> {
+    symbolizer: &'a mut Symbolizer,
+    maps: Vec<MapEntry>,
+    build_ids: HashMap<&'a str, &'a str>,
+    root: Option<&'a Path>,
+}
+
+impl CfiLookup for ProcessCfi<'_> {
+    fn frame_rule(&mut self, pc: u64) -> Option<FrameRule> {
+        let entry = self.maps.iter().find(|m| m.contains(pc))?;
+        let path = entry.file_path()?;
+        let build_id = self.build_ids.get(path).copied();
+        let module = self.symbolizer.module(path, build_id, self.root)?;
+        let address = module.file_offset_to_address(pc - entry.start + entry.offset)?;
+        module.cfi().find(address)
+    }
+}
+
+/// Pick the binary and debug file to symbolize a module with.
+///
+/// The symbol store wins when it has an entry for the recorded build-id. The
+/// file on disk is only used if its build-id still matches: after a redeploy it
+/// describes a different binary and would produce confidently wrong symbols.
+/// A store holding only the debug info then stands in for the binary; it keeps
+/// the program headers and symbols.
+fn locate(
+    store: Option<&SymbolStore>,
+    path: &Path,
+    build_id: Option<&str>,
+) -> Option<(PathBuf, Option<PathBuf>)> {
+    let Some(build_id) = build_id else {
+        return Some((path.to_path_buf(), None));
+    };
+
+    let debuginfo = store.and_then(|s| s.debuginfo(build_id));
+    if let Some(executable) = store.and_then(|s| s.executable(build_id)) {
+        return Some((executable, debuginfo));
+    }
+
+    match read_build_id(path) {
+        Some(on_disk) if on_disk.eq_ignore_ascii_case(build_id) => {
+            Some((path.to_path_buf(), debuginfo))
+        }
+        _ => match debuginfo {
+            Some(debuginfo) => {
+                log::debug!(
+                    "{} no longer matches build-id {build_id}, using {}",
+                    path.display(),
+                    debuginfo.display()
+                );
+                Some((debuginfo, None))
+            }
+            None => {
+                log::debug!(
+                    "{} no longer matches build-id {build_id} and is not in the symbol store",
+                    path.display()
+                );
+                None
+            }
+        },
+    }
+}
@@ -0,0 +1,124 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use log::warn;
+
+use crate::db::ArtifactData;
+use crate::symbolize::{SourceLocation, SymbolizedFrame};
+
+/// Module name shown for frames resolved through a perf map.
+const JIT_MODULE: &str = "[jit]";
+
+/// A `/tmp/perf-<pid>.map` file, in which JIT compilers (V8 with
+/// `--perf-basic-prof`, .NET with `DOTNET_PerfMapEnabled`, ...) list the code
+/// they generate:
+///
+/// ```text
+/// 3ef414b3a0a0 4c LazyCompile:~handler /app/server.js:12
+/// ```
+///
+/// Start and size are hex, with or without `0x`.
+pub struct PerfMap {
+    /// Sorted by start address.
+    entries: Vec<PerfMapEntry>,
+}
+
+struct PerfMapEntry {
+    start: u64,
+    size: u64,
+    name: String,
+}
+
+impl PerfMap {
+    pub fn parse(text: &str) -> Self {
+        let mut entries: Vec<PerfMapEntry> = text.lines().filter_map(parse_line).collect();
+        // Stable, so when a range was reused the most recent entry for a start
+        // address stays last.
+        entries.sort_by_key(|e| e.start);
+        Self { entries }
+    }
+
+    /// The perf map `pid` left among the crash's artifacts, if any.
+    pub fn from_artifacts(artifacts: &[ArtifactData], pid: u32) -> Option<Self> {
+        let filename = format!("perf-{pid}.map");
+        let mut stored = artifacts
+            .iter()
+            .filter(|a| a.filename == filename)
+            .peekable();
+        let path = stored.peek()?.full_path.clone();
+        let map = stored
+            .find_map(|a| a.content.as_deref())
+            .map(|content| Self::parse(&String::from_utf8_lossy(content)));
+        if map.is_none() {
+            warn!("perf map {path} was stored by path only, JIT frames stay unnamed");
+        }
+        map
+    }
+
+    /// The lines of the perf map `file` whose code contains one of `ips`
+    /// (sorted), read a line at a time. For maps too big to store whole.
+    pub fn filter_file(file: File, ips: &[u64]) -> std::io::Result<Vec<u8>> {
+        let mut kept = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let Some(entry) = parse_line(&line) else {
+                continue;
+            };
+            let idx = ips.partition_point(|ip| *ip < entry.start);
+            if ips
+                .get(idx)
+                .is_some_and(|ip| *ip < entry.start.saturating_add(entry.size))
+            {
+                kept.extend_from_slice(line.as_bytes());
+                kept.push(b'\n');
+            }
+        }
+        Ok(kept)
+    }
+
+    /// Name and offset of the JIT code containing `ip`.
+    pub fn lookup(&self, ip: u64) -> Option<(&str, u64)> {
+        let idx = self.entries.partition_point(|e| e.start <= ip);
+        self.entries[..idx]
+            .iter()
+            .rev()
+            .find(|e| ip < e.start.saturating_add(e.size))
+            .map(|e| (e.name.as_str(), ip - e.start))
+    }
+
+    /// Name frames no ELF module could account for.
+    pub fn resolve(&self, frames: &mut [SymbolizedFrame]) {
+        for frame in frames {
+            if frame.locations.iter().any(|loc| loc.function.is_some()) {
+                continue;
+            }
+            let Some((name, offset)) = self.lookup(frame.ip) else {
+                continue;
+            };
+            frame.module.get_or_insert_with(|| JIT_MODULE.to_string());
+            frame.locations = vec![SourceLocation {
+                function: Some(name.to_string()),
+                function_offset: Some(offset),
+                file: None,
+                line: None,
+                inlined: false,
+            }];
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Option<PerfMapEntry> {
+    let mut fields = line.splitn(3, ' ');
+    let start = parse_hex(fields.next()?)?;
+    let size = parse_hex(fields.next()?)?;
+    let name = fields.next()?.trim();
+    Some(PerfMapEntry {
+        start,
+        size,
+        name: name.to_string(),
+    })
+}
+
+fn parse_hex(s: &str) -> Option<u64> {
+    u64::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+/// A local symbol store using the debuginfod directory layout:
+///
+/// ```text
+/// <root>/<build-id>/debuginfo    separate debug info (or an unstripped binary)
+/// <root>/<build-id>/executable   the binary as it was deployed
+/// ```
+///
+/// For IL2CPP builds, the entry of `libil2cpp.so` may also hold
+/// `il2cpp/LineNumberMappings.json` and `il2cpp/script.json` (see
+/// [`Il2CppSymbols`](crate::symbolize::il2cpp::Il2CppSymbols)).
+///
+/// Populating the store is left to the build/deploy pipeline; crash-tracer only
+/// reads from it, so crashes stay symbolizable after the binary on disk is replaced.
+pub struct SymbolStore {
+    root: PathBuf,
+}
+
+impl SymbolStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn debuginfo(&self, build_id: &str) -> Option<PathBuf> {
+        self.lookup(build_id, "debuginfo")
+    }
+
+    pub fn executable(&self, build_id: &str) -> Option<PathBuf> {
+        self.lookup(build_id, "executable")
+    }
+
+    pub fn il2cpp_line_mappings(&self, build_id: &str) -> Option<PathBuf> {
+        self.lookup(build_id, "il2cpp/LineNumberMappings.json")
+    }
+
+    pub fn il2cpp_script(&self, build_id: &str) -> Option<PathBuf> {
+        self.lookup(build_id, "il2cpp/script.json")
+    }
+
+    fn lookup(&self, build_id: &str, kind: &str) -> Option<PathBuf> {
+        // Build-ids come from the database; refuse anything that could escape the root.
+        if build_id.is_empty() || !build_id.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let path = self.root.join(build_id.to_ascii_lowercase()).join(kind);
+        path.is_file().then_some(path)
+    }
+}
@@ -0,0 +1,262 @@
+use gimli::{
+    BaseAddresses, CfaRule, DebugFrame, EhFrame, LittleEndian, Register, RegisterRule,
+    UnwindContext, UnwindSection,
+};
+use object::{Object, ObjectSection};
+
+use crash_tracer_common::{SignalDeliverEvent, ThreadStateEvent};
+
+/// Upper bound on unwound frames; matches the depth of a BPF stack trace.
+const MAX_FRAMES: usize = 127;
+
+// DWARF register numbers for x86_64 (System V psABI, figure 3.36).
+const RBX: usize = 3;
+const RBP: usize = 6;
+const RSP: usize = 7;
+const R12: usize = 12;
+const R13: usize = 13;
+const R14: usize = 14;
+const R15: usize = 15;
+const RA: usize = 16;
+const NUM_REGS: usize = 17;
+
+/// Registers that a callee must preserve; they carry over into the caller's
+/// frame when the CFI has no rule for them.
+const CALLEE_SAVED: [usize; 7] = [RBX, RBP, RSP, R12, R13, R14, R15];
+
+/// Register state of one frame, indexed by DWARF register number.
+#[derive(Clone)]
+pub struct RegisterSet([Option<u64>; NUM_REGS]);
+
+impl RegisterSet {
+    fn from_values(values: [u64; NUM_REGS]) -> Self {
+        Self(values.map(Some))
+    }
+
+    fn get(&self, reg: usize) -> Option<u64> {
+        self.0.get(reg).copied().flatten()
+    }
+}
+
+impl From<&SignalDeliverEvent> for RegisterSet {
+    fn from(e: &SignalDeliverEvent) -> Self {
+        Self::from_values([
+            e.rax, e.rdx, e.rcx, e.rbx, e.rsi, e.rdi, e.rbp, e.rsp, e.r8, e.r9, e.r10, e.r11,
+            e.r12, e.r13, e.r14, e.r15, e.rip,
+        ])
+    }
+}
+
+impl From<&ThreadStateEvent> for RegisterSet {
+    fn from(e: &ThreadStateEvent) -> Self {
+        Self::from_values([
+            e.rax, e.rdx, e.rcx, e.rbx, e.rsi, e.rdi, e.rbp, e.rsp, e.r8, e.r9, e.r10, e.r11,
+            e.r12, e.r13, e.r14, e.r15, e.rip,
+        ])
+    }
+}
+
+/// Copy of the user stack taken at crash time.
+pub struct StackMemory<'a> {
+    pub base: u64,
+    pub data: &'a [u8],
+}
+
+impl StackMemory<'_> {
+    fn read_u64(&self, addr: u64) -> Option<u64> {
+        let start = usize::try_from(addr.checked_sub(self.base)?).ok()?;
+        let bytes = self.data.get(start..start.checked_add(8)?)?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+/// How to recover a register in the caller, reduced to the rules we can
+/// evaluate without a DWARF expression machine.
+#[derive(Clone, Copy)]
+enum Rule {
+    Undefined,
+    SameValue,
+    Offset(i64),
+    ValOffset(i64),
+    Register(usize),
+}
+
+/// The part of an unwind table row the unwinder needs.
+pub struct FrameRule {
+    cfa_register: usize,
+    cfa_offset: i64,
+    rules: [Option<Rule>; NUM_REGS],
+}
+
+struct Section {
+    address: u64,
+    data: Vec<u8>,
+}
+
+/// `.eh_frame` / `.debug_frame` of one ELF file.
+pub struct CfiTables {
+    eh_frame: Option<Section>,
+    debug_frame: Option<Section>,
+    text_address: u64,
+}
+
+impl CfiTables {
+    /// Collect CFI from `file`, falling back to `debug_file` for `.debug_frame`,
+    /// which distros usually strip into the separate debug package.
+    pub fn load(file: &object::File, debug_file: Option<&object::File>) -> Self {
+        let section = |file: &object::File, name: &str| {
+            let section = file.section_by_name(name)?;
+            Some(Section {
+                address: section.address(),
+                data: section.uncompressed_data().ok()?.into_owned(),
+            })
+        };
+        Self {
+            eh_frame: section(file, ".eh_frame"),
+            debug_frame: section(file, ".debug_frame")
+                .or_else(|| debug_file.and_then(|f| section(f, ".debug_frame"))),
+            text_address: file.section_by_name(".text").map_or(0, |s| s.address()),
+        }
+    }
+
+    /// Unwind rule covering the link-time `address`, preferring `.eh_frame`.
+    pub fn find(&self, address: u64) -> Option<FrameRule> {
+        let mut ctx = UnwindContext::new();
+
+        if let Some(section) = &self.eh_frame {
+            let eh_frame = EhFrame::new(&section.data, LittleEndian);
+            let bases = BaseAddresses::default()
+                .set_eh_frame(section.address)
+                .set_text(self.text_address);
+            if let Ok(row) = eh_frame.unwind_info_for_address(
+                &bases,
+                &mut ctx,
+                address,
+                EhFrame::cie_from_offset,
+            ) {
+                return FrameRule::from_row(row);
+            }
+        }
+
+        if let Some(section) = &self.debug_frame {
+            let mut debug_frame = DebugFrame::new(&section.data, LittleEndian);
+            debug_frame.set_address_size(8);
+            let bases = BaseAddresses::default().set_text(self.text_address);
+            if let Ok(row) = debug_frame.unwind_info_for_address(
+                &bases,
+                &mut ctx,
+                address,
+                DebugFrame::cie_from_offset,
+            ) {
+                return FrameRule::from_row(row);
+            }
+        }
+
+        None
+    }
+}
+
+impl FrameRule {
+    fn from_row(row: &gimli::UnwindTableRow<usize>) -> Option<Self> {
+        let CfaRule::RegisterAndOffset { register, offset } = *row.cfa() else {
+            // CFA expressions only show up in PLT stubs and signal trampolines.
+            return None;
+        };
+
+        let mut rules = [None; NUM_REGS];
+        for (reg, rule) in rules.iter_mut().enumerate() {
+            *rule = match row.register(Register(reg as u16)) {
+                RegisterRule::Undefined if CALLEE_SAVED.contains(&reg) => None,
+                RegisterRule::Undefined => Some(Rule::Undefined),
+                RegisterRule::SameValue => Some(Rule::SameValue),
+                RegisterRule::Offset(n) => Some(Rule::Offset(n)),
+                RegisterRule::ValOffset(n) => Some(Rule::ValOffset(n)),
+                RegisterRule::Register(r) => Some(Rule::Register(r.0 as usize)),
+                _ => Some(Rule::Undefined),
+            };
+        }
+
+        Some(Self {
+            cfa_register: register.0 as usize,
+            cfa_offset: offset,
+            rules,
+        })
+    }
+}
+
+/// Where an unwinder step can look up the CFI for a runtime address.
+pub trait CfiLookup {
+    /// Unwind rule for the runtime address `pc` in the process being unwound.
+    fn frame_rule(&mut self, pc: u64) -> Option<FrameRule>;
+}
+
+/// Walk the stack from the crash-time register state using DWARF CFI, reading
+/// saved registers out of the captured stack copy.
+///
+/// Frames without CFI (JIT code, hand-written assembly) fall back to the
+/// frame-pointer chain. Unwinding stops at the first frame that cannot be
+/// recovered, that points outside the stack copy, or that does not move the
+/// stack pointer towards the stack base.
+pub fn unwind(lookup: &mut impl CfiLookup, regs: RegisterSet, stack: &StackMemory) -> Vec<u64> {
+    let mut frames = Vec::new();
+    let mut regs = regs;
+
+    while frames.len() < MAX_FRAMES {
+        let Some(pc) = regs.get(RA).filter(|pc| *pc != 0) else {
+            break;
+        };
+        frames.push(pc);
+
+        // Return addresses point past the call; look up the call instruction.
+        let probe = if frames.len() > 1 { pc - 1 } else { pc };
+        let caller = match lookup.frame_rule(probe) {
+            Some(rule) => step_cfi(&regs, &rule, stack),
+            None => step_frame_pointer(&regs, stack),
+        };
+        let Some(caller) = caller else {
+            break;
+        };
+
+        if caller.get(RSP) <= regs.get(RSP) {
+            break;
+        }
+        regs = caller;
+    }
+
+    frames
+}
+
+fn step_cfi(regs: &RegisterSet, rule: &FrameRule, stack: &StackMemory) -> Option<RegisterSet> {
+    let cfa = regs
+        .get(rule.cfa_register)?
+        .checked_add_signed(rule.cfa_offset)?;
+
+    let mut caller = RegisterSet([None; NUM_REGS]);
+    for (reg, value) in caller.0.iter_mut().enumerate() {
+        *value = match rule.rules[reg] {
+            None => CALLEE_SAVED.contains(&reg).then(|| regs.get(reg)).flatten(),
+            Some(Rule::Undefined) => None,
+            Some(Rule::SameValue) => regs.get(reg),
+            Some(Rule::Offset(n)) => stack.read_u64(cfa.checked_add_signed(n)?),
+            Some(Rule::ValOffset(n)) => cfa.checked_add_signed(n),
+            Some(Rule::Register(r)) => regs.get(r),
+        };
+    }
+    // By definition the caller's stack pointer is the CFA.
+    caller.0[RSP] = Some(cfa);
+
+    Some(caller)
+}
+
+/// `push rbp; mov rbp, rsp` prologue: [rbp] is the caller's rbp, [rbp+8] the return address.
+fn step_frame_pointer(regs: &RegisterSet, stack: &StackMemory) -> Option<RegisterSet> {
+    let rbp = regs.get(RBP)?;
+    let mut caller = RegisterSet([None; NUM_REGS]);
+    for reg in CALLEE_SAVED {
+        caller.0[reg] = regs.get(reg);
+    }
+    caller.0[RBP] = Some(stack.read_u64(rbp)?);
+    caller.0[RA] = Some(stack.read_u64(rbp.checked_add(8)?)?);
+    caller.0[RSP] = Some(rbp.checked_add(16)?);
+    Some(caller)
+}
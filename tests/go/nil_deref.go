@@ -0,0 +1,25 @@
+// SIGSEGV caught by the Go runtime - nil pointer dereference
+// Expected: signal=11 recorded once the process exits. The runtime handles
+// the SIGSEGV, prints a panic and the crashing goroutine to stderr and exits
+// with status 2 rather than re-raising it. Built with -ldflags='-s -w', so
+// frames resolve through .gopclntab only.
+package main
+
+import (
+	"fmt"
+	"os"
+)
+
+type config struct {
+	name string
+}
+
+func load(c *config) string {
+	return c.name
+}
+
+func main() {
+	fmt.Fprintln(os.Stderr, "[go/nil_deref] Dereferencing nil pointer...")
+	var c *config
+	fmt.Println(load(c))
+}
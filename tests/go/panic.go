@@ -0,0 +1,24 @@
+// Plain Go panic - NO signal expected.
+// The runtime prints the panic and the crashing goroutine to stderr and
+// exits with status 2. Recorded as an exit crash, with the Go Panic section.
+package main
+
+import (
+	"fmt"
+	"os"
+	"time"
+)
+
+func worker() {
+	time.Sleep(time.Minute)
+}
+
+func parse(items []string) string {
+	return items[5]
+}
+
+func main() {
+	fmt.Fprintln(os.Stderr, "[go/panic] Indexing out of range...")
+	go worker()
+	fmt.Println(parse([]string{"a", "b", "c"}))
+}
@@ -0,0 +1,19 @@
+// SIGSEGV inside a library loaded with dlopen() after startup
+// Expected: signal=11, with the top frame in libm.so.6 (frexp writing through NULL)
+#include <dlfcn.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+int main() {
+    void *libm = dlopen("libm.so.6", RTLD_NOW);
+    if (!libm) {
+        fprintf(stderr, "[native/dlopen_segfault] dlopen failed: %s\n", dlerror());
+        return 1;
+    }
+    double (*frexp_fn)(double, int *) = (double (*)(double, int *))dlsym(libm, "frexp");
+
+    fprintf(stderr, "[native/dlopen_segfault] Calling frexp() from libm with a NULL exponent...\n");
+    fflush(stderr);
+    frexp_fn(1.0, NULL);
+    return 0;
+}
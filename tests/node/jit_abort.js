@@ -0,0 +1,23 @@
+// process.abort() from a JIT-compiled function
+// Run with: node --perf-basic-prof tests/node/jit_abort.js
+// Expected: SIGABRT, and the user stack shows `[jit]!...hotPath...` frames
+// resolved from /tmp/perf-<pid>.map instead of bare addresses.
+
+function hotPath(n) {
+    let sum = 0;
+    for (let i = 0; i < n; i++) {
+        sum += i % 7;
+    }
+    if (n === -1) {
+        process.abort();
+    }
+    return sum;
+}
+
+// Warm up so V8 optimizes hotPath and lists it in the perf map.
+for (let i = 0; i < 20000; i++) {
+    hotPath(100);
+}
+
+console.error("[node/jit_abort] Calling process.abort() from optimized code...");
+hotPath(-1);
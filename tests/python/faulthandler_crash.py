@@ -6,11 +6,11 @@ SIGSEGV/SIGABRT/etc. On crash, it prints the Python stack trace to stderr
 BEFORE dying. This is the Python equivalent of JVM's hs_err_pid file.
 
 crash-tracer will see the SIGSEGV. The faulthandler output goes to stderr
-(not a file), so collecting it requires capturing the process's stderr.
+(not a file); crash-tracer captures what the process writes to stderr after
+the crash signal and shows the traceback in the "Python Stack" section.
 
 Compare the crash-tracer output between this and segfault_ctypes.py -
-the signal-level data is identical, but this one has a Python traceback
-on stderr that crash-tracer currently can't capture.
+the signal-level data is identical, but this one has a Python traceback.
 """
 import sys
 import faulthandler
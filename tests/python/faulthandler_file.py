@@ -0,0 +1,26 @@
+#!/usr/bin/env python3
+"""Segfault with faulthandler writing to a file - SIGSEGV expected.
+
+Same crash as faulthandler_crash.py, but the traceback goes to
+/tmp/crash-tracer/faulthandler.log instead of stderr. crash-tracer follows
+files named faulthandler*.log and attaches this one to the crash report as a
+runtime artifact.
+"""
+import os
+import sys
+import faulthandler
+import ctypes
+
+os.makedirs("/tmp/crash-tracer", exist_ok=True)
+log = open("/tmp/crash-tracer/faulthandler.log", "w")
+faulthandler.enable(file=log)
+
+print("[python/faulthandler_file] faulthandler -> /tmp/crash-tracer/faulthandler.log, crashing via ctypes...", file=sys.stderr)
+sys.stderr.flush()
+
+def process_data(data):
+    def inner_transform(buf):
+        ctypes.string_at(0, 1)
+    return inner_transform(data)
+
+process_data(b"hello")
@@ -7,16 +7,25 @@
 #   ./tests/run_all.sh native    Run only native tests
 #   ./tests/run_all.sh node      Run only Node.js tests
 #   ./tests/run_all.sh python    Run only Python tests
+#   ./tests/run_all.sh go        Run only Go tests
+#   ./tests/run_all.sh rust      Run only Rust tests
 #
 # Run crash-tracer in another terminal first:
 #   sudo ./target/debug/crash-tracer --verbose
 #
+# "no signal" cases exit with a non-zero status instead. They are recorded as
+# exit crashes when crash-tracer watches their commands:
+#   sudo ./target/debug/crash-tracer --verbose --exit-commands node,python3
+# Go panics ("exit" cases) are recorded as exit crashes either way.
+#
 # Then run this script and compare the crash-tracer output.
 
 set -euo pipefail
 
 SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
 BUILD_DIR="$SCRIPT_DIR/native/bin"
+GO_BUILD_DIR="$SCRIPT_DIR/go/bin"
+RUST_BUILD_DIR="$SCRIPT_DIR/rust/bin"
 DELAY=1  # seconds between tests so crash-tracer output is readable
 
 RED='\033[0;31m'
@@ -29,7 +38,7 @@ RESET='\033[0m'
 
 run_test() {
     local name="$1"
-    local expect="$2"  # "signal" or "no_signal"
+    local expect="$2"  # "signal", "exit" or "no_signal"
     local cmd="$3"
     shift 3
 
@@ -38,8 +47,10 @@ run_test() {
     echo -e "${CYAN}TEST: ${name}${RESET}"
     if [ "$expect" = "signal" ]; then
         echo -e "${RED}EXPECT: crash-tracer SHOULD capture this${RESET}"
+    elif [ "$expect" = "exit" ]; then
+        echo -e "${RED}EXPECT: crash-tracer SHOULD record this as an exit crash (no signal)${RESET}"
     else
-        echo -e "${GREEN}EXPECT: crash-tracer should NOT see this (no signal)${RESET}"
+        echo -e "${GREEN}EXPECT: crash-tracer should NOT see this (no signal), unless run with --exit-commands${RESET}"
     fi
     echo -e "${BOLD}━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━${RESET}"
     echo -e "${DIM}$ ${cmd} $*${RESET}"
@@ -77,6 +88,32 @@ build_native() {
     echo ""
 }
 
+build_go() {
+    echo -e "${BOLD}Building Go test programs (stripped)...${RESET}"
+    mkdir -p "$GO_BUILD_DIR"
+
+    for src in "$SCRIPT_DIR"/go/*.go; do
+        local name
+        name="$(basename "$src" .go)"
+        go build -ldflags='-s -w' -o "$GO_BUILD_DIR/$name" "$src"
+        echo "  Built: $name"
+    done
+    echo ""
+}
+
+build_rust() {
+    echo -e "${BOLD}Building Rust test programs (panic=abort)...${RESET}"
+    mkdir -p "$RUST_BUILD_DIR"
+
+    for src in "$SCRIPT_DIR"/rust/*.rs; do
+        local name
+        name="$(basename "$src" .rs)"
+        rustc -O -C panic=abort -o "$RUST_BUILD_DIR/$name" "$src"
+        echo "  Built: $name"
+    done
+    echo ""
+}
+
 run_native() {
     echo ""
     echo -e "${BOLD}╔══════════════════════════════════════════════════════════════╗${RESET}"
@@ -94,6 +131,7 @@ run_native() {
     run_test "native/stack_overflow (SIGSEGV - stack)"       signal "$BUILD_DIR/stack_overflow"
     run_test "native/bus_error (SIGBUS)"                     signal "$BUILD_DIR/bus_error"
     run_test "native/use_after_free (SIGSEGV - maybe)"       signal "$BUILD_DIR/use_after_free"
+    run_test "native/dlopen_segfault (SIGSEGV - in dlopen'd lib)" signal "$BUILD_DIR/dlopen_segfault"
 }
 
 run_node() {
@@ -111,6 +149,7 @@ run_node() {
     run_test "node/segfault_native (kill -SEGV self)"              signal    node "$SCRIPT_DIR/node/segfault_native.js"
     run_test "node/fatal_error (V8 OOM - SIGABRT)"                signal    node --max-old-space-size=50 "$SCRIPT_DIR/node/fatal_error.js"
     run_test "node/diagnostic_report (abort + report.json)"        signal    node --report-on-fatalerror --report-directory=/tmp/crash-tracer/ "$SCRIPT_DIR/node/diagnostic_report.js"
+    run_test "node/jit_abort (abort from JIT + perf map)"          signal    node --perf-basic-prof "$SCRIPT_DIR/node/jit_abort.js"
 }
 
 run_python() {
@@ -125,10 +164,39 @@ run_python() {
     run_test "python/stack_overflow (RecursionError - no signal)"      no_signal python3 "$SCRIPT_DIR/python/stack_overflow.py"
     run_test "python/segfault_ctypes (SIGSEGV via ctypes)"             signal    python3 "$SCRIPT_DIR/python/segfault_ctypes.py"
     run_test "python/faulthandler_crash (SIGSEGV + Python traceback)"  signal    python3 "$SCRIPT_DIR/python/faulthandler_crash.py"
+    run_test "python/faulthandler_file (SIGSEGV + faulthandler.log)"   signal    python3 "$SCRIPT_DIR/python/faulthandler_file.py"
     run_test "python/abort_signal (SIGABRT via os.abort)"              signal    python3 "$SCRIPT_DIR/python/abort_signal.py"
     run_test "python/kill_self (SIGSEGV via os.kill)"                  signal    python3 "$SCRIPT_DIR/python/kill_self.py"
 }
 
+run_go() {
+    echo ""
+    echo -e "${BOLD}╔══════════════════════════════════════════════════════════════╗${RESET}"
+    echo -e "${BOLD}║  GO TESTS                                                  ║${RESET}"
+    echo -e "${BOLD}║  The Go runtime turns SIGSEGV into a panic and exits 2;     ║${RESET}"
+    echo -e "${BOLD}║  the signal is recorded at exit.                            ║${RESET}"
+    echo -e "${BOLD}║  Binaries are stripped; frames come from .gopclntab.        ║${RESET}"
+    echo -e "${BOLD}╚══════════════════════════════════════════════════════════════╝${RESET}"
+
+    build_go
+
+    run_test "go/nil_deref (SIGSEGV - runtime panic, exit 2)"     signal    "$GO_BUILD_DIR/nil_deref"
+    run_test "go/panic (index out of range - no signal)"          exit      "$GO_BUILD_DIR/panic"
+}
+
+run_rust() {
+    echo ""
+    echo -e "${BOLD}╔══════════════════════════════════════════════════════════════╗${RESET}"
+    echo -e "${BOLD}║  RUST TESTS                                                ║${RESET}"
+    echo -e "${BOLD}║  panic = \"abort\" turns a panic into SIGABRT. The report     ║${RESET}"
+    echo -e "${BOLD}║  should have a Rust Panic section with the location.        ║${RESET}"
+    echo -e "${BOLD}╚══════════════════════════════════════════════════════════════╝${RESET}"
+
+    build_rust
+
+    run_test "rust/panic_abort (index out of bounds - SIGABRT)"   signal    "$RUST_BUILD_DIR/panic_abort"
+}
+
 # --- Main ---
 
 echo -e "${BOLD}crash-tracer runtime test suite${RESET}"
@@ -141,27 +209,38 @@ case "$filter" in
     native)  run_native ;;
     node)    run_node ;;
     python)  run_python ;;
+    go)      run_go ;;
+    rust)    run_rust ;;
     all)
         run_native
         run_node
         run_python
+        run_go
+        run_rust
         echo ""
         echo -e "${BOLD}━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━${RESET}"
         echo -e "${BOLD}ALL TESTS COMPLETE${RESET}"
         echo ""
         echo "Summary of what crash-tracer should have captured:"
-        echo "  Native:  segfault, abort, divzero, illegal_instruction, stack_overflow, bus_error"
+        echo "  Native:  segfault, abort, divzero, illegal_instruction, stack_overflow, bus_error,"
+        echo "           dlopen_segfault, with its top frame and a Memory Maps entry in libm.so.6"
         echo "           (use_after_free is non-deterministic)"
-        echo "  Node.js: abort, segfault_native, fatal_error, diagnostic_report"
+        echo "  Node.js: abort, segfault_native, fatal_error, diagnostic_report, jit_abort"
         echo "           (NOT: uncaught_exception, unhandled_rejection, stack_overflow)"
-        echo "  Python:  segfault_ctypes, faulthandler_crash, abort_signal, kill_self"
+        echo "  Python:  segfault_ctypes, faulthandler_crash, faulthandler_file, abort_signal,"
+        echo "           kill_self"
         echo "           (NOT: unhandled_exception, stack_overflow)"
+        echo "  Go:      nil_deref, with the Go Panic section and Go build info"
+        echo "           panic, as an exit crash with the Go Panic section"
+        echo "  Rust:    panic_abort, with the Rust Panic section"
         echo ""
-        echo "Check /tmp/crash-tracer/ for generated crash reports."
+        echo "Check /tmp/crash-tracer/ for generated crash reports. diagnostic_report and"
+        echo "faulthandler_file should list their report.*.json / faulthandler.log under"
+        echo "Runtime Artifacts."
         echo -e "${BOLD}━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━${RESET}"
         ;;
     *)
-        echo "Usage: $0 [native|node|python|all]"
+        echo "Usage: $0 [native|node|python|go|rust|all]"
         exit 1
         ;;
 esac
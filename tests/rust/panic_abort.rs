@@ -0,0 +1,14 @@
+// Rust panic built with panic = "abort" - SIGABRT expected.
+// The panic hook prints the message to stderr before aborting, so the
+// message is only kept with --exit-commands panic_abort. Without it the
+// report still names the panic location, recovered from the stack dump.
+
+fn parse(items: &[u32], index: usize) -> u32 {
+    items[index]
+}
+
+fn main() {
+    eprintln!("[rust/panic_abort] Indexing out of bounds...");
+    let items = vec![1, 2, 3];
+    println!("{}", parse(&items, std::hint::black_box(7)));
+}